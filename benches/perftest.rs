@@ -11,8 +11,8 @@ use std::borrow::Cow;
 use perftest_data::*;
 
 use test::{Bencher, black_box};
-use quick_protobuf::{BytesReader, Reader, Writer};
-use quick_protobuf::message::{MessageWrite};
+use quick_protobuf::{BytesReader, Reader, ReaderLimits, Writer, serialize_into_slice};
+use quick_protobuf::message::{MessageRead, MessageWrite};
 
 #[bench]
 fn read_file(b: &mut Bencher) {
@@ -24,6 +24,9 @@ fn read_file(b: &mut Bencher) {
 
 macro_rules! perfbench {
     ($gen: ident, $m:ident, $write:ident, $read:ident) => {
+        perfbench!($gen, $m, $write, $read, ReaderLimits::default());
+    };
+    ($gen: ident, $m:ident, $write:ident, $read:ident, $limits:expr) => {
 #[bench]
 fn $write(b: &mut Bencher) {
     let v = $gen();
@@ -47,7 +50,7 @@ fn $read(b: &mut Bencher) {
         }
     }
     b.iter(|| {
-        let mut r = BytesReader::from_bytes(&buf);
+        let mut r = BytesReader::with_limits(&buf, $limits).unwrap();
         while !r.is_eof() {
             let _ = black_box($m::from_reader(&mut r, &buf).unwrap());
         }
@@ -176,3 +179,53 @@ fn generate_all() -> Vec<PerftestData<'static>> {
 }
 
 perfbench!(generate_all, PerftestData, write_all, read_all);
+
+fn generate_deep_messages() -> Vec<TestOptionalMessages> {
+    let mut message = TestOptionalMessages::default();
+    for _ in 0..200 {
+        message = TestOptionalMessages {
+            message1: Some(Box::new(message)),
+            message2: None,
+            message3: None,
+        };
+    }
+    vec![message]
+}
+
+// deeper than the default `ReaderLimits::max_depth`
+perfbench!(generate_deep_messages, TestOptionalMessages, write_deep_messages, read_deep_messages,
+           ReaderLimits { max_depth: 256, ..ReaderLimits::default() });
+
+// sizing the nested messages written by `write_deep_messages`, before and after their sizes were
+// cached: `Writer::write_message` used to call `get_size` on every nested message
+
+#[bench]
+fn size_deep_messages_uncached(b: &mut Bencher) {
+    let v = generate_deep_messages();
+    b.iter(|| {
+        let mut m = Some(&v[0]);
+        while let Some(n) = m {
+            black_box(n.get_size());
+            m = n.message1.as_ref().map(|n| &**n);
+        }
+    })
+}
+
+#[bench]
+fn size_deep_messages_cached(b: &mut Bencher) {
+    let v = generate_deep_messages();
+    let mut sizes = Vec::new();
+    b.iter(|| {
+        sizes.clear();
+        black_box(v[0].get_size_cached(&mut sizes));
+    })
+}
+
+#[bench]
+fn write_all_bytes_writer(b: &mut Bencher) {
+    let v = generate_all();
+    let mut buf = vec![0; v[0].get_size()];
+    b.iter(|| {
+        serialize_into_slice(&v[0], black_box(&mut buf)).unwrap()
+    })
+}
@@ -2,10 +2,10 @@
 
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
 
-use std::io::{Write};
 use std::borrow::Cow;
-use quick_protobuf::{MessageWrite, BytesReader, Writer, Result};
+use quick_protobuf::{MessageRead, MessageWrite, BytesReader, Writer, WriterBackend, Result};
 use quick_protobuf::sizeofs::*;
 
 #[derive(Debug, Default, PartialEq, Clone)]
@@ -13,14 +13,14 @@ pub struct Test1 {
     pub value: Option<i32>,
 }
 
-impl Test1 {
-    pub fn from_reader(r: &mut BytesReader, bytes: &[u8]) -> Result<Self> {
+impl<'a> MessageRead<'a> for Test1 {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
         let mut msg = Self::default();
         while !r.is_eof() {
             match r.next_tag(bytes) {
-                Ok(8) => msg.value = Some(r.read_int32(bytes)?),
-                Ok(t) => { r.read_unknown(bytes, t)?; }
-                Err(e) => return Err(e),
+                Ok(8) => msg.value = Some(r.read_int32(bytes).map_err(|e| e.in_field("Test1", "value"))?),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("Test1"))?; }
+                Err(e) => return Err(e.in_message("Test1")),
             }
         }
         Ok(msg)
@@ -32,7 +32,7 @@ impl MessageWrite for Test1 {
         self.value.as_ref().map_or(0, |m| 1 + sizeof_int32(*m))
     }
 
-    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
         if let Some(ref s) = self.value { r.write_int32_with_tag(8, *s)?; }
         Ok(())
     }
@@ -43,14 +43,14 @@ pub struct TestRepeatedBool {
     pub values: Vec<bool>,
 }
 
-impl TestRepeatedBool {
-    pub fn from_reader(r: &mut BytesReader, bytes: &[u8]) -> Result<Self> {
+impl<'a> MessageRead<'a> for TestRepeatedBool {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
         let mut msg = Self::default();
         while !r.is_eof() {
             match r.next_tag(bytes) {
-                Ok(8) => msg.values.push(r.read_bool(bytes)?),
-                Ok(t) => { r.read_unknown(bytes, t)?; }
-                Err(e) => return Err(e),
+                Ok(8) => msg.values.push(r.read_bool(bytes).map_err(|e| e.in_repeated_field("TestRepeatedBool", "values", msg.values.len()))?),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("TestRepeatedBool"))?; }
+                Err(e) => return Err(e.in_message("TestRepeatedBool")),
             }
         }
         Ok(msg)
@@ -62,7 +62,7 @@ impl MessageWrite for TestRepeatedBool {
         self.values.iter().map(|s| 1 + sizeof_bool(*s)).sum::<usize>()
     }
 
-    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
         for s in &self.values { r.write_bool_with_tag(8, *s)? }
         Ok(())
     }
@@ -73,14 +73,14 @@ pub struct TestRepeatedPackedInt32 {
     pub values: Vec<i32>,
 }
 
-impl TestRepeatedPackedInt32 {
-    pub fn from_reader(r: &mut BytesReader, bytes: &[u8]) -> Result<Self> {
+impl<'a> MessageRead<'a> for TestRepeatedPackedInt32 {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
         let mut msg = Self::default();
         while !r.is_eof() {
             match r.next_tag(bytes) {
-                Ok(10) => msg.values = r.read_packed(bytes, |r, bytes| r.read_int32(bytes))?,
-                Ok(t) => { r.read_unknown(bytes, t)?; }
-                Err(e) => return Err(e),
+                Ok(10) => msg.values = r.read_packed(bytes, |r, bytes| r.read_int32(bytes)).map_err(|e| e.in_field("TestRepeatedPackedInt32", "values"))?,
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("TestRepeatedPackedInt32"))?; }
+                Err(e) => return Err(e.in_message("TestRepeatedPackedInt32")),
             }
         }
         Ok(msg)
@@ -92,7 +92,7 @@ impl MessageWrite for TestRepeatedPackedInt32 {
         if self.values.is_empty() { 0 } else { 1 + sizeof_var_length(self.values.iter().map(|s| sizeof_int32(*s)).sum::<usize>()) }
     }
 
-    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
         r.write_packed_repeated_field_with_tag(10, &self.values, |r, m| r.write_int32(*m), &|m| sizeof_int32(*m))?;
         Ok(())
     }
@@ -105,16 +105,16 @@ pub struct TestRepeatedMessages {
     pub messages3: Vec<TestRepeatedMessages>,
 }
 
-impl TestRepeatedMessages {
-    pub fn from_reader(r: &mut BytesReader, bytes: &[u8]) -> Result<Self> {
+impl<'a> MessageRead<'a> for TestRepeatedMessages {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
         let mut msg = Self::default();
         while !r.is_eof() {
             match r.next_tag(bytes) {
-                Ok(10) => msg.messages1.push(r.read_message(bytes, TestRepeatedMessages::from_reader)?),
-                Ok(18) => msg.messages2.push(r.read_message(bytes, TestRepeatedMessages::from_reader)?),
-                Ok(26) => msg.messages3.push(r.read_message(bytes, TestRepeatedMessages::from_reader)?),
-                Ok(t) => { r.read_unknown(bytes, t)?; }
-                Err(e) => return Err(e),
+                Ok(10) => msg.messages1.push(r.read_message(bytes, TestRepeatedMessages::from_reader).map_err(|e| e.in_repeated_field("TestRepeatedMessages", "messages1", msg.messages1.len()))?),
+                Ok(18) => msg.messages2.push(r.read_message(bytes, TestRepeatedMessages::from_reader).map_err(|e| e.in_repeated_field("TestRepeatedMessages", "messages2", msg.messages2.len()))?),
+                Ok(26) => msg.messages3.push(r.read_message(bytes, TestRepeatedMessages::from_reader).map_err(|e| e.in_repeated_field("TestRepeatedMessages", "messages3", msg.messages3.len()))?),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("TestRepeatedMessages"))?; }
+                Err(e) => return Err(e.in_message("TestRepeatedMessages")),
             }
         }
         Ok(msg)
@@ -128,7 +128,13 @@ impl MessageWrite for TestRepeatedMessages {
         + self.messages3.iter().map(|s| 1 + sizeof_var_length(s.get_size())).sum::<usize>()
     }
 
-    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        self.messages1.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+        + self.messages2.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+        + self.messages3.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
         for s in &self.messages1 { r.write_message_with_tag(10, s)? }
         for s in &self.messages2 { r.write_message_with_tag(18, s)? }
         for s in &self.messages3 { r.write_message_with_tag(26, s)? }
@@ -143,16 +149,16 @@ pub struct TestOptionalMessages {
     pub message3: Option<Box<TestOptionalMessages>>,
 }
 
-impl TestOptionalMessages {
-    pub fn from_reader(r: &mut BytesReader, bytes: &[u8]) -> Result<Self> {
+impl<'a> MessageRead<'a> for TestOptionalMessages {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
         let mut msg = Self::default();
         while !r.is_eof() {
             match r.next_tag(bytes) {
-                Ok(10) => msg.message1 = Some(Box::new(r.read_message(bytes, TestOptionalMessages::from_reader)?)),
-                Ok(18) => msg.message2 = Some(Box::new(r.read_message(bytes, TestOptionalMessages::from_reader)?)),
-                Ok(26) => msg.message3 = Some(Box::new(r.read_message(bytes, TestOptionalMessages::from_reader)?)),
-                Ok(t) => { r.read_unknown(bytes, t)?; }
-                Err(e) => return Err(e),
+                Ok(10) => msg.message1 = Some(Box::new(r.read_message(bytes, TestOptionalMessages::from_reader).map_err(|e| e.in_field("TestOptionalMessages", "message1"))?)),
+                Ok(18) => msg.message2 = Some(Box::new(r.read_message(bytes, TestOptionalMessages::from_reader).map_err(|e| e.in_field("TestOptionalMessages", "message2"))?)),
+                Ok(26) => msg.message3 = Some(Box::new(r.read_message(bytes, TestOptionalMessages::from_reader).map_err(|e| e.in_field("TestOptionalMessages", "message3"))?)),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("TestOptionalMessages"))?; }
+                Err(e) => return Err(e.in_message("TestOptionalMessages")),
             }
         }
         Ok(msg)
@@ -166,7 +172,13 @@ impl MessageWrite for TestOptionalMessages {
         + self.message3.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.get_size()))
     }
 
-    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        self.message1.as_ref().map_or(0, |m| 1 + sizeof_message_cached(&**m, sizes))
+        + self.message2.as_ref().map_or(0, |m| 1 + sizeof_message_cached(&**m, sizes))
+        + self.message3.as_ref().map_or(0, |m| 1 + sizeof_message_cached(&**m, sizes))
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
         if let Some(ref s) = self.message1 { r.write_message_with_tag(10, &**s)?; }
         if let Some(ref s) = self.message2 { r.write_message_with_tag(18, &**s)?; }
         if let Some(ref s) = self.message3 { r.write_message_with_tag(26, &**s)?; }
@@ -181,16 +193,16 @@ pub struct TestStrings<'a> {
     pub s3: Option<Cow<'a, str>>,
 }
 
-impl<'a> TestStrings<'a> {
-    pub fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+impl<'a> MessageRead<'a> for TestStrings<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
         let mut msg = Self::default();
         while !r.is_eof() {
             match r.next_tag(bytes) {
-                Ok(10) => msg.s1 = Some(Cow::Borrowed(r.read_string(bytes)?)),
-                Ok(18) => msg.s2 = Some(Cow::Borrowed(r.read_string(bytes)?)),
-                Ok(26) => msg.s3 = Some(Cow::Borrowed(r.read_string(bytes)?)),
-                Ok(t) => { r.read_unknown(bytes, t)?; }
-                Err(e) => return Err(e),
+                Ok(10) => msg.s1 = Some(Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_field("TestStrings", "s1"))?)),
+                Ok(18) => msg.s2 = Some(Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_field("TestStrings", "s2"))?)),
+                Ok(26) => msg.s3 = Some(Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_field("TestStrings", "s3"))?)),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("TestStrings"))?; }
+                Err(e) => return Err(e.in_message("TestStrings")),
             }
         }
         Ok(msg)
@@ -204,7 +216,7 @@ impl<'a> MessageWrite for TestStrings<'a> {
         + self.s3.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
     }
 
-    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
         if let Some(ref s) = self.s1 { r.write_string_with_tag(10, s)?; }
         if let Some(ref s) = self.s2 { r.write_string_with_tag(18, s)?; }
         if let Some(ref s) = self.s3 { r.write_string_with_tag(26, s)?; }
@@ -217,14 +229,14 @@ pub struct TestBytes<'a> {
     pub b1: Option<Cow<'a, [u8]>>,
 }
 
-impl<'a> TestBytes<'a> {
-    pub fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+impl<'a> MessageRead<'a> for TestBytes<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
         let mut msg = Self::default();
         while !r.is_eof() {
             match r.next_tag(bytes) {
-                Ok(10) => msg.b1 = Some(Cow::Borrowed(r.read_bytes(bytes)?)),
-                Ok(t) => { r.read_unknown(bytes, t)?; }
-                Err(e) => return Err(e),
+                Ok(10) => msg.b1 = Some(Cow::Borrowed(r.read_bytes(bytes).map_err(|e| e.in_field("TestBytes", "b1"))?)),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("TestBytes"))?; }
+                Err(e) => return Err(e.in_message("TestBytes")),
             }
         }
         Ok(msg)
@@ -236,7 +248,7 @@ impl<'a> MessageWrite for TestBytes<'a> {
         self.b1.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
     }
 
-    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
         if let Some(ref s) = self.b1 { r.write_bytes_with_tag(10, s)?; }
         Ok(())
     }
@@ -254,21 +266,21 @@ pub struct PerftestData<'a> {
     pub test_large_bytearrays: Vec<TestBytes<'a>>,
 }
 
-impl<'a> PerftestData<'a> {
-    pub fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+impl<'a> MessageRead<'a> for PerftestData<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
         let mut msg = Self::default();
         while !r.is_eof() {
             match r.next_tag(bytes) {
-                Ok(10) => msg.test1.push(r.read_message(bytes, Test1::from_reader)?),
-                Ok(18) => msg.test_repeated_bool.push(r.read_message(bytes, TestRepeatedBool::from_reader)?),
-                Ok(26) => msg.test_repeated_messages.push(r.read_message(bytes, TestRepeatedMessages::from_reader)?),
-                Ok(34) => msg.test_optional_messages.push(r.read_message(bytes, TestOptionalMessages::from_reader)?),
-                Ok(42) => msg.test_strings.push(r.read_message(bytes, TestStrings::from_reader)?),
-                Ok(50) => msg.test_repeated_packed_int32.push(r.read_message(bytes, TestRepeatedPackedInt32::from_reader)?),
-                Ok(58) => msg.test_small_bytearrays.push(r.read_message(bytes, TestBytes::from_reader)?),
-                Ok(66) => msg.test_large_bytearrays.push(r.read_message(bytes, TestBytes::from_reader)?),
-                Ok(t) => { r.read_unknown(bytes, t)?; }
-                Err(e) => return Err(e),
+                Ok(10) => msg.test1.push(r.read_message(bytes, Test1::from_reader).map_err(|e| e.in_repeated_field("PerftestData", "test1", msg.test1.len()))?),
+                Ok(18) => msg.test_repeated_bool.push(r.read_message(bytes, TestRepeatedBool::from_reader).map_err(|e| e.in_repeated_field("PerftestData", "test_repeated_bool", msg.test_repeated_bool.len()))?),
+                Ok(26) => msg.test_repeated_messages.push(r.read_message(bytes, TestRepeatedMessages::from_reader).map_err(|e| e.in_repeated_field("PerftestData", "test_repeated_messages", msg.test_repeated_messages.len()))?),
+                Ok(34) => msg.test_optional_messages.push(r.read_message(bytes, TestOptionalMessages::from_reader).map_err(|e| e.in_repeated_field("PerftestData", "test_optional_messages", msg.test_optional_messages.len()))?),
+                Ok(42) => msg.test_strings.push(r.read_message(bytes, TestStrings::from_reader).map_err(|e| e.in_repeated_field("PerftestData", "test_strings", msg.test_strings.len()))?),
+                Ok(50) => msg.test_repeated_packed_int32.push(r.read_message(bytes, TestRepeatedPackedInt32::from_reader).map_err(|e| e.in_repeated_field("PerftestData", "test_repeated_packed_int32", msg.test_repeated_packed_int32.len()))?),
+                Ok(58) => msg.test_small_bytearrays.push(r.read_message(bytes, TestBytes::from_reader).map_err(|e| e.in_repeated_field("PerftestData", "test_small_bytearrays", msg.test_small_bytearrays.len()))?),
+                Ok(66) => msg.test_large_bytearrays.push(r.read_message(bytes, TestBytes::from_reader).map_err(|e| e.in_repeated_field("PerftestData", "test_large_bytearrays", msg.test_large_bytearrays.len()))?),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("PerftestData"))?; }
+                Err(e) => return Err(e.in_message("PerftestData")),
             }
         }
         Ok(msg)
@@ -287,7 +299,18 @@ impl<'a> MessageWrite for PerftestData<'a> {
         + self.test_large_bytearrays.iter().map(|s| 1 + sizeof_var_length(s.get_size())).sum::<usize>()
     }
 
-    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        self.test1.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+        + self.test_repeated_bool.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+        + self.test_repeated_messages.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+        + self.test_optional_messages.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+        + self.test_strings.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+        + self.test_repeated_packed_int32.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+        + self.test_small_bytearrays.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+        + self.test_large_bytearrays.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
         for s in &self.test1 { r.write_message_with_tag(10, s)? }
         for s in &self.test_repeated_bool { r.write_message_with_tag(18, s)? }
         for s in &self.test_repeated_messages { r.write_message_with_tag(26, s)? }
@@ -3,9 +3,8 @@
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
 
-use std::io::{Write};
 use std::borrow::Cow;
-use quick_protobuf::{MessageWrite, BytesReader, Writer, Result};
+use quick_protobuf::{MessageWrite, BytesReader, Writer, WriterBackend, Result};
 use quick_protobuf::sizeofs::*;
 
 #[derive(Debug, Default, PartialEq, Clone)]
@@ -32,7 +31,7 @@ impl MessageWrite for Test1 {
         self.value.as_ref().map_or(0, |m| 1 + sizeof_int32(*m))
     }
 
-    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
         if let Some(ref s) = self.value { r.write_int32_with_tag(8, *s)?; }
         Ok(())
     }
@@ -62,7 +61,7 @@ impl MessageWrite for TestRepeatedBool {
         self.values.iter().map(|s| 1 + sizeof_bool(*s)).sum::<usize>()
     }
 
-    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
         for s in &self.values { r.write_bool_with_tag(8, *s)? }
         Ok(())
     }
@@ -92,7 +91,7 @@ impl MessageWrite for TestRepeatedPackedInt32 {
         if self.values.is_empty() { 0 } else { 1 + sizeof_var_length(self.values.iter().map(|s| sizeof_int32(*s)).sum::<usize>()) }
     }
 
-    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
         r.write_packed_repeated_field_with_tag(10, &self.values, |r, m| r.write_int32(*m), &|m| sizeof_int32(*m))?;
         Ok(())
     }
@@ -128,7 +127,7 @@ impl MessageWrite for TestRepeatedMessages {
         + self.messages3.iter().map(|s| 1 + sizeof_var_length(s.get_size())).sum::<usize>()
     }
 
-    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
         for s in &self.messages1 { r.write_message_with_tag(10, s)? }
         for s in &self.messages2 { r.write_message_with_tag(18, s)? }
         for s in &self.messages3 { r.write_message_with_tag(26, s)? }
@@ -166,7 +165,7 @@ impl MessageWrite for TestOptionalMessages {
         + self.message3.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.get_size()))
     }
 
-    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
         if let Some(ref s) = self.message1 { r.write_message_with_tag(10, &**s)?; }
         if let Some(ref s) = self.message2 { r.write_message_with_tag(18, &**s)?; }
         if let Some(ref s) = self.message3 { r.write_message_with_tag(26, &**s)?; }
@@ -204,7 +203,7 @@ impl<'a> MessageWrite for TestStrings<'a> {
         + self.s3.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
     }
 
-    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
         if let Some(ref s) = self.s1 { r.write_string_with_tag(10, s)?; }
         if let Some(ref s) = self.s2 { r.write_string_with_tag(18, s)?; }
         if let Some(ref s) = self.s3 { r.write_string_with_tag(26, s)?; }
@@ -236,7 +235,7 @@ impl<'a> MessageWrite for TestBytes<'a> {
         self.b1.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
     }
 
-    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
         if let Some(ref s) = self.b1 { r.write_bytes_with_tag(10, s)?; }
         Ok(())
     }
@@ -287,7 +286,7 @@ impl<'a> MessageWrite for PerftestData<'a> {
         + self.test_large_bytearrays.iter().map(|s| 1 + sizeof_var_length(s.get_size())).sum::<usize>()
     }
 
-    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
         for s in &self.test1 { r.write_message_with_tag(10, s)? }
         for s in &self.test_repeated_bool { r.write_message_with_tag(18, s)? }
         for s in &self.test_repeated_messages { r.write_message_with_tag(26, s)? }
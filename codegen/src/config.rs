@@ -0,0 +1,144 @@
+//! A module to generate rust modules from .proto files, typically from a build script
+
+use std::env;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use errors::Result;
+use imports;
+
+/// The module declaring all the modules generated in an output directory
+const MOD_FILE: &str = "mod.rs";
+
+/// Generation options
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// pb_rs::Config::new()
+///     .include("protos")
+///     .text(true)
+///     .compile(&["protos/a.proto", "protos/b.proto"])
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    include_paths: Vec<PathBuf>,
+    out_dir: Option<PathBuf>,
+    rerun_if_changed: bool,
+    keep_unknown_fields: bool,
+    json: bool,
+    text: bool,
+    no_std: bool,
+}
+
+impl Config {
+
+    /// Creates a configuration suited to build scripts
+    ///
+    /// If the `OUT_DIR` environment variable is set, as it is by cargo when running a build
+    /// script, modules are generated into it and a `cargo:rerun-if-changed` line is printed for
+    /// every .proto file. Use `Config::default()` to ignore the environment.
+    pub fn new() -> Config {
+        let out_dir = env::var_os("OUT_DIR").map(PathBuf::from);
+        Config {
+            rerun_if_changed: out_dir.is_some(),
+            out_dir,
+            ..Config::default()
+        }
+    }
+
+    /// Adds a directory where imported files are searched
+    ///
+    /// Include paths are searched in order, then the directory of the compiled .proto file.
+    pub fn include<P: AsRef<Path>>(&mut self, path: P) -> &mut Config {
+        self.include_paths.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets the directory where the modules are generated, along with a `mod.rs` declaring them
+    ///
+    /// Without output directory, each module is generated next to its .proto file.
+    pub fn out_dir<P: AsRef<Path>>(&mut self, path: P) -> &mut Config {
+        self.out_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Prints `cargo:rerun-if-changed` lines for every compiled or imported .proto file
+    pub fn rerun_if_changed(&mut self, rerun_if_changed: bool) -> &mut Config {
+        self.rerun_if_changed = rerun_if_changed;
+        self
+    }
+
+    /// Makes every generated message keep the fields it doesn't know and write them back
+    pub fn keep_unknown_fields(&mut self, keep_unknown_fields: bool) -> &mut Config {
+        self.keep_unknown_fields = keep_unknown_fields;
+        self
+    }
+
+    /// Implements `JsonWrite` and `JsonRead` for every generated message and enum
+    pub fn json(&mut self, json: bool) -> &mut Config {
+        self.json = json;
+        self
+    }
+
+    /// Implements `TextWrite` and `TextRead` for every generated message and enum
+    pub fn text(&mut self, text: bool) -> &mut Config {
+        self.text = text;
+        self
+    }
+
+    /// Generates modules depending on `core` and `alloc` only, for `no_std` crates
+    ///
+    /// The crate must declare `extern crate alloc;` and maps are generated as `BTreeMap`
+    /// instead of `HashMap`. quick-protobuf itself must be built without its `std` feature.
+    pub fn no_std(&mut self, no_std: bool) -> &mut Config {
+        self.no_std = no_std;
+        self
+    }
+
+    /// Generates the modules of `protos` and of all the files they import
+    pub fn compile<P: AsRef<Path>>(&self, protos: &[P]) -> Result<()> {
+        let mut generated: Vec<(String, PathBuf)> = Vec::new();
+        for proto in protos {
+            let files = imports::load(proto.as_ref(), &self.include_paths)?;
+            for (file, mut f) in files.iter().zip(imports::resolve(&files)?) {
+                if self.rerun_if_changed {
+                    println!("cargo:rerun-if-changed={}", file.path.display());
+                }
+                let out_file = match self.out_dir {
+                    Some(ref dir) => dir.join(file.module()).with_extension("rs"),
+                    None => file.out_file(),
+                };
+                if generated.iter().any(|g| g.1 == out_file) {
+                    continue;
+                }
+
+                if self.keep_unknown_fields {
+                    f.keep_unknown_fields();
+                }
+                f.json = self.json;
+                f.text = self.text;
+                f.no_std = self.no_std;
+
+                let name = file.path.file_name().and_then(|e| e.to_str()).unwrap();
+                let mut w = BufWriter::new(File::create(&out_file)?);
+                f.write(&mut w, name)?;
+                generated.push((file.module().to_string(), out_file));
+            }
+        }
+
+        if let Some(ref dir) = self.out_dir {
+            let mut w = BufWriter::new(File::create(dir.join(MOD_FILE))?);
+            writeln!(w, "// Automatically generated by pb-rs, declaring the modules generated in this directory")?;
+            writeln!(w, "// include!(concat!(env!(\"OUT_DIR\"), \"/{}\"));", MOD_FILE)?;
+            for (module, path) in generated {
+                writeln!(w)?;
+                writeln!(w, "#[path = {:?}]", path.display().to_string())?;
+                writeln!(w, "pub mod {};", module)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,619 @@
+//! A module to handle messages described by parsed .proto files instead of generated code
+//!
+//! A `DynamicMessage` holds the values of the fields of a message, by field number, along with
+//! the resolved `FileDescriptor` describing it. It is decoded from a `BytesReader`, read and
+//! modified by field name, and encoded back through a `Writer` like generated messages are
+//! (`MessageWrite`). Fields unknown to the descriptor are kept and written back verbatim.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::slice;
+
+use quick_protobuf::{self, BytesReader, MessageWrite, UnknownFields, Writer, WriterBackend};
+use quick_protobuf::sizeofs::*;
+
+use errors::{Result, ErrorKind};
+use types::{Enumerator, Field, FileDescriptor, Message};
+
+/// The value of a field
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<'a> {
+    /// `int32`, `sint32` or `sfixed32`
+    I32(i32),
+    /// `int64`, `sint64` or `sfixed64`
+    I64(i64),
+    /// `uint32` or `fixed32`
+    U32(u32),
+    /// `uint64` or `fixed64`
+    U64(u64),
+    /// `float`
+    F32(f32),
+    /// `double`
+    F64(f64),
+    /// `bool`
+    Bool(bool),
+    /// `string`
+    String(String),
+    /// `bytes`
+    Bytes(Vec<u8>),
+    /// An enum value, possibly unknown to the enum
+    Enum(i32),
+    /// A nested message or group
+    Message(DynamicMessage<'a>),
+    /// A map entry: its key and value
+    Entry(Box<(Value<'a>, Value<'a>)>),
+}
+
+/// What a field holds, depending on its type
+#[derive(Debug, Clone, Copy)]
+pub enum Kind<'a> {
+    /// Numbers, `bool`, `string` or `bytes`
+    Scalar,
+    /// An enum
+    Enum(&'a Enumerator<'a>),
+    /// A nested message
+    Message(&'a Message<'a>),
+    /// A group (deprecated proto2 feature)
+    Group(&'a Message<'a>),
+    /// A map, with its key and value fields
+    Map(&'a Field<'a>, &'a Field<'a>),
+}
+
+/// A message whose structure is only known at runtime
+#[derive(Clone)]
+pub struct DynamicMessage<'a> {
+    file: &'a FileDescriptor<'a>,
+    descriptor: &'a Message<'a>,
+    fields: Vec<(i32, Vec<Value<'a>>)>,
+    unknown_fields: UnknownFields<'static>,
+}
+
+impl<'a> DynamicMessage<'a> {
+
+    /// Creates an empty message
+    ///
+    /// `name` is either the fully qualified name of the message or its name relative to the
+    /// package of `file`, which must be resolved
+    pub fn new(file: &'a FileDescriptor<'a>, name: &str) -> Result<DynamicMessage<'a>> {
+        let relative = format!("{}.{}", file.package, name);
+        file.messages.iter()
+            .find(|m| m.fqn() == name || m.fqn() == relative)
+            .map(|descriptor| DynamicMessage {
+                file,
+                descriptor,
+                fields: Vec::new(),
+                unknown_fields: UnknownFields::default(),
+            })
+            .ok_or_else(|| ErrorKind::UnknownMessage(name.to_string()).into())
+    }
+
+    /// Reads the message `name` from the current position of `r` up to its end
+    pub fn from_reader(file: &'a FileDescriptor<'a>,
+                       name: &str,
+                       r: &mut BytesReader,
+                       bytes: &[u8]) -> Result<DynamicMessage<'a>> {
+        let mut msg = DynamicMessage::new(file, name)?;
+        msg.read(r, bytes)?;
+        Ok(msg)
+    }
+
+    /// Gets the file describing the message
+    pub fn file(&self) -> &'a FileDescriptor<'a> {
+        self.file
+    }
+
+    /// Gets the definition of the message
+    pub fn descriptor(&self) -> &'a Message<'a> {
+        self.descriptor
+    }
+
+    /// Finds a field of the message by name, oneof fields included
+    pub fn field(&self, name: &str) -> Option<&'a Field<'a>> {
+        self.all_fields().find(|f| f.name == name)
+    }
+
+    fn field_by_number(&self, number: i32) -> Option<&'a Field<'a>> {
+        self.all_fields().find(|f| f.number == number)
+    }
+
+    fn all_fields(&self) -> impl Iterator<Item = &'a Field<'a>> {
+        let descriptor = self.descriptor;
+        descriptor.fields.iter().chain(descriptor.oneofs.iter().flat_map(|o| o.fields.iter()))
+    }
+
+    /// Gets what a field of the message holds
+    pub fn kind(&self, f: &'a Field<'a>) -> Kind<'a> {
+        if let Some(ref m) = f.map {
+            return Kind::Map(&m.0, &m.1);
+        }
+        if let Some(m) = self.file.messages.iter().find(|m| m.fqn() == f.typ) {
+            return if f.is_group { Kind::Group(m) } else { Kind::Message(m) };
+        }
+        match self.file.enums.iter().find(|e| e.fqn() == f.typ) {
+            Some(e) => Kind::Enum(e),
+            None => Kind::Scalar,
+        }
+    }
+
+    /// Gets the value of a field when it is not set
+    pub fn default_value(&self, f: &'a Field<'a>) -> Value<'a> {
+        match self.kind(f) {
+            Kind::Enum(e) => Value::Enum(e.fields.first().map_or(0, |v| v.1)),
+            Kind::Message(m) | Kind::Group(m) => Value::Message(DynamicMessage {
+                file: self.file,
+                descriptor: m,
+                fields: Vec::new(),
+                unknown_fields: UnknownFields::default(),
+            }),
+            Kind::Map(key, value) => Value::Entry(Box::new((self.default_value(key), self.default_value(value)))),
+            Kind::Scalar => match &*f.typ {
+                "int32" | "sint32" | "sfixed32" => Value::I32(0),
+                "int64" | "sint64" | "sfixed64" => Value::I64(0),
+                "uint32" | "fixed32" => Value::U32(0),
+                "uint64" | "fixed64" => Value::U64(0),
+                "float" => Value::F32(0.),
+                "double" => Value::F64(0.),
+                "bool" => Value::Bool(false),
+                "string" => Value::String(String::new()),
+                _ => Value::Bytes(Vec::new()),
+            },
+        }
+    }
+
+    /// Gets the value of a field, the last one if it is set several times
+    pub fn get(&self, name: &str) -> Option<&Value<'a>> {
+        self.get_repeated(name).last()
+    }
+
+    /// Gets a mutable reference to the value of a field, the last one if it is set several times
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Value<'a>> {
+        let number = self.field(name)?.number;
+        self.fields.iter_mut()
+            .find(|&&mut (n, _)| n == number)
+            .and_then(|&mut (_, ref mut values)| values.last_mut())
+    }
+
+    /// Gets all the values of a field (the entries of a map field)
+    pub fn get_repeated(&self, name: &str) -> &[Value<'a>] {
+        let number = match self.field(name) {
+            Some(f) => f.number,
+            None => return &[],
+        };
+        self.fields.iter()
+            .find(|&&(n, _)| n == number)
+            .map_or(&[], |(_, values)| values)
+    }
+
+    /// Checks if a field is set
+    pub fn has(&self, name: &str) -> bool {
+        !self.get_repeated(name).is_empty()
+    }
+
+    /// Sets the value of a field, replacing its previous values
+    ///
+    /// Other fields of the same oneof, if any, are cleared.
+    pub fn set(&mut self, name: &str, v: Value<'a>) -> Result<()> {
+        let f = self.known_field(name)?;
+        self.check(f, &v)?;
+        self.fields.retain(|&(n, _)| n != f.number);
+        self.add(f, v);
+        Ok(())
+    }
+
+    /// Appends a value to a field (an entry to a map field)
+    ///
+    /// Non repeated fields set several times, as they may be on the wire, are read as their last
+    /// value.
+    pub fn push(&mut self, name: &str, v: Value<'a>) -> Result<()> {
+        let f = self.known_field(name)?;
+        self.check(f, &v)?;
+        self.add(f, v);
+        Ok(())
+    }
+
+    /// Removes all the values of a field
+    pub fn clear(&mut self, name: &str) -> Result<()> {
+        let number = self.known_field(name)?.number;
+        self.fields.retain(|&(n, _)| n != number);
+        Ok(())
+    }
+
+    /// Iterates over the fields which are set and their values, in the order of the definition
+    pub fn iter<'b>(&'b self) -> Iter<'b, 'a> {
+        Iter {
+            msg: self,
+            fields: self.fields.iter(),
+        }
+    }
+
+    /// Gets the fields read which are not in the message definition
+    pub fn unknown_fields(&self) -> &UnknownFields<'static> {
+        &self.unknown_fields
+    }
+
+    fn known_field(&self, name: &str) -> Result<&'a Field<'a>> {
+        self.field(name).ok_or_else(|| {
+            ErrorKind::UnknownField(name.to_string(), self.descriptor.fqn()).into()
+        })
+    }
+
+    /// Checks that `v` can be a value of `f`
+    fn check(&self, f: &'a Field<'a>, v: &Value<'a>) -> Result<()> {
+        let valid = match (self.kind(f), v) {
+            (Kind::Enum(_), &Value::Enum(_)) => true,
+            (Kind::Message(m), Value::Message(msg)) | (Kind::Group(m), Value::Message(msg)) => {
+                msg.descriptor.fqn() == m.fqn()
+            }
+            (Kind::Map(key, value), Value::Entry(entry)) => {
+                self.check(key, &entry.0).is_ok() && self.check(value, &entry.1).is_ok()
+            }
+            (Kind::Scalar, v) => matches!((&*f.typ, v),
+                ("int32", &Value::I32(_)) | ("sint32", &Value::I32(_)) | ("sfixed32", &Value::I32(_)) |
+                ("int64", &Value::I64(_)) | ("sint64", &Value::I64(_)) | ("sfixed64", &Value::I64(_)) |
+                ("uint32", &Value::U32(_)) | ("fixed32", &Value::U32(_)) |
+                ("uint64", &Value::U64(_)) | ("fixed64", &Value::U64(_)) |
+                ("float", &Value::F32(_)) | ("double", &Value::F64(_)) | ("bool", &Value::Bool(_)) |
+                ("string", &Value::String(_)) | ("bytes", &Value::Bytes(_))),
+            _ => false,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(ErrorKind::InvalidValue(format!("{:?}", v), f.name.clone()).into())
+        }
+    }
+
+    /// Appends `v` to the values of `f`, keeping the fields in the order of the definition
+    fn add(&mut self, f: &'a Field<'a>, v: Value<'a>) {
+        if let Some(o) = self.descriptor.oneofs.iter().find(|o| o.fields.iter().any(|g| g.number == f.number)) {
+            self.fields.retain(|&(n, _)| n == f.number || o.fields.iter().all(|g| g.number != n));
+        }
+        if let Some(&mut (_, ref mut values)) = self.fields.iter_mut().find(|&&mut (n, _)| n == f.number) {
+            values.push(v);
+            return;
+        }
+        let position = |n: i32| self.all_fields().position(|f| f.number == n);
+        let i = self.fields.iter().position(|&(n, _)| position(n) > position(f.number)).unwrap_or(self.fields.len());
+        self.fields.insert(i, (f.number, vec![v]));
+    }
+
+    /// Reads fields until the end of `r` or the end group tag of `group`
+    fn read(&mut self, r: &mut BytesReader, bytes: &[u8]) -> quick_protobuf::Result<()> {
+        while !r.is_eof() {
+            let tag = r.next_tag(bytes)?;
+            let number = (tag >> 3) as i32;
+            let wire = tag & 0x7;
+            if let Some(f) = self.field_by_number(number) {
+                let kind = self.kind(f);
+                let expected = wire_type(f, &kind);
+                if wire == 2 && expected != 2 && expected != 3 {
+                    // packed repeated field
+                    let data = r.read_bytes(bytes)?;
+                    let mut r = BytesReader::from_bytes(data);
+                    while !r.is_eof() {
+                        let v = self.read_value(f, &kind, &mut r, data, tag)?;
+                        self.add(f, v);
+                    }
+                    continue;
+                }
+                if wire == expected {
+                    let v = self.read_value(f, &kind, r, bytes, tag)?;
+                    self.add(f, v);
+                    continue;
+                }
+            }
+            let data = r.read_unknown_field(bytes, tag)?;
+            self.unknown_fields.push(tag, Cow::Owned(data.to_vec()));
+        }
+        Ok(())
+    }
+
+    /// Reads a value of `f`, its tag being already read
+    ///
+    /// Nested messages are read through `BytesReader::read_message` and `read_group`, so the
+    /// limits of the reader apply to them.
+    fn read_value(&self,
+                  f: &'a Field<'a>,
+                  kind: &Kind<'a>,
+                  r: &mut BytesReader,
+                  bytes: &[u8],
+                  tag: u32) -> quick_protobuf::Result<Value<'a>> {
+        Ok(match *kind {
+            Kind::Enum(_) => Value::Enum(r.read_int32(bytes)?),
+            Kind::Message(_) => {
+                let mut msg = self.default_value(f);
+                if let Value::Message(ref mut msg) = msg {
+                    r.read_message(bytes, |r, bytes| msg.read(r, bytes))?;
+                }
+                msg
+            }
+            Kind::Group(_) => {
+                let mut msg = self.default_value(f);
+                if let Value::Message(ref mut msg) = msg {
+                    r.read_group(bytes, tag, |r, bytes| msg.read(r, bytes))?;
+                }
+                msg
+            }
+            Kind::Map(key, value) => {
+                let entry = r.read_message(bytes, |r, bytes| {
+                    let (mut k, mut v) = (self.default_value(key), self.default_value(value));
+                    while !r.is_eof() {
+                        let tag = r.next_tag(bytes)?;
+                        match tag >> 3 {
+                            1 => k = self.read_value(key, &self.kind(key), r, bytes, tag)?,
+                            2 => v = self.read_value(value, &self.kind(value), r, bytes, tag)?,
+                            _ => r.read_unknown(bytes, tag)?,
+                        }
+                    }
+                    Ok((k, v))
+                })?;
+                Value::Entry(Box::new(entry))
+            }
+            Kind::Scalar => match &*f.typ {
+                "int32" => Value::I32(r.read_int32(bytes)?),
+                "sint32" => Value::I32(r.read_sint32(bytes)?),
+                "sfixed32" => Value::I32(r.read_sfixed32(bytes)?),
+                "int64" => Value::I64(r.read_int64(bytes)?),
+                "sint64" => Value::I64(r.read_sint64(bytes)?),
+                "sfixed64" => Value::I64(r.read_sfixed64(bytes)?),
+                "uint32" => Value::U32(r.read_uint32(bytes)?),
+                "fixed32" => Value::U32(r.read_fixed32(bytes)?),
+                "uint64" => Value::U64(r.read_uint64(bytes)?),
+                "fixed64" => Value::U64(r.read_fixed64(bytes)?),
+                "float" => Value::F32(r.read_float(bytes)?),
+                "double" => Value::F64(r.read_double(bytes)?),
+                "bool" => Value::Bool(r.read_bool(bytes)?),
+                "string" => Value::String(r.read_string(bytes)?.to_string()),
+                _ => Value::Bytes(r.read_bytes(bytes)?.to_vec()),
+            },
+        })
+    }
+
+    /// Checks if the values of `f` are written as a single length delimited field
+    fn is_packed(&self, f: &Field, kind: &Kind) -> bool {
+        f.packed == Some(true) && wire_type(f, kind) != 2 && wire_type(f, kind) != 3
+    }
+
+    /// Computes the binary size of a value of `f`, without its tag
+    fn value_size(&self, f: &'a Field<'a>, kind: &Kind<'a>, v: &Value<'a>) -> usize {
+        match *v {
+            Value::I32(v) => match &*f.typ {
+                "sint32" => sizeof_sint32(v),
+                "sfixed32" => 4,
+                _ => sizeof_int32(v),
+            },
+            Value::I64(v) => match &*f.typ {
+                "sint64" => sizeof_sint64(v),
+                "sfixed64" => 8,
+                _ => sizeof_int64(v),
+            },
+            Value::U32(v) => if f.typ == "fixed32" { 4 } else { sizeof_uint32(v) },
+            Value::U64(v) => if f.typ == "fixed64" { 8 } else { sizeof_uint64(v) },
+            Value::F32(_) => 4,
+            Value::F64(_) => 8,
+            Value::Bool(v) => sizeof_bool(v),
+            Value::String(ref v) => sizeof_var_length(v.len()),
+            Value::Bytes(ref v) => sizeof_var_length(v.len()),
+            Value::Enum(v) => sizeof_enum(v),
+            Value::Message(ref m) => match *kind {
+                Kind::Group(_) => m.get_size() + sizeof_varint(((f.number as u32) << 3 | 4) as u64),
+                _ => sizeof_var_length(m.get_size()),
+            },
+            Value::Entry(ref entry) => match *kind {
+                Kind::Map(key, value) => {
+                    sizeof_map_entry(1 + self.value_size(key, &self.kind(key), &entry.0),
+                                     1 + self.value_size(value, &self.kind(value), &entry.1))
+                }
+                _ => 0,
+            },
+        }
+    }
+
+    /// Writes a value of `f`, without its tag
+    fn write_value<W: WriterBackend>(&self,
+                             f: &'a Field<'a>,
+                             kind: &Kind<'a>,
+                             v: &Value<'a>,
+                             w: &mut Writer<W>) -> quick_protobuf::Result<()> {
+        match *v {
+            Value::I32(v) => match &*f.typ {
+                "sint32" => w.write_sint32(v),
+                "sfixed32" => w.write_sfixed32(v),
+                _ => w.write_int32(v),
+            },
+            Value::I64(v) => match &*f.typ {
+                "sint64" => w.write_sint64(v),
+                "sfixed64" => w.write_sfixed64(v),
+                _ => w.write_int64(v),
+            },
+            Value::U32(v) => if f.typ == "fixed32" { w.write_fixed32(v) } else { w.write_uint32(v) },
+            Value::U64(v) => if f.typ == "fixed64" { w.write_fixed64(v) } else { w.write_uint64(v) },
+            Value::F32(v) => w.write_float(v),
+            Value::F64(v) => w.write_double(v),
+            Value::Bool(v) => w.write_bool(v),
+            Value::String(ref v) => w.write_string(v),
+            Value::Bytes(ref v) => w.write_bytes(v),
+            Value::Enum(v) => w.write_enum(v),
+            Value::Message(ref m) => match *kind {
+                Kind::Group(_) => {
+                    m.write_message(w)?;
+                    w.write_end_group(f.number as u32)
+                }
+                _ => w.write_message(m),
+            },
+            Value::Entry(ref entry) => match *kind {
+                Kind::Map(key, value) => {
+                    let (key_kind, value_kind) = (self.kind(key), self.kind(value));
+                    let len = 2 + self.value_size(key, &key_kind, &entry.0) +
+                              self.value_size(value, &value_kind, &entry.1);
+                    w.write_varint(len as u64)?;
+                    w.write_tag(1 << 3 | wire_type(key, &key_kind))?;
+                    self.write_value(key, &key_kind, &entry.0, w)?;
+                    w.write_tag(2 << 3 | wire_type(value, &value_kind))?;
+                    self.write_value(value, &value_kind, &entry.1, w)
+                }
+                _ => Ok(()),
+            },
+        }
+    }
+}
+
+impl<'a> MessageWrite for DynamicMessage<'a> {
+    fn get_size(&self) -> usize {
+        let mut size = self.unknown_fields.get_size();
+        for (f, values) in self.iter() {
+            let kind = self.kind(f);
+            let tag_size = sizeof_varint(((f.number as u32) << 3) as u64);
+            let values_size = values.iter().map(|v| self.value_size(f, &kind, v)).sum::<usize>();
+            size += if self.is_packed(f, &kind) {
+                tag_size + sizeof_var_length(values_size)
+            } else {
+                tag_size * values.len() + values_size
+            };
+        }
+        size
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> quick_protobuf::Result<()> {
+        for (f, values) in self.iter() {
+            let kind = self.kind(f);
+            let number = f.number as u32;
+            if self.is_packed(f, &kind) {
+                let len = values.iter().map(|v| self.value_size(f, &kind, v)).sum::<usize>();
+                w.write_tag(number << 3 | 2)?;
+                w.write_varint(len as u64)?;
+                for v in values {
+                    self.write_value(f, &kind, v, w)?;
+                }
+            } else {
+                for v in values {
+                    w.write_tag(number << 3 | wire_type(f, &kind))?;
+                    self.write_value(f, &kind, v, w)?;
+                }
+            }
+        }
+        w.write_unknown_fields(&self.unknown_fields)
+    }
+}
+
+impl<'a> PartialEq for DynamicMessage<'a> {
+    fn eq(&self, other: &DynamicMessage<'a>) -> bool {
+        self.descriptor.fqn() == other.descriptor.fqn() &&
+            self.fields == other.fields &&
+            self.unknown_fields == other.unknown_fields
+    }
+}
+
+impl<'a> fmt::Debug for DynamicMessage<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DynamicMessage")
+            .field("message", &self.descriptor.fqn())
+            .field("fields", &self.fields)
+            .field("unknown_fields", &self.unknown_fields)
+            .finish()
+    }
+}
+
+/// An iterator over the fields set in a `DynamicMessage`, with their values
+pub struct Iter<'b, 'a: 'b> {
+    msg: &'b DynamicMessage<'a>,
+    fields: slice::Iter<'b, (i32, Vec<Value<'a>>)>,
+}
+
+impl<'b, 'a> Iterator for Iter<'b, 'a> {
+    type Item = (&'a Field<'a>, &'b [Value<'a>]);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (number, values) = self.fields.next()?;
+            if let Some(f) = self.msg.field_by_number(*number) {
+                return Some((f, values));
+            }
+        }
+    }
+}
+
+/// Gets the wire type of non packed values of `f`
+fn wire_type(f: &Field, kind: &Kind) -> u32 {
+    match *kind {
+        Kind::Message(_) | Kind::Map(..) => 2,
+        Kind::Group(_) => 3,
+        Kind::Enum(_) => 0,
+        Kind::Scalar => match &*f.typ {
+            "fixed64" | "sfixed64" | "double" => 1,
+            "fixed32" | "sfixed32" | "float" => 5,
+            "string" | "bytes" => 2,
+            _ => 0,
+        },
+    }
+}
+
+
+#[test]
+fn test_dynamic_message() {
+    let proto = br#"package test;
+message Inner { optional string name = 1; }
+message Outer {
+    optional sint32 id = 1;
+    repeated uint32 nums = 2 [packed = true];
+    optional Inner inner = 3;
+    map<string, int32> counts = 4;
+    oneof choice {
+        bool flag = 5;
+        Inner other = 6;
+    }
+}"#;
+    let mut file = FileDescriptor::from_bytes(proto).unwrap();
+    file.resolve().unwrap();
+
+    // id: -2, nums: [1, 300], inner { name: "a" }, counts { "x": 1 }, flag: true, unknown field 9
+    let bytes = [0x08, 0x03, 0x12, 0x03, 0x01, 0xac, 0x02, 0x1a, 0x03, 0x0a, 0x01, b'a',
+                 0x22, 0x05, 0x0a, 0x01, b'x', 0x10, 0x01, 0x28, 0x01, 0x48, 0x07];
+    let mut r = BytesReader::from_bytes(&bytes);
+    let mut msg = DynamicMessage::from_reader(&file, "Outer", &mut r, &bytes).unwrap();
+    assert_eq!(Some(&Value::I32(-2)), msg.get("id"));
+    assert_eq!(&[Value::U32(1), Value::U32(300)], msg.get_repeated("nums"));
+    match msg.get("inner") {
+        Some(Value::Message(inner)) => assert_eq!(Some(&Value::String("a".to_string())), inner.get("name")),
+        v => panic!("expecting a message, got {:?}", v),
+    }
+    assert_eq!(Some(&Value::Entry(Box::new((Value::String("x".to_string()), Value::I32(1))))), msg.get("counts"));
+    assert_eq!(1, msg.unknown_fields().len());
+    assert_eq!(bytes.len(), msg.get_size());
+
+    let mut out = Vec::new();
+    msg.write_message(&mut Writer::new(&mut out)).unwrap();
+    assert_eq!(&bytes[..], &*out);
+
+    msg.set("id", Value::I32(150)).unwrap();
+    assert!(msg.set("id", Value::U32(1)).is_err());
+    assert!(msg.set("missing", Value::I32(1)).is_err());
+    let mut out = Vec::new();
+    msg.write_message(&mut Writer::new(&mut out)).unwrap();
+    assert_eq!(out.len(), msg.get_size());
+    assert_eq!(&[0x08, 0xac, 0x02], &out[..3]);
+}
+
+#[test]
+fn test_dynamic_oneof() {
+    let proto = br#"message Inner { optional int32 x = 1; }
+message Outer {
+    oneof choice {
+        bool flag = 1;
+        Inner inner = 2;
+    }
+}"#;
+    let mut file = FileDescriptor::from_bytes(proto).unwrap();
+    file.resolve().unwrap();
+
+    let mut msg = DynamicMessage::new(&file, "Outer").unwrap();
+    msg.set("flag", Value::Bool(true)).unwrap();
+    let mut inner = DynamicMessage::new(&file, "Inner").unwrap();
+    inner.set("x", Value::I32(3)).unwrap();
+    msg.set("inner", Value::Message(inner)).unwrap();
+    assert!(!msg.has("flag"));
+    assert!(msg.set("inner", Value::Message(DynamicMessage::new(&file, "Outer").unwrap())).is_err());
+
+    let mut out = Vec::new();
+    msg.write_message(&mut Writer::new(&mut out)).unwrap();
+    assert_eq!(vec![0x12, 0x02, 0x08, 0x03], out);
+}
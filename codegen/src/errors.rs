@@ -4,8 +4,9 @@
 
 error_chain! {
     foreign_links {
+        Protobuf(::quick_protobuf::errors::Error);
         Io(::std::io::Error);
-        Nom(::nom::simple_errors::Err);
+        Nom(::nom::Err<Vec<u8>>);
     }
     errors {
         InvalidMessage(desc: String) {
@@ -13,5 +14,37 @@ error_chain! {
             display("message checks errored: {}", desc)
             cause("proto definition might be invalid or something got wrong in the parsing")
         }
+        ParseError(file: String, line: usize, column: usize, expected: String, snippet: String) {
+            description("cannot parse .proto file")
+            display("{}:{}:{}: expecting {}\n{}", file, line, column, expected, snippet)
+        }
+        UnknownType(typ: String, field: String) {
+            description("unknown field type")
+            display("unknown type '{}' for field '{}', is an import missing?", typ, field)
+        }
+        ImportNotFound(import: String, file: String) {
+            description("imported file not found")
+            display("cannot find '{}' imported by '{}' in the include paths", import, file)
+        }
+        ImportCycle(cycle: String) {
+            description("import cycle")
+            display("import cycle: {}", cycle)
+        }
+        UnknownMessage(name: String) {
+            description("unknown message")
+            display("unknown message '{}'", name)
+        }
+        UnknownField(field: String, message: String) {
+            description("unknown field")
+            display("unknown field '{}' in message '{}'", field, message)
+        }
+        InvalidValue(value: String, field: String) {
+            description("invalid field value")
+            display("invalid value {} for field '{}'", value, field)
+        }
+        InvalidInput(desc: String) {
+            description("invalid input")
+            display("invalid input: {}", desc)
+        }
     }
 }
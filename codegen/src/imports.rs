@@ -0,0 +1,105 @@
+//! A module to load a .proto file along with all the files it imports
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use errors::{Result, ErrorKind};
+use types::FileDescriptor;
+
+/// A loaded .proto file
+#[derive(Debug)]
+pub struct ProtoFile {
+    /// canonical path of the file
+    pub path: PathBuf,
+    /// content of the file
+    pub data: Vec<u8>,
+    /// indexes of the imported files
+    pub imports: Vec<usize>,
+}
+
+impl ProtoFile {
+    /// The name of the generated module, which is the file stem
+    ///
+    /// Modules generated for imported files are expected to be declared next to each other
+    pub fn module(&self) -> &str {
+        self.path.file_stem().and_then(|s| s.to_str()).unwrap_or("")
+    }
+
+    /// The generated rust file, next to the .proto one
+    pub fn out_file(&self) -> PathBuf {
+        self.path.with_extension("rs")
+    }
+}
+
+/// Loads `in_file` and all the files it (transitively) imports
+///
+/// Imports are searched in `include_paths`, in order, then in the directory of `in_file`. Files
+/// are returned sorted such that imported files always come before the files importing them.
+pub fn load(in_file: &Path, include_paths: &[PathBuf]) -> Result<Vec<ProtoFile>> {
+    let mut include_paths = include_paths.to_vec();
+    include_paths.push(in_file.parent().map_or_else(PathBuf::new, Path::to_path_buf));
+    let mut files = Vec::new();
+    load_file(fs::canonicalize(in_file)?, &include_paths, &mut files, &mut Vec::new())?;
+    Ok(files)
+}
+
+/// Parses and resolves loaded files, in order
+///
+/// The last descriptor, which is the one of the loaded `in_file`, knows the types of all the files.
+pub fn resolve<'a>(files: &'a [ProtoFile]) -> Result<Vec<FileDescriptor<'a>>> {
+    let mut descriptors: Vec<FileDescriptor> = Vec::with_capacity(files.len());
+    for file in files {
+        let mut f = FileDescriptor::from_bytes(&file.data)?;
+        for &i in &file.imports {
+            f.import(&descriptors[i], files[i].module());
+        }
+        f.resolve()?;
+        descriptors.push(f);
+    }
+    Ok(descriptors)
+}
+
+/// Loads `path` after its imports, `stack` being the chain of files importing it
+fn load_file(path: PathBuf,
+             include_paths: &[PathBuf],
+             files: &mut Vec<ProtoFile>,
+             stack: &mut Vec<PathBuf>) -> Result<usize> {
+
+    if let Some(i) = files.iter().position(|f| f.path == path) {
+        return Ok(i);
+    }
+    if let Some(i) = stack.iter().position(|p| *p == path) {
+        let cycle = stack[i..].iter().chain(Some(&path))
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>();
+        return Err(ErrorKind::ImportCycle(cycle.join(" -> ")).into());
+    }
+
+    let mut data = Vec::new();
+    File::open(&path)?.read_to_end(&mut data)?;
+    let import_names = FileDescriptor::parse(&data, &path.display().to_string())?.imports.iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>();
+
+    stack.push(path);
+    let mut imports = Vec::with_capacity(import_names.len());
+    for name in import_names {
+        let import_path = match include_paths.iter().map(|p| p.join(&name)).find(|p| p.is_file()) {
+            Some(p) => fs::canonicalize(p)?,
+            None => {
+                let file = stack.last().unwrap().display().to_string();
+                return Err(ErrorKind::ImportNotFound(name, file).into());
+            }
+        };
+        imports.push(load_file(import_path, include_paths, files, stack)?);
+    }
+    let path = stack.pop().unwrap();
+
+    files.push(ProtoFile {
+        path,
+        data,
+        imports,
+    });
+    Ok(files.len() - 1)
+}
@@ -0,0 +1,44 @@
+//! A converter from .proto files into quick-protobuf compatible rust modules
+//!
+//! Besides the `pb-rs` binary, modules can be generated from a build script with `Config`:
+//!
+//! ```rust,no_run
+//! // build.rs
+//! extern crate pb_rs;
+//!
+//! fn main() {
+//!     // writes `a.rs`, the modules of the files it imports and a `mod.rs` declaring them
+//!     // into `OUT_DIR`, and tells cargo to rerun the script if any .proto file changes
+//!     pb_rs::Config::new()
+//!         .include("protos")
+//!         .compile(&["protos/a.proto"])
+//!         .expect("cannot generate protobuf modules");
+//! }
+//! ```
+//!
+//! The generated modules are then included from the crate:
+//!
+//! ```rust,ignore
+//! mod protos {
+//!     include!(concat!(env!("OUT_DIR"), "/mod.rs"));
+//! }
+//! ```
+//!
+//! Parsed .proto files (`types::FileDescriptor`) also describe messages at runtime, see the
+//! `dynamic` module.
+
+#[macro_use]
+extern crate nom;
+#[macro_use]
+extern crate error_chain;
+extern crate quick_protobuf;
+
+mod parser;
+mod config;
+pub mod types;
+pub mod errors;
+pub mod imports;
+pub mod dynamic;
+pub mod transcode;
+
+pub use config::Config;
@@ -1,29 +1,76 @@
-#[macro_use]
-extern crate nom;
-#[macro_use]
-extern crate error_chain;
-
-mod parser;
-mod types;
-mod errors;
+extern crate pb_rs;
+extern crate quick_protobuf;
 
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs::File;
-use std::io::{Read, BufReader, BufWriter};
-use types::FileDescriptor;
+use std::io::{self, Read, Write};
+use std::process;
+use pb_rs::Config;
+use pb_rs::errors::{Result, ErrorKind};
+use pb_rs::imports;
+use pb_rs::transcode::{self, Format};
+use quick_protobuf::raw::RawMessage;
 
 fn main() {
 
     let args = env::args().collect::<Vec<_>>();
-    let usage = format!("{} <file.proto>", args[0]);
+    let usage = format!("{0} [--keep-unknown-fields] [--json] [--text] [--no-std] [-I <include_path>]... <file.proto>\n\
+                         {0} decode|encode [--json] [-I <include_path>]... <file.proto> <Message> [<file>]\n\
+                         {0} decode-raw [<file>]", args[0]);
 
-    if args.len() == 0 {
-        println!("{}", usage);
+    if args.get(1).map(|a| &**a) == Some("decode-raw") {
+        if args.len() > 3 {
+            println!("{}", usage);
+            return;
+        }
+        if let Err(e) = decode_raw(args.get(2).map(Path::new)) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
         return;
     }
 
-    let in_file: PathBuf = args.get(1).map(|a| a.into()).unwrap();
+    let command = match args.get(1).map(|a| &**a) {
+        Some(c @ "decode") | Some(c @ "encode") => Some(c),
+        _ => None,
+    };
+
+    let mut keep_unknown_fields = false;
+    let mut json = false;
+    let mut text = false;
+    let mut no_std = false;
+    let mut positionals = Vec::new();
+    let mut include_paths = Vec::new();
+    let mut args_iter = args.iter().skip(if command.is_some() { 2 } else { 1 });
+    while let Some(arg) = args_iter.next() {
+        match &**arg {
+            "--keep-unknown-fields" if command.is_none() => keep_unknown_fields = true,
+            "--json" => json = true,
+            "--text" if command.is_none() => text = true,
+            "--no-std" if command.is_none() => no_std = true,
+            "-I" => match args_iter.next() {
+                Some(p) => include_paths.push(PathBuf::from(p)),
+                None => {
+                    println!("{}", usage);
+                    return;
+                }
+            },
+            a if a.starts_with("-I") => include_paths.push(PathBuf::from(&a[2..])),
+            a if !a.starts_with('-') => positionals.push(a),
+            _ => {
+                println!("{}", usage);
+                return;
+            }
+        }
+    }
+
+    let expected = if command.is_some() { 2..4 } else { 1..2 };
+    if !expected.contains(&positionals.len()) {
+        println!("{}", usage);
+        return;
+    }
+    let in_file = PathBuf::from(positionals[0]);
     match in_file.extension().and_then(|e| e.to_str()) {
         Some("proto") => (),
         _ => {
@@ -33,20 +80,72 @@ fn main() {
         }
     }
 
-    let out_file = in_file.with_extension("rs");
-
-    let mut data = Vec::with_capacity(in_file.metadata()
-                                     .expect("Cannot get input file length")
-                                     .len() as usize);
-    let parsed_file = {
-        let f = File::open(&in_file).expect(&usage);
-        let mut reader = BufReader::new(f);
-        reader.read_to_end(&mut data).expect("Cannot read input file");
-        FileDescriptor::from_bytes(&data).expect("Cannot parse protobuf messages")
+    let format = if json { Format::Json } else { Format::Text };
+    let result = match command {
+        Some(c) => transcode(&in_file, &include_paths, c == "encode", positionals[1],
+                             positionals.get(2).map(Path::new), format),
+        None => generate(&in_file, &include_paths, keep_unknown_fields, json, text, no_std),
     };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+/// Generates the rust modules of `in_file` and all the files it imports, next to them
+fn generate(in_file: &Path,
+            include_paths: &[PathBuf],
+            keep_unknown_fields: bool,
+            json: bool,
+            text: bool,
+            no_std: bool) -> Result<()> {
+    let mut config = Config::default();
+    for path in include_paths {
+        config.include(path);
+    }
+    config.keep_unknown_fields(keep_unknown_fields)
+        .json(json)
+        .text(text)
+        .no_std(no_std)
+        .compile(&[in_file])
+}
 
-    let name = in_file.file_name().and_then(|e| e.to_str()).unwrap();
-    let mut w = BufWriter::new(File::create(out_file).expect("Cannot create output file"));
-    parsed_file.write(&mut w, name).expect("Cannot write rust module");
+/// Decodes a `message` of `proto_file` read from `in_file` (or stdin) and prints it in `format`,
+/// or encodes it (if `encode` is set)
+fn transcode(proto_file: &Path,
+             include_paths: &[PathBuf],
+             encode: bool,
+             message: &str,
+             in_file: Option<&Path>,
+             format: Format) -> Result<()> {
 
+    let files = imports::load(proto_file, include_paths)?;
+    let descriptors = imports::resolve(&files)?;
+    let file = descriptors.last().unwrap();
+
+    let mut input = Vec::new();
+    match in_file {
+        Some(p) => File::open(p)?.read_to_end(&mut input)?,
+        None => io::stdin().read_to_end(&mut input)?,
+    };
+    if encode {
+        let input = String::from_utf8(input).map_err(|e| ErrorKind::InvalidInput(e.to_string()))?;
+        let bytes = transcode::encode(file, message, &input, format)?;
+        io::stdout().write_all(&bytes)?;
+    } else {
+        print!("{}", transcode::decode(file, message, &input, format)?);
+    }
+    Ok(())
+}
+
+/// Prints the fields of an encoded message read from `in_file` (or stdin), without any schema
+fn decode_raw(in_file: Option<&Path>) -> Result<()> {
+    let mut bytes = Vec::new();
+    match in_file {
+        Some(p) => File::open(p)?.read_to_end(&mut bytes)?,
+        None => io::stdin().read_to_end(&mut bytes)?,
+    };
+    let msg = RawMessage::decode(&bytes)?;
+    print!("{}", quick_protobuf::text::to_string(&msg));
+    Ok(())
 }
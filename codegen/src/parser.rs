@@ -1,6 +1,7 @@
 use std::str;
-use types::{Frequency, Field, Message, Enumerator, MessageOrEnum, FileDescriptor, Syntax};
-use nom::{multispace, digit};
+use types::{Frequency, Field, Message, MessageEvent, OneOf, Enumerator, MessageOrEnum, FileDescriptor, Syntax,
+            Service, RpcMethod};
+use nom::{multispace, digit, Err, IResult};
 
 fn is_word(b: u8) -> bool {
     match b {
@@ -9,7 +10,14 @@ fn is_word(b: u8) -> bool {
     }
 }
 
-named!(word<&str>, map_res!(take_while!(is_word), str::from_utf8));
+fn is_full_ident(b: u8) -> bool {
+    is_word(b) || b == b'.'
+}
+
+named!(word<&'a str>, map_res!(take_while1!(is_word), str::from_utf8));
+
+// a type name, possibly qualified (`Outer.Inner`, `.package.Message`)
+named!(full_ident<&'a str>, map_res!(take_while1!(is_full_ident), str::from_utf8));
 
 named!(comment<()>, do_parse!(tag!("//") >> take_until_and_consume!("\n") >> ()));
 named!(block_comment<()>, do_parse!(tag!("/*") >> take_until_and_consume!("*/") >> ()));
@@ -17,10 +25,13 @@ named!(block_comment<()>, do_parse!(tag!("/*") >> take_until_and_consume!("*/")
 /// word break: multispace or comment
 named!(br<()>, alt!(map!(multispace, |_| ()) | comment | block_comment));
 
+named!(syntax_version<Syntax>,
+       alt!(tag!("\"proto2\"") => { |_| Syntax::Proto2 } |
+            tag!("\"proto3\"") => { |_| Syntax::Proto3 }));
+
 named!(syntax<Syntax>, 
-       do_parse!(tag!("syntax") >> many0!(br) >> tag!("=") >> 
-                 proto: alt!(tag!("\"proto2\"") => { |_| Syntax::Proto2 } | 
-                             tag!("\"proto3\"") => { |_| Syntax::Proto3 }) >> 
+       do_parse!(tag!("syntax") >> many0!(br) >> tag!("=") >> many0!(br) >>
+                 proto: syntax_version >> many0!(br) >> tag!(";") >> many0!(br) >>
                  (proto) ));
 
 named!(reserved_nums<Vec<i32>>, 
@@ -29,14 +40,14 @@ named!(reserved_nums<Vec<i32>>,
                                         many0!(alt!(br | tag!(",") => { |_| () })) >> (num))) >>
                 (nums) ));
                               
-named!(reserved_names<Vec<&str>>, 
+named!(reserved_names<Vec<&'a str>>, 
        do_parse!(tag!("reserved") >> many1!(br) >> 
                  names: many1!(do_parse!(tag!("\"") >> name: word >> tag!("\"") >>
                                         many0!(alt!(br | tag!(",") => { |_| () })) >> (name))) >>
                 (names) ));
                               
 
-named!(default_value<&str>, 
+named!(default_value<&'a str>, 
        do_parse!(tag!("[") >> many0!(br) >> tag!("default") >> many0!(br) >> tag!("=") >> many0!(br) >> 
                  default: word >> many0!(br) >> tag!("]") >>
                  (default) ));
@@ -56,9 +67,9 @@ named!(frequency<Frequency>,
             tag!("repeated") => { |_| Frequency::Repeated } |
             tag!("required") => { |_| Frequency::Required } ));
 
-named!(message_field<Field>, 
-       do_parse!(frequency: opt!(frequency) >> many1!(br) >>
-                 typ: word >> many1!(br) >>
+named!(message_field<Field<'a>>, 
+       do_parse!(frequency: opt!(do_parse!(f: frequency >> many1!(br) >> (f))) >>
+                 typ: full_ident >> many1!(br) >>
                  name: word >> many0!(br) >>
                  tag!("=") >> many0!(br) >>
                  number: map_res!(map_res!(digit, str::from_utf8), str::FromStr::from_str) >> many0!(br) >> 
@@ -66,58 +77,204 @@ named!(message_field<Field>,
                  deprecated: opt!(deprecated) >> many0!(br) >> 
                  packed: opt!(packed) >> many0!(br) >> tag!(";") >> many0!(br) >>
                  (Field {
-                    name: name,
+                    name: name.to_string(),
                     frequency: frequency.unwrap_or(Frequency::Optional),
-                    typ: typ,
+                    typ: typ.to_string(),
                     number: number,
                     default: default,
                     packed: packed,
                     boxed: false,
                     deprecated: deprecated.unwrap_or(false),
+                    is_group: false,
+                    map: None,
+                    typ_path: None,
+                    proto3: false,
                  }) ));
 
-named!(message<Message>, 
+fn map_entry_field(name: &str, typ: &str, number: i32) -> Field<'static> {
+    Field {
+        name: name.to_string(),
+        frequency: Frequency::Required,
+        typ: typ.to_string(),
+        number,
+        default: None,
+        packed: None,
+        boxed: false,
+        deprecated: false,
+        is_group: false,
+        map: None,
+        typ_path: None,
+        proto3: false,
+    }
+}
+
+// a map field: an implicit repeated entry message with a key (1) and a value (2)
+named!(map_field<Field<'a>>, 
+       do_parse!(tag!("map") >> many0!(br) >> tag!("<") >> many0!(br) >>
+                 key: word >> many0!(br) >> tag!(",") >> many0!(br) >>
+                 value: full_ident >> many0!(br) >> tag!(">") >> many0!(br) >>
+                 name: word >> many0!(br) >>
+                 tag!("=") >> many0!(br) >>
+                 number: map_res!(map_res!(digit, str::from_utf8), str::FromStr::from_str) >> many0!(br) >> 
+                 tag!(";") >> many0!(br) >>
+                 (Field {
+                    name: name.to_string(),
+                    frequency: Frequency::Repeated,
+                    typ: "map".to_string(),
+                    number,
+                    default: None,
+                    packed: None,
+                    boxed: false,
+                    deprecated: false,
+                    is_group: false,
+                    map: Some(Box::new((map_entry_field("key", key, 1), map_entry_field("value", value, 2)))),
+                    typ_path: None,
+                    proto3: false,
+                 }) ));
+
+// a proto2 group: both a field and the definition of its (nested) message type
+named!(group<(Field<'a>, Message<'a>)>, 
+       do_parse!(frequency: opt!(do_parse!(f: frequency >> many1!(br) >> (f))) >>
+                 tag!("group") >> many1!(br) >>
+                 name: word >> many0!(br) >>
+                 tag!("=") >> many0!(br) >>
+                 number: map_res!(map_res!(digit, str::from_utf8), str::FromStr::from_str) >> many0!(br) >> 
+                 tag!("{") >> many0!(br) >>
+                 events: many0!(message_event) >> 
+                 tag!("}") >> many0!(br) >>
+                 ((Field {
+                     name: name.to_lowercase(),
+                     frequency: frequency.unwrap_or(Frequency::Optional),
+                     typ: name.to_string(),
+                     number,
+                     default: None,
+                     packed: None,
+                     boxed: false,
+                     deprecated: false,
+                     is_group: true,
+                     map: None,
+                     typ_path: None,
+                     proto3: false,
+                   }, Message::new(name, events))) ));
+
+named!(one_of<OneOf<'a>>, 
+       do_parse!(tag!("oneof") >> many1!(br) >>
+                 name: word >> many0!(br) >>
+                 tag!("{") >> many0!(br) >>
+                 fields: many0!(message_field) >> 
+                 tag!("}") >> many0!(br) >>
+                 (OneOf { name, fields }) ));
+
+named!(message_event<MessageEvent<'a>>, alt!(
+         do_parse!(nums: reserved_nums >> opt!(tag!(";")) >> many0!(br) >> (nums)) => 
+             { MessageEvent::ReservedNums } |
+         do_parse!(names: reserved_names >> opt!(tag!(";")) >> many0!(br) >> (names)) => 
+             { MessageEvent::ReservedNames } |
+         group => { |(f, m)| MessageEvent::Group(f, m) } |
+         one_of => { MessageEvent::OneOf } |
+         map_field => { MessageEvent::Field } |
+         message => { MessageEvent::Message } |
+         enumerator => { MessageEvent::Enumerator } |
+         message_field => { MessageEvent::Field } ));
+
+named!(message<Message<'a>>, 
        do_parse!(tag!("message") >> many0!(br) >> 
                  name: word >> many0!(br) >> 
                  tag!("{") >> many0!(br) >>
-                 reserved_nums: opt!(reserved_nums) >> many0!(br) >>
-                 reserved_names: opt!(reserved_names) >> many0!(br) >>
-                 fields: many0!(message_field) >> 
+                 events: many0!(message_event) >> 
                  tag!("}") >> many0!(br) >>
-                 (Message { 
-                     name: name, 
-                     fields: fields, 
-                     reserved_nums: reserved_nums,
-                     reserved_names: reserved_names,
-                 }) ));
+                 (Message::new(name, events)) ));
 
-named!(enum_field<(&str, i32)>, 
+named!(enum_field<(&'a str, i32)>, 
        do_parse!(name: word >> many0!(br) >>
                  tag!("=") >> many0!(br) >>
                  number: map_res!(map_res!(digit, str::from_utf8), str::FromStr::from_str) >> many0!(br) >>
                  tag!(";") >> many0!(br) >>
                  ((name, number))));
     
-named!(enumerator<Enumerator>, 
+named!(enumerator<Enumerator<'a>>, 
        do_parse!(tag!("enum") >> many1!(br) >>
                  name: word >> many0!(br) >>
                  tag!("{") >> many0!(br) >>
                  fields: many0!(enum_field) >> 
                  tag!("}") >> many0!(br) >>
-                 (Enumerator { name: name, fields: fields })));
+                 (Enumerator { 
+                     name, 
+                     fields, 
+                     package: String::new(),
+                     module: String::new(),
+                     import: None,
+                 })));
+
+named!(package<&'a str>, 
+       do_parse!(tag!("package") >> many1!(br) >> 
+                 name: full_ident >> many0!(br) >> 
+                 tag!(";") >> many0!(br) >>
+                 (name) ));
 
 named!(ignore<()>, 
-       do_parse!(alt!(tag!("package") | tag!("option") | tag!("import")) >> many1!(br) >> 
+       do_parse!(tag!("option") >> many1!(br) >> 
                  take_until_and_consume!(";") >> many0!(br) >> ()));
 
-named!(service_ignore<()>, do_parse!(tag!("service") >> many1!(br) >> word >> many0!(br) >> tag!("{") >>
-                                     take_until_and_consume!("}") >> many0!(br) >> ()));
+named!(import<&'a str>, 
+       do_parse!(tag!("import") >> many1!(br) >> 
+                 opt!(do_parse!(alt!(tag!("public") | tag!("weak")) >> many1!(br) >> ())) >>
+                 tag!("\"") >> path: map_res!(take_until!("\""), str::from_utf8) >> tag!("\"") >> 
+                 many0!(br) >> tag!(";") >> many0!(br) >>
+                 (path) ));
+
+// a `{ ... }` block, possibly containing nested blocks (e.g. rpc options)
+named!(block<()>, 
+       do_parse!(tag!("{") >> 
+                 many0!(alt!(block | map!(is_not!("{}"), |_| ()))) >> 
+                 tag!("}") >> ()));
 
-named!(message_or_enum<MessageOrEnum>, alt!(
-         message => { |m| MessageOrEnum::Msg(m) } | 
-         enumerator => { |e| MessageOrEnum::Enum(e) } |
+named!(rpc_arg<(&'a str, bool)>, 
+       do_parse!(tag!("(") >> many0!(br) >>
+                 stream: opt!(do_parse!(tag!("stream") >> many1!(br) >> ())) >>
+                 typ: full_ident >> many0!(br) >>
+                 tag!(")") >>
+                 ((typ, stream.is_some())) ));
+
+named!(rpc<RpcMethod<'a>>, 
+       do_parse!(tag!("rpc") >> many1!(br) >>
+                 name: word >> many0!(br) >>
+                 input: rpc_arg >> many0!(br) >>
+                 tag!("returns") >> many0!(br) >>
+                 output: rpc_arg >> many0!(br) >>
+                 alt!(tag!(";") => { |_| () } | block) >> many0!(br) >>
+                 (RpcMethod {
+                     name,
+                     input: input.0.to_string(),
+                     input_stream: input.1,
+                     output: output.0.to_string(),
+                     output_stream: output.1,
+                 }) ));
+
+named!(service_event<Option<RpcMethod<'a>>>, alt!(
+         rpc => { Some } | 
+         ignore => { |_| None } |
+         do_parse!(tag!(";") >> many0!(br) >> ()) => { |_| None } ));
+
+named!(service<Service<'a>>, 
+       do_parse!(tag!("service") >> many1!(br) >> 
+                 name: word >> many0!(br) >> 
+                 tag!("{") >> many0!(br) >>
+                 methods: many0!(service_event) >>
+                 tag!("}") >> many0!(br) >>
+                 (Service {
+                     name,
+                     methods: methods.into_iter().flatten().collect(),
+                     package: String::new(),
+                 }) ));
+
+named!(message_or_enum<MessageOrEnum<'a>>, alt!(
+         message => { MessageOrEnum::Msg } | 
+         enumerator => { MessageOrEnum::Enum } |
+         import => { MessageOrEnum::Import } |
+         package => { MessageOrEnum::Package } |
          ignore => { |_| MessageOrEnum::Ignore } |
-         service_ignore => { |_| MessageOrEnum::Ignore } ));
+         service => { MessageOrEnum::Service } ));
 
 named!(pub file_descriptor<FileDescriptor>, do_parse!(
     many0!(br) >> syntax: opt!(syntax) >> many0!(br) >>
@@ -127,8 +284,102 @@ named!(pub file_descriptor<FileDescriptor>, do_parse!(
         message_and_enums: message_and_enums,
         messages: Vec::new(),
         enums: Vec::new(),
+        imports: Vec::new(),
+        package: "",
+        services: Vec::new(),
+        json: false,
+        text: false,
+        no_std: false,
     })));
 
+/// Parses a whole .proto file
+///
+/// On failure, returns the offset of the first statement which cannot be parsed, as deep as
+/// possible in nested blocks, and what was expected there.
+pub fn parse(input: &[u8]) -> Result<FileDescriptor<'_>, (usize, &'static str)> {
+    let mut offset = match file_descriptor(input) {
+        IResult::Done(b"", f) => return Ok(f),
+        IResult::Done(rest, _) => input.len() - rest.len(),
+        _ => 0,
+    };
+    let mut expected = "a message, enum, service, import, package or option";
+    // a block stops parsing at its first invalid statement: moves into the statement
+    // of the current block which parses the furthest, while there is one
+    let mut block = Some(Block::File);
+    while let Some(b) = block {
+        let rest = &input[offset..];
+        match statements(b, rest).into_iter().filter(|s| s.0 > 0).max_by_key(|s| s.0) {
+            Some((e, exp, inner)) => {
+                offset += e;
+                expected = exp;
+                block = inner;
+            }
+            None => break,
+        }
+    }
+    Err((offset, expected))
+}
+
+/// The statements which a block contains
+#[derive(Clone, Copy)]
+enum Block {
+    File,
+    Message,
+    Enum,
+    OneOf,
+    Service,
+}
+
+/// The offset where `result`, parsed from `input`, fails, or 0 if it parses
+fn error_offset<O>(input: &[u8], result: IResult<&[u8], O>) -> usize {
+    match result {
+        IResult::Done(..) => 0,
+        IResult::Incomplete(_) => input.len(),
+        IResult::Error(Err::Position(_, rest)) |
+        IResult::Error(Err::NodePosition(_, rest, _)) => input.len() - rest.len(),
+        IResult::Error(_) => 0,
+    }
+}
+
+/// Where each statement of `block` fails to parse `i`, what is expected there and, if the
+/// statement is itself a block, which one
+fn statements(block: Block, i: &[u8]) -> Vec<(usize, &'static str, Option<Block>)> {
+    const FIELD: &str = "a field ('label type name = number;'), with no other option than \
+                         '[default = value]', '[deprecated = bool]' or '[packed = bool]'";
+    const OPTION: &str = "an option ('option name = value;')";
+    match block {
+        Block::File => vec![
+            (error_offset(i, syntax(i)), "\"proto2\" or \"proto3\" syntax ('syntax = \"proto3\";')", None),
+            (error_offset(i, import(i)), "an import ('import \"path/file.proto\";')", None),
+            (error_offset(i, package(i)), "a package ('package name;')", None),
+            (error_offset(i, ignore(i)), OPTION, None),
+            (error_offset(i, message(i)), "a field, message, enum, oneof, reserved or '}'", Some(Block::Message)),
+            (error_offset(i, enumerator(i)), "an enum value or '}'", Some(Block::Enum)),
+            (error_offset(i, service(i)), "an rpc, an option or '}'", Some(Block::Service)),
+        ],
+        Block::Message => vec![
+            (error_offset(i, message(i)), "a field, message, enum, oneof, reserved or '}'", Some(Block::Message)),
+            (error_offset(i, enumerator(i)), "an enum value or '}'", Some(Block::Enum)),
+            (error_offset(i, one_of(i)), "a field or '}'", Some(Block::OneOf)),
+            (error_offset(i, group(i)), "a field, message, enum, oneof, reserved or '}'", Some(Block::Message)),
+            (error_offset(i, map_field(i)), "a map field ('map<key_type, value_type> name = number;')", None),
+            (error_offset(i, reserved_nums(i)), "reserved field numbers ('reserved 1, 2;')", None),
+            (error_offset(i, reserved_names(i)), "reserved field names ('reserved \"a\", \"b\";')", None),
+            (error_offset(i, message_field(i)), FIELD, None),
+        ],
+        Block::Enum => vec![
+            (error_offset(i, enum_field(i)), "an enum value ('NAME = number;')", None),
+        ],
+        Block::OneOf => vec![
+            (error_offset(i, message_field(i)), FIELD, None),
+        ],
+        Block::Service => vec![
+            (error_offset(i, rpc(i)), "an rpc ('rpc Name (Request) returns (Response);')", None),
+            (error_offset(i, ignore(i)), OPTION, None),
+        ],
+    }
+}
+
 #[test]
 fn test_message() {
     let msg = r#"message ReferenceData 
@@ -151,6 +402,107 @@ fn test_message() {
     }
 }
 
+#[test]
+fn test_group() {
+    let msg = r#"message SearchResponse {
+    reserved 7, 8;
+    repeated group Hit = 1 {
+        required string url = 2;
+        optional group Inner = 4 {
+            optional int32 x = 5;
+        }
+    }
+    int32 count = 6;
+}"#;
+
+    match message(msg.as_bytes()) {
+        ::nom::IResult::Done(_, mess) => {
+            assert_eq!(2, mess.fields.len());
+            assert!(mess.fields[0].is_group);
+            assert_eq!("hit", mess.fields[0].name);
+            assert_eq!("Hit", mess.fields[0].typ);
+            assert_eq!(1, mess.messages.len());
+            assert_eq!(1, mess.messages[0].messages.len());
+            assert_eq!(Some(vec![7, 8]), mess.reserved_nums);
+        }
+        e => panic!("Expecting done {:?}", e),
+    }
+}
+
+#[test]
+fn test_one_of() {
+    let msg = r#"message SampleMessage {
+    optional int32 id = 1;
+    oneof test_oneof {
+        string name = 4;
+        SubMessage sub_message = 9;
+    }
+}"#;
+
+    match message(msg.as_bytes()) {
+        ::nom::IResult::Done(_, mess) => {
+            assert_eq!(1, mess.fields.len());
+            assert_eq!(1, mess.oneofs.len());
+            assert_eq!("test_oneof", mess.oneofs[0].name);
+            assert_eq!(2, mess.oneofs[0].fields.len());
+            assert_eq!("sub_message", mess.oneofs[0].fields[1].name);
+            assert_eq!(9, mess.oneofs[0].fields[1].number);
+        }
+        e => panic!("Expecting done {:?}", e),
+    }
+}
+
+#[test]
+fn test_map() {
+    let msg = r#"message A {
+    map<string, int32> counts = 3;
+    map< int64 , Bar > bars = 4;
+}"#;
+
+    match message(msg.as_bytes()) {
+        ::nom::IResult::Done(_, mess) => {
+            assert_eq!(2, mess.fields.len());
+            assert_eq!("counts", mess.fields[0].name);
+            let map = mess.fields[0].map.as_ref().unwrap();
+            assert_eq!(("string", "int32"), (&*map.0.typ, &*map.1.typ));
+            let map = mess.fields[1].map.as_ref().unwrap();
+            assert_eq!(("int64", "Bar"), (&*map.0.typ, &*map.1.typ));
+            assert_eq!(4, mess.fields[1].number);
+        }
+        e => panic!("Expecting done {:?}", e),
+    }
+}
+
+#[test]
+fn test_nested() {
+    let msg = r#"message Outer {
+    message Inner {
+        enum Kind {
+            A = 0;
+        }
+        optional Kind kind = 1;
+    }
+    enum Kind {
+        X = 0;
+    }
+    optional Inner inner = 1;
+    optional Inner.Kind inner_kind = 2;
+    optional .Outer.Kind kind = 3;
+}"#;
+
+    match message(msg.as_bytes()) {
+        ::nom::IResult::Done(_, mess) => {
+            assert_eq!(3, mess.fields.len());
+            assert_eq!(1, mess.messages.len());
+            assert_eq!(1, mess.enums.len());
+            assert_eq!(1, mess.messages[0].enums.len());
+            assert_eq!("Inner.Kind", mess.fields[1].typ);
+            assert_eq!(".Outer.Kind", mess.fields[2].typ);
+        }
+        e => panic!("Expecting done {:?}", e),
+    }
+}
+
 #[test]
 fn test_enum() {
     let msg = r#"enum PairingStatus {
@@ -168,9 +520,7 @@ fn test_enum() {
 
 #[test]
 fn test_ignore() {
-    let msg = r#"package com.test.v0;
-
-option optimize_for = SPEED;
+    let msg = r#"option optimize_for = SPEED;
 "#;
 
     match ignore(msg.as_bytes()) {
@@ -178,3 +528,117 @@ option optimize_for = SPEED;
         e => panic!("Expecting done {:?}", e),
     }
 }
+
+#[test]
+fn test_import() {
+    let msg = r#"import "common/types.proto";
+import public "base.proto";
+
+message A { 
+    optional Base base = 1;
+}
+"#;
+
+    let desc = file_descriptor(msg.as_bytes()).to_full_result().unwrap();
+    let imports = desc.message_and_enums.iter().filter_map(|m| match *m {
+        MessageOrEnum::Import(i) => Some(i),
+        _ => None,
+    }).collect::<Vec<_>>();
+    assert_eq!(vec!["common/types.proto", "base.proto"], imports);
+}
+
+#[test]
+fn test_package() {
+    let msg = r#"package com.test.v0;
+
+message A { 
+    optional .com.test.v0.B b = 1;
+}
+"#;
+
+    let desc = file_descriptor(msg.as_bytes()).to_full_result().unwrap();
+    match desc.message_and_enums[0] {
+        MessageOrEnum::Package(p) => assert_eq!("com.test.v0", p),
+        ref e => panic!("Expecting package {:?}", e),
+    }
+    assert_eq!(2, desc.message_and_enums.len());
+}
+
+#[test]
+fn test_service() {
+    let msg = r#"service Greeter {
+    option deprecated = true;
+    rpc SayHello (HelloRequest) returns (HelloReply);
+    rpc Chat(stream .chat.Msg) returns (stream Msg) {
+        option (google.api.http) = { post: "/v1/chat" body: "*" };
+    }
+    rpc Empty (Req) returns (Resp) {}
+}
+"#;
+
+    match service(msg.as_bytes()) {
+        ::nom::IResult::Done(rest, s) => {
+            assert!(rest.is_empty());
+            assert_eq!("Greeter", s.name);
+            assert_eq!(3, s.methods.len());
+            assert_eq!("SayHello", s.methods[0].name);
+            assert!(!s.methods[0].input_stream && !s.methods[0].output_stream);
+            assert_eq!(".chat.Msg", s.methods[1].input);
+            assert_eq!("Msg", s.methods[1].output);
+            assert!(s.methods[1].input_stream && s.methods[1].output_stream);
+            assert_eq!("Empty", s.methods[2].name);
+        }
+        e => panic!("Expecting done {:?}", e),
+    }
+}
+
+#[test]
+fn test_syntax() {
+    let msg = r#"syntax = "proto3";
+
+message A { int32 a = 1; }"#;
+
+    let desc = parse(msg.as_bytes()).unwrap();
+    assert!(matches!(desc.syntax, Syntax::Proto3));
+    assert_eq!(1, desc.message_and_enums.len());
+}
+
+#[test]
+fn test_parse_error() {
+    let msg = r#"message A {
+    message B {
+        optional int32 b = 1
+    }
+}"#;
+
+    let (offset, expected) = parse(msg.as_bytes()).unwrap_err();
+    assert_eq!(msg.rfind("}\n}").unwrap(), offset);
+    assert!(expected.starts_with("a field"), "{}", expected);
+
+    let msg = r#"enum E {
+    A = 0;
+    B = -1;
+}"#;
+
+    let (offset, expected) = parse(msg.as_bytes()).unwrap_err();
+    assert_eq!(msg.find("-1").unwrap(), offset);
+    assert_eq!("an enum value ('NAME = number;')", expected);
+
+    let msg = "message A {\n    optional int32 a = 1;\n";
+    assert_eq!((msg.len(), "a field, message, enum, oneof, reserved or '}'"), parse(msg.as_bytes()).unwrap_err());
+}
+
+#[test]
+fn test_parse_error_location() {
+    let msg = r#"syntax = "proto2";
+
+message Person {
+  required string name = 1;
+  optional int32 id = 2 [json_name = "i"];
+}"#;
+
+    let e = ::types::FileDescriptor::parse(msg.as_bytes(), "person.proto").unwrap_err();
+    assert_eq!("person.proto:5:25: expecting a field ('label type name = number;'), with no other option than \
+                '[default = value]', '[deprecated = bool]' or '[packed = bool]'\n  \
+                optional int32 id = 2 [json_name = \"i\"];\n                        ^", e.to_string());
+}
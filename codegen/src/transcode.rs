@@ -0,0 +1,375 @@
+//! A module to convert encoded messages from and to the text format or JSON at runtime
+//!
+//! Messages are described by the parsed (and resolved) .proto files instead of generated code:
+//! they are decoded into a `DynamicMessage`, which is then written in the requested format.
+
+use quick_protobuf::{BytesReader, MessageWrite, Writer};
+use quick_protobuf::json::{self, JsonWriter};
+use quick_protobuf::text::{self, TextWriter};
+
+use dynamic::{DynamicMessage, Kind, Value};
+use errors::{Result, ErrorKind};
+use types::{Field, FileDescriptor, Frequency};
+
+/// The human readable formats
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// the protobuf text format
+    Text,
+    /// the canonical proto3 JSON mapping
+    Json,
+}
+
+/// Decodes a `message` out of `bytes`, and writes it in `format`
+pub fn decode(file: &FileDescriptor, message: &str, bytes: &[u8], format: Format) -> Result<String> {
+    let mut r = BytesReader::from_bytes(bytes);
+    let msg = DynamicMessage::from_reader(file, message, &mut r, bytes)?;
+    Ok(match format {
+        Format::Text => {
+            let mut w = TextWriter::new();
+            write_text(&msg, &mut w);
+            w.into_string()
+        }
+        Format::Json => {
+            let mut w = JsonWriter::new();
+            write_json(&msg, &mut w);
+            let mut s = w.into_string();
+            s.push('\n');
+            s
+        }
+    })
+}
+
+/// Parses a `message` written in `format`, and encodes it
+pub fn encode(file: &FileDescriptor, message: &str, input: &str, format: Format) -> Result<Vec<u8>> {
+    let mut msg = DynamicMessage::new(file, message)?;
+    match format {
+        Format::Text => read_text(&mut msg, &text::Value::parse(input)?)?,
+        Format::Json => read_json(&mut msg, &json::Value::parse(input)?)?,
+    }
+    let mut bytes = Vec::new();
+    msg.write_message(&mut Writer::new(&mut bytes))?;
+    Ok(bytes)
+}
+
+fn is_repeated(f: &Field) -> bool {
+    matches!(f.frequency, Frequency::Repeated)
+}
+
+/// Converts a map key into a JSON object key
+fn map_key(v: &Value) -> String {
+    match *v {
+        Value::I32(v) => v.to_string(),
+        Value::I64(v) => v.to_string(),
+        Value::U32(v) => v.to_string(),
+        Value::U64(v) => v.to_string(),
+        Value::Bool(v) => v.to_string(),
+        Value::String(ref v) => v.clone(),
+        _ => String::new(),
+    }
+}
+
+// text format
+
+fn write_text(msg: &DynamicMessage, w: &mut TextWriter) {
+    w.begin_message();
+    for (f, values) in msg.iter() {
+        for v in values {
+            w.key(f.text_name());
+            write_text_value(msg, f, v, w);
+        }
+    }
+    w.end_message();
+}
+
+fn write_text_value<'a>(msg: &DynamicMessage<'a>, f: &'a Field<'a>, v: &Value<'a>, w: &mut TextWriter) {
+    match *v {
+        Value::I32(v) => w.write_i32(v),
+        Value::I64(v) => w.write_i64(v),
+        Value::U32(v) => w.write_u32(v),
+        Value::U64(v) => w.write_u64(v),
+        Value::F32(v) => w.write_f32(v),
+        Value::F64(v) => w.write_f64(v),
+        Value::Bool(v) => w.write_bool(v),
+        Value::String(ref v) => w.write_string(v),
+        Value::Bytes(ref v) => w.write_bytes(v),
+        Value::Enum(i) => match msg.kind(f) {
+            Kind::Enum(e) => match e.fields.iter().find(|v| v.1 == i) {
+                Some(&(name, _)) => w.write_ident(name),
+                None => w.write_i32(i),
+            },
+            _ => w.write_i32(i),
+        },
+        Value::Message(ref m) => write_text(m, w),
+        Value::Entry(ref entry) => {
+            if let Kind::Map(key, value) = msg.kind(f) {
+                w.begin_message();
+                w.key("key");
+                write_text_value(msg, key, &entry.0, w);
+                w.key("value");
+                write_text_value(msg, value, &entry.1, w);
+                w.end_message();
+            }
+        }
+    }
+}
+
+fn read_text(msg: &mut DynamicMessage, v: &text::Value) -> Result<()> {
+    for field in v.as_message()? {
+        // groups are named after their type, their field name being the lowercased type name
+        let f = match msg.field(&field.name).or_else(|| msg.field(&field.name.to_lowercase()).filter(|f| f.is_group)) {
+            Some(f) => f,
+            None => return Err(field.unknown().into()),
+        };
+        for v in field.value.values() {
+            let value = read_text_value(msg, f, v)?;
+            if is_repeated(f) {
+                msg.push(&f.name, value)?;
+            } else {
+                msg.set(&f.name, value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_text_value<'a>(msg: &DynamicMessage<'a>, f: &'a Field<'a>, v: &text::Value) -> Result<Value<'a>> {
+    Ok(match msg.kind(f) {
+        Kind::Enum(e) => match v.as_ident() {
+            Ok(name) => match e.fields.iter().find(|v| v.0 == name) {
+                Some(&(_, i)) => Value::Enum(i),
+                None => return Err(v.unknown_value(e.name).into()),
+            },
+            Err(_) => Value::Enum(v.as_i32()?),
+        },
+        Kind::Message(_) | Kind::Group(_) => {
+            let mut value = msg.default_value(f);
+            if let Value::Message(ref mut m) = value {
+                read_text(m, v)?;
+            }
+            value
+        }
+        Kind::Map(key, value) => {
+            let (mut k, mut val) = (msg.default_value(key), msg.default_value(value));
+            for field in v.as_message()? {
+                match &*field.name {
+                    "key" => k = read_text_value(msg, key, &field.value)?,
+                    "value" => val = read_text_value(msg, value, &field.value)?,
+                    _ => return Err(field.unknown().into()),
+                }
+            }
+            Value::Entry(Box::new((k, val)))
+        }
+        Kind::Scalar => match &*f.typ {
+            "int32" | "sint32" | "sfixed32" => Value::I32(v.as_i32()?),
+            "int64" | "sint64" | "sfixed64" => Value::I64(v.as_i64()?),
+            "uint32" | "fixed32" => Value::U32(v.as_u32()?),
+            "uint64" | "fixed64" => Value::U64(v.as_u64()?),
+            "float" => Value::F32(v.as_f32()?),
+            "double" => Value::F64(v.as_f64()?),
+            "bool" => Value::Bool(v.as_bool()?),
+            "string" => Value::String(v.as_str()?.to_string()),
+            _ => Value::Bytes(v.as_bytes()?.to_vec()),
+        },
+    })
+}
+
+// json
+
+fn write_json(msg: &DynamicMessage, w: &mut JsonWriter) {
+    w.begin_object();
+    for (f, values) in msg.iter() {
+        w.key(&f.json_name());
+        if let Kind::Map(_, value) = msg.kind(f) {
+            w.begin_object();
+            for v in values {
+                if let Value::Entry(ref entry) = *v {
+                    w.key(&map_key(&entry.0));
+                    write_json_value(msg, value, &entry.1, w);
+                }
+            }
+            w.end_object();
+        } else if is_repeated(f) {
+            w.begin_array();
+            for v in values {
+                write_json_value(msg, f, v, w);
+            }
+            w.end_array();
+        } else if let Some(v) = values.last() {
+            write_json_value(msg, f, v, w);
+        }
+    }
+    w.end_object();
+}
+
+fn write_json_value<'a>(msg: &DynamicMessage<'a>, f: &'a Field<'a>, v: &Value<'a>, w: &mut JsonWriter) {
+    match *v {
+        Value::I32(v) => w.write_i32(v),
+        Value::I64(v) => w.write_i64(v),
+        Value::U32(v) => w.write_u32(v),
+        Value::U64(v) => w.write_u64(v),
+        Value::F32(v) => w.write_f32(v),
+        Value::F64(v) => w.write_f64(v),
+        Value::Bool(v) => w.write_bool(v),
+        Value::String(ref v) => w.write_string(v),
+        Value::Bytes(ref v) => w.write_bytes(v),
+        Value::Enum(i) => match msg.kind(f) {
+            Kind::Enum(e) => match e.fields.iter().find(|v| v.1 == i) {
+                Some(&(name, _)) => w.write_string(name),
+                None => w.write_i32(i),
+            },
+            _ => w.write_i32(i),
+        },
+        Value::Message(ref m) => write_json(m, w),
+        Value::Entry(_) => w.write_null(),
+    }
+}
+
+fn read_json(msg: &mut DynamicMessage, v: &json::Value) -> Result<()> {
+    let descriptor = msg.descriptor();
+    for (name, v) in v.as_object()? {
+        if v.is_null() {
+            continue;
+        }
+        let f = match descriptor.fields.iter()
+            .chain(descriptor.oneofs.iter().flat_map(|o| o.fields.iter()))
+            .find(|f| f.json_name() == *name || f.name == *name) {
+            Some(f) => f,
+            None => return Err(ErrorKind::UnknownField(name.clone(), descriptor.fqn()).into()),
+        };
+        if let Kind::Map(key, value) = msg.kind(f) {
+            for (k, v) in v.as_object()? {
+                let k = read_json_value(msg, key, &json::Value::String(k.clone()))?;
+                let v = read_json_value(msg, value, v)?;
+                msg.push(&f.name, Value::Entry(Box::new((k, v))))?;
+            }
+        } else if is_repeated(f) {
+            for v in v.as_array()? {
+                let value = read_json_value(msg, f, v)?;
+                msg.push(&f.name, value)?;
+            }
+        } else {
+            let value = read_json_value(msg, f, v)?;
+            msg.set(&f.name, value)?;
+        }
+    }
+    Ok(())
+}
+
+fn read_json_value<'a>(msg: &DynamicMessage<'a>, f: &'a Field<'a>, v: &json::Value) -> Result<Value<'a>> {
+    Ok(match msg.kind(f) {
+        Kind::Enum(e) => match *v {
+            json::Value::String(ref name) => match e.fields.iter().find(|v| v.0 == name) {
+                Some(&(_, i)) => Value::Enum(i),
+                None => return Err(ErrorKind::InvalidInput(format!("unknown {} value '{}'", e.name, name)).into()),
+            },
+            _ => Value::Enum(v.as_i32()?),
+        },
+        Kind::Message(_) | Kind::Group(_) => {
+            let mut value = msg.default_value(f);
+            if let Value::Message(ref mut m) = value {
+                read_json(m, v)?;
+            }
+            value
+        }
+        Kind::Map(..) => return Err(ErrorKind::InvalidInput(format!("field '{}' cannot be nested", f.name)).into()),
+        Kind::Scalar => match &*f.typ {
+            "int32" | "sint32" | "sfixed32" => Value::I32(v.as_i32()?),
+            "int64" | "sint64" | "sfixed64" => Value::I64(v.as_i64()?),
+            "uint32" | "fixed32" => Value::U32(v.as_u32()?),
+            "uint64" | "fixed64" => Value::U64(v.as_u64()?),
+            "float" => Value::F32(v.as_f32()?),
+            "double" => Value::F64(v.as_f64()?),
+            "bool" => Value::Bool(v.as_bool()?),
+            "string" => Value::String(v.as_str()?.to_string()),
+            _ => Value::Bytes(v.as_bytes()?),
+        },
+    })
+}
+
+#[cfg(test)]
+fn test_file() -> FileDescriptor<'static> {
+    let proto = br#"package test;
+enum Kind { TEXT = 0; CODE = 1; }
+message Line { optional string content = 1; optional Kind kind = 2; }
+message Doc {
+    optional string title = 1;
+    repeated Line lines = 2;
+    map<string, int32> counts = 3;
+    map<int64, Line> by_id = 4;
+    optional group Meta = 8 { optional string author = 9; }
+    oneof body {
+        Kind default_kind = 5;
+        Line summary = 6;
+        bytes raw = 7;
+    }
+}"#;
+    let mut file = FileDescriptor::from_bytes(proto).unwrap();
+    file.resolve().unwrap();
+    file
+}
+
+#[test]
+fn test_transcode_text() {
+    let file = test_file();
+    let text = r#"title: "doc"
+lines {
+  content: "a"
+  kind: CODE
+}
+lines {
+  content: "b"
+}
+counts {
+  key: "x"
+  value: -1
+}
+by_id {
+  key: 3
+  value {
+    kind: TEXT
+  }
+}
+Meta {
+  author: "me"
+}
+summary {
+  content: "s"
+}
+"#;
+    let bytes = encode(&file, "Doc", text, Format::Text).unwrap();
+    assert_eq!(text, decode(&file, "Doc", &bytes, Format::Text).unwrap());
+
+    // groups are named after their type, their lowercased field name is accepted too
+    let lowercase = text.replace("Meta {", "meta {");
+    assert_eq!(bytes, encode(&file, "Doc", &lowercase, Format::Text).unwrap());
+}
+
+#[test]
+fn test_transcode_json() {
+    let file = test_file();
+    let json = concat!(r#"{"title":"doc","lines":[{"content":"a","kind":"CODE"},{"content":"b"}],"#,
+                       r#""counts":{"x":-1},"byId":{"3":{"kind":"TEXT"}},"defaultKind":"CODE"}"#, "\n");
+    let bytes = encode(&file, "Doc", json, Format::Json).unwrap();
+    assert_eq!(json, decode(&file, "Doc", &bytes, Format::Json).unwrap());
+}
+
+#[test]
+fn test_transcode_binary() {
+    let file = test_file();
+    let text = "title: \"doc\"\nlines {\n  kind: CODE\n}\ncounts {\n  key: \"x\"\n  value: 2\n}\nraw: \"\\001\"\n";
+    let bytes = encode(&file, "Doc", text, Format::Text).unwrap();
+    let json = decode(&file, "Doc", &bytes, Format::Json).unwrap();
+    assert_eq!(bytes, encode(&file, "Doc", &json, Format::Json).unwrap());
+    let text = decode(&file, "Doc", &bytes, Format::Text).unwrap();
+    assert_eq!(bytes, encode(&file, "Doc", &text, Format::Text).unwrap());
+}
+
+#[test]
+fn test_transcode_unknown_message() {
+    let file = test_file();
+    assert!(encode(&file, "Missing", "", Format::Text).is_err());
+    assert!(encode(&file, "Missing", "{}", Format::Json).is_err());
+    assert!(decode(&file, "Missing", &[], Format::Text).is_err());
+    assert!(decode(&file, "Missing", &[], Format::Json).is_err());
+}
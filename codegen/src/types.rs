@@ -1,7 +1,7 @@
 use std::io::Write;
 
-use errors::{Result, ErrorKind};
-use parser::file_descriptor;
+use errors::{Error, Result, ErrorKind};
+use parser;
 
 fn sizeof_varint(v: u32) -> usize {
     match v {
@@ -13,6 +13,25 @@ fn sizeof_varint(v: u32) -> usize {
     }
 }
 
+/// The expression of the size of the nested message `m`, length included, recording it if `cached`
+fn sizeof_message(m: &str, cached: bool) -> String {
+    if cached {
+        format!("sizeof_message_cached({}, sizes)", m)
+    } else {
+        format!("sizeof_var_length({}.get_size())", m)
+    }
+}
+
+/// The expression of the size of the group `m`, tags excluded, recording its nested messages if
+/// `cached`
+fn sizeof_group(m: &str, cached: bool) -> String {
+    if cached {
+        format!("{}.get_size_cached(sizes)", m)
+    } else {
+        format!("{}.get_size()", m)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum Syntax {
     Proto2,
@@ -28,14 +47,21 @@ pub enum Frequency {
 
 #[derive(Debug, Clone)]
 pub struct Field<'a> {
-    pub name: &'a str,
+    pub name: String,
     pub frequency: Frequency,
-    pub typ: &'a str,
+    pub typ: String,
     pub number: i32,
     pub default: Option<&'a str>,
     pub packed: Option<bool>,
     pub boxed: bool,
     pub deprecated: bool,
+    pub is_group: bool,
+    /// key and value of the implicit entry message of a map field
+    pub map: Option<Box<(Field<'a>, Field<'a>)>>,
+    /// rust path of the message or enum type, once resolved
+    pub typ_path: Option<String>,
+    /// declared in a proto3 file, where singular fields holding their type default are not serialized
+    pub proto3: bool,
 }
 
 impl<'a> Field<'a> {
@@ -44,14 +70,12 @@ impl<'a> Field<'a> {
     }
 
     fn is_numeric(&self) -> bool {
-        match self.typ {
+        matches!(&*self.typ,
             "int32" | "sint32" | "sfixed32" |
             "int64" | "sint64" | "sfixed64" |
             "uint32" | "fixed32" |
-            "uint64" | "fixed64" | 
-            "float" | "double" => true,
-            _ => false,
-        }
+            "uint64" | "fixed64" |
+            "float" | "double")
     }
 
     /// searches if the message must be boxed
@@ -59,18 +83,23 @@ impl<'a> Field<'a> {
         match self.frequency {
             Frequency::Repeated | Frequency::Required => return true,
             Frequency::Optional if !self.is_message(msgs) => true,
-            _ => leaf_messages.iter().any(|m| m == &self.typ),
+            _ => leaf_messages.iter().any(|m| *m == self.typ),
         }
     }
 
     fn is_message(&self, msgs: &[Message]) -> bool {
-        msgs.iter().any(|m| m.name == self.typ)
+        msgs.iter().any(|m| m.fqn() == self.typ)
     }
 
     fn is_enum(&self, msgs: &[Message]) -> bool {
         self.get_type(msgs) == "enum"
     }
 
+    /// Checks if the size of the field depends on the size of a nested message (or group)
+    fn has_nested_message(&self, msgs: &[Message]) -> bool {
+        self.is_message(msgs) || self.map_entry().is_some_and(|(_, v)| v.is_message(msgs))
+    }
+
     fn is_fixed_size(&self, msgs: &[Message]) -> bool {
         match self.wire_type_num_non_packed(msgs) {
             1 | 5 => true,
@@ -78,12 +107,29 @@ impl<'a> Field<'a> {
         }
     }
 
+    fn is_scalar(&self) -> bool {
+        self.is_numeric() || self.typ == "bool" || self.is_cow()
+    }
+
     fn is_cow(&self) -> bool {
         self.typ == "bytes" || self.typ == "string"
     }
 
+    /// The condition for the value `s` of a proto3 `Option` field not to be the type default
+    fn not_default(&self, s: &str, msgs: &[Message]) -> String {
+        match &*self.typ {
+            "bool" => format!("*{}", s),
+            "string" | "bytes" => format!("!{}.is_empty()", s),
+            _ if self.is_enum(msgs) => format!("*{} as i32 != 0", s),
+            _ => "true".to_string(),
+        }
+    }
+
     fn rust_type(&self, msgs: &[Message]) -> String {
-        match self.typ {
+        if let Some((key, value)) = self.map_entry() {
+            return format!("HashMap<{}, {}>", key.rust_type(msgs), value.rust_type(msgs));
+        }
+        match &*self.typ {
             "int32" | "sint32" | "sfixed32" => "i32".to_string(),
             "int64" | "sint64" | "sfixed64" => "i64".to_string(),
             "uint32" | "fixed32" => "u32".to_string(),
@@ -92,10 +138,10 @@ impl<'a> Field<'a> {
             "double" => "f64".to_string(),
             "string" => "Cow<'a, str>".to_string(),
             "bytes" => "Cow<'a, [u8]>".to_string(),
-            t => msgs.iter().find(|m| m.name == t).map_or(t.to_string(), |m| if m.has_lifetime(msgs) {
-                format!("{}<'a>", t.to_string())
+            t => msgs.iter().find(|m| m.fqn() == t).map_or(self.rust_path().to_string(), |m| if m.has_lifetime(msgs) {
+                format!("{}<'a>", self.rust_path())
             } else {
-                t.to_string()
+                self.rust_path().to_string()
             })
         }
     }
@@ -109,29 +155,38 @@ impl<'a> Field<'a> {
     }
 
     fn wire_type_num_non_packed(&self, msgs: &[Message]) -> u32 {
-        match self.typ {
+        if self.is_group {
+            return 3;
+        }
+        if self.map.is_some() {
+            return 2;
+        }
+        match &*self.typ {
             "int32" | "sint32" | "int64" | "sint64" | 
                 "uint32" | "uint64" | "bool" | "enum" => 0,
             "fixed64" | "sfixed64" | "double" => 1,
             "fixed32" | "sfixed32" | "float" => 5,
             "string" | "bytes" => 2,
-            t => if msgs.iter().any(|m| m.name == t) { 2 } else { 0 /* enum */ }
+            t => if msgs.iter().any(|m| m.fqn() == t) { 2 } else { 0 /* enum */ }
         }
     }
 
     fn get_type(&self, msgs: &[Message]) -> &str {
-        match self.typ {
+        match &*self.typ {
             "int32" | "sint32" | "int64" | "sint64" | 
                 "uint32" | "uint64" | "bool" | "fixed64" | 
                 "sfixed64" | "double" | "fixed32" | "sfixed32" | 
-                "float" | "bytes" | "string" => self.typ,
+                "float" | "bytes" | "string" => &self.typ,
+            _ if self.is_group => "group",
             _ => if self.is_message(msgs) { "message" } else { "enum" },
         }
     }
 
     fn read_fn(&self, msgs: &[Message]) -> String {
-        if self.is_message(msgs) {
-            format!("read_message(bytes, {}::from_reader)", self.typ)
+        if self.is_group {
+            format!("read_group(bytes, {}, {}::from_reader)", self.tag(msgs), self.rust_path())
+        } else if self.is_message(msgs) {
+            format!("read_message(bytes, {}::from_reader)", self.rust_path())
         } else {
             format!("read_{}(bytes)", self.get_type(msgs))
         }
@@ -141,7 +196,95 @@ impl<'a> Field<'a> {
         (self.number as u32) << 3 | self.wire_type_num(msgs)
     }
 
-    fn write_definition<W: Write>(&self, w: &mut W, msgs: &[Message]) -> Result<()> {
+    /// The key and value fields of the implicit entry message of a map
+    fn map_entry(&self) -> Option<(&Field<'a>, &Field<'a>)> {
+        self.map.as_ref().map(|m| (&m.0, &m.1))
+    }
+
+    /// The rust path of the type, as seen from the generated module
+    fn rust_path(&self) -> &str {
+        self.typ_path.as_ref().unwrap_or(&self.typ)
+    }
+
+    /// The closure reading a map key or value
+    fn map_read_fn(&self, msgs: &[Message]) -> String {
+        if self.is_cow() {
+            format!("|r, bytes| r.{}.map(Cow::Borrowed)", self.read_fn(msgs))
+        } else {
+            format!("|r, bytes| r.{}", self.read_fn(msgs))
+        }
+    }
+
+    /// The binary size of a map key or value field, tag included
+    fn map_get_size(&self, msgs: &[Message], s: &str, cached: bool) -> Result<String> {
+        let mut size = Vec::new();
+        self.write_inner_get_size(&mut size, msgs, s, "*", cached)?;
+        Ok(String::from_utf8(size).unwrap())
+    }
+
+    /// The binary size of a map key or value field, tag included, when it is written
+    ///
+    /// The size of a message value is the one the writer computed along with its parent
+    fn map_write_size(&self, msgs: &[Message], s: &str) -> Result<String> {
+        if self.is_message(msgs) {
+            Ok(format!("{} + sizeof_var_length(r.message_size({}))", sizeof_varint(self.tag(msgs)), s))
+        } else {
+            self.map_get_size(msgs, s, false)
+        }
+    }
+
+    /// The closure writing a map key or value (without the tag)
+    fn map_write_fn(&self, msgs: &[Message], s: &str) -> String {
+        let r = if self.wire_type_num_non_packed(msgs) == 2 { "" } else { "*" };
+        let as_enum = if self.is_enum(msgs) { " as i32" } else { "" };
+        format!("|r| r.write_{}({}{}{})", self.get_type(msgs), r, s, as_enum)
+    }
+
+    /// Replaces the type (if it is a message or an enum) by its fully qualified name
+    ///
+    /// `names` are the fully qualified names and rust paths of all known messages and enums
+    fn resolve_type(&mut self, scope: &str, names: &[(String, String)]) -> Result<()> {
+        match resolve_name(&self.typ, scope, names) {
+            Some((fqn, path)) => {
+                self.typ = fqn.clone();
+                self.typ_path = Some(path.clone());
+            }
+            None if self.is_scalar() || self.map.is_some() => (),
+            None => return Err(ErrorKind::UnknownType(self.typ.clone(), fqn(scope, &self.name)).into()),
+        }
+        if let Some(ref mut m) = self.map {
+            m.1.resolve_type(scope, names)?;
+        }
+        Ok(())
+    }
+
+    /// Records this field of `msg` in the path of the errors of `r.<read>`
+    fn map_err_path(&self, msg: &Message) -> String {
+        match self.frequency {
+            Frequency::Repeated if self.map.is_none() && !self.packed() => {
+                format!(".map_err(|e| e.in_repeated_field(\"{}\", \"{}\", msg.{}.len()))",
+                        msg.name, self.name, self.name)
+            }
+            _ => format!(".map_err(|e| e.in_field(\"{}\", \"{}\"))", msg.name, self.name),
+        }
+    }
+
+    fn write_match_tag_map<W: Write>(&self, w: &mut W, msg: &Message, msgs: &[Message]) -> Result<()> {
+        let (key, value) = self.map_entry().unwrap();
+        writeln!(w, "Ok({}) => {{", self.tag(msgs))?;
+        writeln!(w, "                    let (key, value) = r.read_map(bytes, {}, {}){}?;",
+                 key.map_read_fn(msgs), value.map_read_fn(msgs), self.map_err_path(msg))?;
+        writeln!(w, "                    msg.{}.insert(key, value);", self.name)?;
+        writeln!(w, "                }}")?;
+        Ok(())
+    }
+
+    fn write_definition<W: Write>(&self, w: &mut W, msgs: &[Message], no_std: bool) -> Result<()> {
+        if let Some((key, value)) = self.map_entry() {
+            let map = if no_std { "BTreeMap" } else { "HashMap" };
+            writeln!(w, "    pub {}: {}<{}, {}>,", self.name, map, key.rust_type(msgs), value.rust_type(msgs))?;
+            return Ok(());
+        }
         match self.frequency {
             Frequency::Optional => {
                 if self.boxed {
@@ -160,82 +303,98 @@ impl<'a> Field<'a> {
         Ok(())
     }
 
-    fn write_match_tag_owned<W: Write>(&self, w: &mut W, msgs: &[Message]) -> Result<()> {
+    fn write_match_tag_owned<W: Write>(&self, w: &mut W, msg: &Message, msgs: &[Message]) -> Result<()> {
+        let path = self.map_err_path(msg);
         match self.frequency {
             Frequency::Optional => {
                 if self.boxed {
-                    writeln!(w, "Ok({}) => msg.{} = Some(Box::new(r.{}?)),",
-                             self.tag(msgs), self.name, self.read_fn(msgs))?
+                    writeln!(w, "Ok({}) => msg.{} = Some(Box::new(r.{}{}?)),",
+                             self.tag(msgs), self.name, self.read_fn(msgs), path)?
                 } else {
                     if self.default.is_none() {
-                        writeln!(w, "Ok({}) => msg.{} = Some(r.{}?),",
-                                 self.tag(msgs), self.name, self.read_fn(msgs))?
+                        writeln!(w, "Ok({}) => msg.{} = Some(r.{}{}?),",
+                                 self.tag(msgs), self.name, self.read_fn(msgs), path)?
                     } else {
-                        writeln!(w, "Ok({}) => msg.{} = r.{}?,",
-                                 self.tag(msgs), self.name, self.read_fn(msgs))?
+                        writeln!(w, "Ok({}) => msg.{} = r.{}{}?,",
+                                 self.tag(msgs), self.name, self.read_fn(msgs), path)?
                     }
                 }
             }
             Frequency::Repeated => {
                 if self.packed() {
-                    writeln!(w, "Ok({}) => msg.{} = r.read_packed(bytes, |r, bytes| r.{})?,",
-                             self.tag(msgs), self.name, self.read_fn(msgs))?
+                    writeln!(w, "Ok({}) => msg.{} = r.read_packed(bytes, |r, bytes| r.{}){}?,",
+                             self.tag(msgs), self.name, self.read_fn(msgs), path)?
                 } else {
-                    writeln!(w, "Ok({}) => msg.{}.push(r.{}?),",
-                             self.tag(msgs), self.name, self.read_fn(msgs))?
+                    writeln!(w, "Ok({}) => msg.{}.push(r.{}{}?),",
+                             self.tag(msgs), self.name, self.read_fn(msgs), path)?
                 }
             }
             Frequency::Required => {
-                writeln!(w, "Ok({}) => msg.{} = r.{}?,",
-                         self.tag(msgs), self.name, self.read_fn(msgs))?
+                writeln!(w, "Ok({}) => msg.{} = r.{}{}?,",
+                         self.tag(msgs), self.name, self.read_fn(msgs), path)?
             }
         }
         Ok(())
     }
 
-    fn write_match_tag_borrowed<W: Write>(&self, w: &mut W, msgs: &[Message]) -> Result<()> {
+    fn write_match_tag_borrowed<W: Write>(&self, w: &mut W, msg: &Message, msgs: &[Message]) -> Result<()> {
+        let path = self.map_err_path(msg);
         match self.frequency {
             Frequency::Optional => {
                 if self.boxed {
-                    writeln!(w, "Ok({}) => msg.{} = Some(Box::new(Cow::Borrowed(r.{}?))),",
-                             self.tag(msgs), self.name, self.read_fn(msgs))?
+                    writeln!(w, "Ok({}) => msg.{} = Some(Box::new(Cow::Borrowed(r.{}{}?))),",
+                             self.tag(msgs), self.name, self.read_fn(msgs), path)?
                 } else {
                     if self.default.is_none() {
-                        writeln!(w, "Ok({}) => msg.{} = Some(Cow::Borrowed(r.{}?)),",
-                                 self.tag(msgs), self.name, self.read_fn(msgs))?
+                        writeln!(w, "Ok({}) => msg.{} = Some(Cow::Borrowed(r.{}{}?)),",
+                                 self.tag(msgs), self.name, self.read_fn(msgs), path)?
                     } else {
-                        writeln!(w, "Ok({}) => msg.{} = Cow::Borrowed(r.{}?),",
-                                 self.tag(msgs), self.name, self.read_fn(msgs))?
+                        writeln!(w, "Ok({}) => msg.{} = Cow::Borrowed(r.{}{}?),",
+                                 self.tag(msgs), self.name, self.read_fn(msgs), path)?
                     }
                 }
             }
             Frequency::Repeated => {
                 if self.packed() {
-                    writeln!(w, "Ok({}) => msg.{} = r.read_packed(bytes, |r, bytes| r.{})?,",
-                             self.tag(msgs), self.name, self.read_fn(msgs))?
+                    writeln!(w, "Ok({}) => msg.{} = r.read_packed(bytes, |r, bytes| r.{}){}?,",
+                             self.tag(msgs), self.name, self.read_fn(msgs), path)?
                 } else {
-                    writeln!(w, "Ok({}) => msg.{}.push(Cow::Borrowed(r.{}?)),",
-                             self.tag(msgs), self.name, self.read_fn(msgs))?
+                    writeln!(w, "Ok({}) => msg.{}.push(Cow::Borrowed(r.{}{}?)),",
+                             self.tag(msgs), self.name, self.read_fn(msgs), path)?
                 }
             }
             Frequency::Required => {
-                writeln!(w, "Ok({}) => msg.{} = Cow::Borrowed(r.{}?),",
-                         self.tag(msgs), self.name, self.read_fn(msgs))?
+                writeln!(w, "Ok({}) => msg.{} = Cow::Borrowed(r.{}{}?),",
+                         self.tag(msgs), self.name, self.read_fn(msgs), path)?
             }
         }
         Ok(())
     }
 
-    fn write_get_size<W: Write>(&self, w: &mut W, msgs: &[Message], is_first: bool) -> Result<()> {
+    /// Writes the size of the field, a term of the message size: the first one unless `is_first`
+    /// is false, followed by other terms unless `is_last`
+    ///
+    /// If `cached`, nested messages are sized with `sizeof_message_cached`, recording their sizes
+    fn write_get_size<W: Write>(&self, w: &mut W, msgs: &[Message], is_first: bool, is_last: bool,
+                                cached: bool) -> Result<()> {
+        // a leading `if` is parsed as a statement if other terms follow, unless parenthesized
+        let (open, close) = if is_first && !is_last { ("(", ")") } else { ("", "") };
         if is_first { 
             write!(w, "        ")?;
         } else { 
             write!(w, "        + ")?;
         }
+        if let Some((key, value)) = self.map_entry() {
+            let k = if key.is_fixed_size(msgs) { "_" } else { "k" };
+            let v = if value.is_fixed_size(msgs) { "_" } else { "v" };
+            writeln!(w, "self.{}.iter().map(|({}, {})| {} + sizeof_map_entry({}, {})).sum::<usize>()",
+                     self.name, k, v, sizeof_varint(self.tag(msgs)), key.map_get_size(msgs, "k", cached)?, value.map_get_size(msgs, "v", cached)?)?;
+            return Ok(());
+        }
         match self.frequency {
             Frequency::Required => {
-                self.write_inner_get_size(w, msgs, &format!("self.{}", self.name), "")?;
-                writeln!(w, "")?;
+                self.write_inner_get_size(w, msgs, &format!("self.{}", self.name), "", cached)?;
+                writeln!(w)?;
             }
             Frequency::Optional => {
                 match self.default {
@@ -245,13 +404,13 @@ impl<'a> Field<'a> {
                         } else {
                             write!(w, "self.{}.as_ref().map_or(0, |m| ", self.name)?;
                         }
-                        self.write_inner_get_size(w, msgs, "m", "*")?;
+                        self.write_inner_get_size(w, msgs, "m", "*", cached)?;
                         writeln!(w, ")")?;
                     }
                     Some(d) => {
-                        write!(w, "if self.{} == {} {{ 0 }} else {{", self.name, d)?;
-                        self.write_inner_get_size(w, msgs, &format!("self.{}", self.name), "")?;
-                        writeln!(w, "}}")?;
+                        write!(w, "{}if self.{} == {} {{ 0 }} else {{", open, self.name, d)?;
+                        self.write_inner_get_size(w, msgs, &format!("self.{}", self.name), "", cached)?;
+                        writeln!(w, "}}{}", close)?;
                     }
                 }
             }
@@ -260,31 +419,35 @@ impl<'a> Field<'a> {
                 let get_type = self.get_type(msgs);
                 let as_enum = if self.is_enum(msgs) { " as i32" } else { "" };
                 if self.packed() {
-                    write!(w, "if self.{}.is_empty() {{ 0 }} else {{ ", self.name)?;
+                    write!(w, "{}if self.{}.is_empty() {{ 0 }} else {{ ", open, self.name)?;
                     match self.wire_type_num_non_packed(msgs) {
                         0 => write!(w, "{} + sizeof_var_length(self.{}.iter().map(|s| sizeof_{}(*s{})).sum::<usize>())", 
                                     tag_size, self.name, get_type, as_enum)?,
                         1 => write!(w, "{} + sizeof_var_length(self.{}.len() * 8)", tag_size, self.name)?,
                         5 => write!(w, "{} + sizeof_var_length(self.{}.len() * 4)", tag_size, self.name)?,
-                        2 => {
-                            let len = if self.is_message(msgs) { "get_size" } else { "len" };
-                            write!(w, "{} + sizeof_var_length(self.{}.iter().map(|s| sizeof_var_length(s.{}())).sum::<usize>())", 
-                                   tag_size, self.name, len)?;
+                        2 if self.is_message(msgs) => {
+                            write!(w, "{} + sizeof_var_length(self.{}.iter().map(|s| {}).sum::<usize>())",
+                                   tag_size, self.name, sizeof_message("s", cached))?;
                         }
+                        2 => write!(w, "{} + sizeof_var_length(self.{}.iter().map(|s| sizeof_var_length(s.len())).sum::<usize>())",
+                                    tag_size, self.name)?,
                         e => panic!("expecting wire type number, got: {}", e),
                     }
-                    writeln!(w, " }}")?;
+                    writeln!(w, " }}{}", close)?;
                 } else {
                     match self.wire_type_num_non_packed(msgs) {
                         0 => writeln!(w, "self.{}.iter().map(|s| {} + sizeof_{}(*s{})).sum::<usize>()", 
                                       self.name, tag_size, get_type, as_enum)?,
                         1 => writeln!(w, "({} + 8) * self.{}.len()", tag_size, self.name)?,
                         5 => writeln!(w, "({} + 4) * self.{}.len()", tag_size, self.name)?,
-                        2 => {
-                            let len = if self.is_message(msgs) { "get_size" } else { "len" };
-                            writeln!(w, "self.{}.iter().map(|s| {} + sizeof_var_length(s.{}())).sum::<usize>()", 
-                                     self.name, tag_size, len)?;
+                        2 if self.is_message(msgs) => {
+                            writeln!(w, "self.{}.iter().map(|s| {} + {}).sum::<usize>()",
+                                     self.name, tag_size, sizeof_message("s", cached))?;
                         }
+                        2 => writeln!(w, "self.{}.iter().map(|s| {} + sizeof_var_length(s.len())).sum::<usize>()",
+                                      self.name, tag_size)?,
+                        3 => writeln!(w, "self.{}.iter().map(|s| {} + {}).sum::<usize>()",
+                                      self.name, 2 * tag_size, sizeof_group("s", cached))?,
                         e => panic!("expecting wire type number, got: {}", e),
                     }
                 }
@@ -293,7 +456,8 @@ impl<'a> Field<'a> {
         Ok(())
     }
 
-    fn write_inner_get_size<W: Write>(&self, w: &mut W, msgs: &[Message], s: &str, as_ref: &str) -> Result<()> {
+    fn write_inner_get_size<W: Write>(&self, w: &mut W, msgs: &[Message], s: &str, as_ref: &str,
+                                      cached: bool) -> Result<()> {
         let tag_size = sizeof_varint(self.tag(msgs));
         match self.wire_type_num_non_packed(msgs) {
             0 => {
@@ -303,12 +467,26 @@ impl<'a> Field<'a> {
             },
             1 => write!(w, "{} + 8", tag_size)?,
             5 => write!(w, "{} + 4", tag_size)?,
+            3 => write!(w, "{} + {}", 2 * tag_size, sizeof_group(s, cached))?,
+            2 if self.is_message(msgs) && !cached => {
+                write!(w, "{} + sizeof_var_length({}.get_size())", tag_size, s)?;
+            }
+            2 if self.is_message(msgs) => {
+                // a reference to the message itself, `s` being either the field or a reference to it
+                let m = if as_ref.is_empty() {
+                    format!("&{}", s)
+                } else if self.boxed {
+                    format!("&**{}", s)
+                } else {
+                    s.to_string()
+                };
+                write!(w, "{} + sizeof_message_cached({}, sizes)", tag_size, m)?;
+            }
             2 => {
-                let len = if self.is_message(msgs) { "get_size" } else { "len" };
                 if self.packed() {
-                    write!(w, "if s.is_empty() {{ 0 }} else {{ {} + sizeof_var_length({}.{}()) }}", tag_size, s, len)?;
+                    write!(w, "if s.is_empty() {{ 0 }} else {{ {} + sizeof_var_length({}.len()) }}", tag_size, s)?;
                 } else {
-                    write!(w, "{} + sizeof_var_length({}.{}())", tag_size, s, len)?;
+                    write!(w, "{} + sizeof_var_length({}.len())", tag_size, s)?;
                 }
             }
             e => panic!("expecting wire type number, got: {}", e),
@@ -318,9 +496,15 @@ impl<'a> Field<'a> {
 
     fn write_write<W: Write>(&self, w: &mut W, msgs: &[Message]) -> Result<()> {
         let tag = self.tag(msgs);
-        let use_ref = self.wire_type_num_non_packed(msgs) == 2;
+        let use_ref = self.wire_type_num_non_packed(msgs) == 2 || self.is_group;
         let get_type = self.get_type(msgs);
         let as_enum = if self.is_enum(msgs) { " as i32" } else { "" };
+        if let Some((key, value)) = self.map_entry() {
+            writeln!(w, "        for (k, v) in self.{}.iter() {{ r.write_tag({})?; r.write_map({} + {}, {}, {}, {}, {})?; }}",
+                     self.name, tag, key.map_write_size(msgs, "k")?, value.map_write_size(msgs, "v")?,
+                     key.tag(msgs), key.map_write_fn(msgs, "k"), value.tag(msgs), value.map_write_fn(msgs, "v"))?;
+            return Ok(());
+        }
         match self.frequency {
             Frequency::Required => {
                 let r = if use_ref { "&" } else { "" };
@@ -357,9 +541,14 @@ impl<'a> Field<'a> {
                                      tag, self.name, get_type, if use_ref { "" } else { "*" }, as_enum)?
                         },
                         t => {
+                            let size = match self.wire_type_num_non_packed(msgs) {
+                                1 => "|_| 8".to_string(),
+                                5 => "|_| 4".to_string(),
+                                _ => format!("|m| sizeof_{}(*m{})", t, as_enum),
+                            };
                             writeln!(w, "        r.write_packed_repeated_field_with_tag({}, &self.{}, |r, m| r.write_{}({}m{}), \
-                                        &|m| sizeof_{}(*m))?;", 
-                                     tag, self.name, get_type, if use_ref { "" } else { "*" }, as_enum, t)?
+                                        &{})?;", 
+                                     tag, self.name, get_type, if use_ref { "" } else { "*" }, as_enum, size)?
                         },
                     }
                 } else {
@@ -371,6 +560,223 @@ impl<'a> Field<'a> {
         Ok(())
     }
 
+    /// The lowerCamelCase name of the field in JSON, as computed by protoc
+    pub fn json_name(&self) -> String {
+        let mut name = String::with_capacity(self.name.len());
+        let mut upper = false;
+        for c in self.name.chars() {
+            if c == '_' {
+                upper = true;
+            } else if upper {
+                name.extend(c.to_uppercase());
+                upper = false;
+            } else {
+                name.push(c);
+            }
+        }
+        name
+    }
+
+    /// The names accepted when parsing JSON, as a match pattern
+    /// The name of the field in the text format, which is the name of its message type for groups
+    pub fn text_name(&self) -> &str {
+        if self.is_group {
+            self.typ.rsplit('.').next().unwrap_or(&self.typ)
+        } else {
+            &self.name
+        }
+    }
+
+    /// The text format names matching the field, the field name being accepted for groups
+    fn text_pattern(&self) -> String {
+        if self.is_group {
+            format!("\"{}\" | \"{}\"", self.text_name(), self.name)
+        } else {
+            format!("\"{}\"", self.name)
+        }
+    }
+
+    fn json_pattern(&self) -> String {
+        let json_name = self.json_name();
+        if json_name == self.name {
+            format!("\"{}\"", json_name)
+        } else {
+            format!("\"{}\" | \"{}\"", json_name, self.name)
+        }
+    }
+
+    /// The `JsonWriter` and `TextWriter` method suffix of scalar types
+    fn scalar_type(&self) -> Option<&'static str> {
+        match &*self.typ {
+            "int32" | "sint32" | "sfixed32" => Some("i32"),
+            "int64" | "sint64" | "sfixed64" => Some("i64"),
+            "uint32" | "fixed32" => Some("u32"),
+            "uint64" | "fixed64" => Some("u64"),
+            "float" => Some("f32"),
+            "double" => Some("f64"),
+            "bool" => Some("bool"),
+            "string" => Some("string"),
+            "bytes" => Some("bytes"),
+            _ => None,
+        }
+    }
+
+    /// Writes the value `s` as `format` (json or text), `is_ref` being true if `s` is a reference
+    fn value_write(&self, s: &str, is_ref: bool, format: &str) -> String {
+        match self.scalar_type() {
+            Some(t @ "string") | Some(t @ "bytes") => {
+                format!("w.write_{}({}{})", t, if is_ref { "" } else { "&" }, s)
+            }
+            Some(t) => format!("w.write_{}({}{})", t, if is_ref { "*" } else { "" }, s),
+            None => format!("{}.write_{}(w)", s, format),
+        }
+    }
+
+    /// Reads a value out of the `v` json or text value
+    fn value_read(&self, v: &str, format: &str) -> String {
+        match self.scalar_type() {
+            Some("string") => format!("Cow::Owned({}.as_str()?.to_string())", v),
+            Some("bytes") if format == "text" => format!("Cow::Owned({}.as_bytes()?.to_vec())", v),
+            Some("bytes") => format!("Cow::Owned({}.as_bytes()?)", v),
+            Some(t) => format!("{}.as_{}()?", v, t),
+            None if self.boxed => format!("Box::new({}::from_{}({})?)", self.rust_path(), format, v),
+            None => format!("{}::from_{}({})?", self.rust_path(), format, v),
+        }
+    }
+
+    fn write_write_json<W: Write>(&self, w: &mut W, msgs: &[Message]) -> Result<()> {
+        let name = self.json_name();
+        if let Some((key, value)) = self.map_entry() {
+            let key = if key.typ == "string" { "k" } else { "&k.to_string()" };
+            writeln!(w, "        if !self.{}.is_empty() {{", self.name)?;
+            writeln!(w, "            w.key(\"{}\");", name)?;
+            writeln!(w, "            w.begin_object();")?;
+            writeln!(w, "            for (k, v) in &self.{} {{ w.key({}); {}; }}", self.name, key, value.value_write("v", true, "json"))?;
+            writeln!(w, "            w.end_object();")?;
+            writeln!(w, "        }}")?;
+            return Ok(());
+        }
+        match self.frequency {
+            Frequency::Required => {
+                writeln!(w, "        w.key(\"{}\"); {};", name, self.value_write(&format!("self.{}", self.name), false, "json"))?;
+            }
+            Frequency::Optional => match self.default {
+                None if self.proto3 && !self.is_message(msgs) => {
+                    writeln!(w, "        if let Some(ref s) = self.{} {{ if {} {{ w.key(\"{}\"); {}; }} }}",
+                             self.name, self.not_default("s", msgs), name, self.value_write("s", true, "json"))?;
+                }
+                None => {
+                    writeln!(w, "        if let Some(ref s) = self.{} {{ w.key(\"{}\"); {}; }}",
+                             self.name, name, self.value_write("s", true, "json"))?;
+                }
+                Some(d) => {
+                    writeln!(w, "        if self.{} != {} {{ w.key(\"{}\"); {}; }}",
+                             self.name, d, name, self.value_write(&format!("self.{}", self.name), false, "json"))?;
+                }
+            },
+            Frequency::Repeated => {
+                writeln!(w, "        if !self.{}.is_empty() {{", self.name)?;
+                writeln!(w, "            w.key(\"{}\");", name)?;
+                writeln!(w, "            w.begin_array();")?;
+                writeln!(w, "            for s in &self.{} {{ {}; }}", self.name, self.value_write("s", true, "json"))?;
+                writeln!(w, "            w.end_array();")?;
+                writeln!(w, "        }}")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_match_json<W: Write>(&self, w: &mut W) -> Result<()> {
+        if let Some((key, value)) = self.map_entry() {
+            writeln!(w, "                {} => for &(ref k, ref v) in v.as_object()? {{", self.json_pattern())?;
+            if key.typ == "string" {
+                writeln!(w, "                    msg.{}.insert(Cow::Owned(k.clone()), {});", self.name, value.value_read("v", "json"))?;
+            } else {
+                writeln!(w, "                    let key = Value::String(k.clone());")?;
+                writeln!(w, "                    msg.{}.insert({}, {});", self.name, key.value_read("key", "json"), value.value_read("v", "json"))?;
+            }
+            writeln!(w, "                }},")?;
+            return Ok(());
+        }
+        match self.frequency {
+            Frequency::Optional if self.default.is_none() => {
+                writeln!(w, "                {} => msg.{} = Some({}),", self.json_pattern(), self.name, self.value_read("v", "json"))?;
+            }
+            Frequency::Optional | Frequency::Required => {
+                writeln!(w, "                {} => msg.{} = {},", self.json_pattern(), self.name, self.value_read("v", "json"))?;
+            }
+            Frequency::Repeated => {
+                writeln!(w, "                {} => msg.{} = v.as_array()?.iter().map(|v| Ok({})).collect::<Result<Vec<_>>>()?,",
+                         self.json_pattern(), self.name, self.value_read("v", "json"))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_write_text<W: Write>(&self, w: &mut W) -> Result<()> {
+        if let Some((key, value)) = self.map_entry() {
+            writeln!(w, "        for (k, v) in &self.{} {{", self.name)?;
+            writeln!(w, "            w.key(\"{}\");", self.name)?;
+            writeln!(w, "            w.begin_message();")?;
+            writeln!(w, "            w.key(\"key\"); {};", key.value_write("k", true, "text"))?;
+            writeln!(w, "            w.key(\"value\"); {};", value.value_write("v", true, "text"))?;
+            writeln!(w, "            w.end_message();")?;
+            writeln!(w, "        }}")?;
+            return Ok(());
+        }
+        match self.frequency {
+            Frequency::Required => {
+                writeln!(w, "        w.key(\"{}\"); {};", self.text_name(), self.value_write(&format!("self.{}", self.name), false, "text"))?;
+            }
+            Frequency::Optional => match self.default {
+                None => {
+                    writeln!(w, "        if let Some(ref s) = self.{} {{ w.key(\"{}\"); {}; }}",
+                             self.name, self.text_name(), self.value_write("s", true, "text"))?;
+                }
+                Some(d) => {
+                    writeln!(w, "        if self.{} != {} {{ w.key(\"{}\"); {}; }}",
+                             self.name, d, self.text_name(), self.value_write(&format!("self.{}", self.name), false, "text"))?;
+                }
+            },
+            Frequency::Repeated => {
+                writeln!(w, "        for s in &self.{} {{ w.key(\"{}\"); {}; }}",
+                         self.name, self.text_name(), self.value_write("s", true, "text"))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_match_text<W: Write>(&self, w: &mut W) -> Result<()> {
+        if let Some((key, value)) = self.map_entry() {
+            writeln!(w, "                \"{}\" => for v in v.values() {{", self.name)?;
+            writeln!(w, "                    let (mut key, mut value) = (Default::default(), Default::default());")?;
+            writeln!(w, "                    for f in v.as_message()? {{")?;
+            writeln!(w, "                        let v = &f.value;")?;
+            writeln!(w, "                        match &*f.name {{")?;
+            writeln!(w, "                            \"key\" => key = {},", key.value_read("v", "text"))?;
+            writeln!(w, "                            \"value\" => value = {},", value.value_read("v", "text"))?;
+            writeln!(w, "                            _ => return Err(f.unknown()),")?;
+            writeln!(w, "                        }}")?;
+            writeln!(w, "                    }}")?;
+            writeln!(w, "                    msg.{}.insert(key, value);", self.name)?;
+            writeln!(w, "                }},")?;
+            return Ok(());
+        }
+        match self.frequency {
+            Frequency::Optional if self.default.is_none() => {
+                writeln!(w, "                {} => msg.{} = Some({}),", self.text_pattern(), self.name, self.value_read("v", "text"))?;
+            }
+            Frequency::Optional | Frequency::Required => {
+                writeln!(w, "                {} => msg.{} = {},", self.text_pattern(), self.name, self.value_read("v", "text"))?;
+            }
+            Frequency::Repeated => {
+                writeln!(w, "                {} => for v in v.values() {{ msg.{}.push({}); }},",
+                         self.text_pattern(), self.name, self.value_read("v", "text"))?;
+            }
+        }
+        Ok(())
+    }
+
     fn has_unregular_default(&self, enums: &[Enumerator], msgs: &[Message]) -> bool {
         match self.default {
             None => false,
@@ -379,7 +785,7 @@ impl<'a> Field<'a> {
                 "bool" => *d != "false",
                 "Cow<'a, str>" => *d != "\"\"",
                 "Cow<'a, [u8]>" => *d != "[]",
-                t => match enums.iter().find(|e| e.name == self.typ) {
+                t => match enums.iter().find(|e| e.fqn() == self.typ) {
                     Some(e) => t != e.fields[0].0,
                     None => false, // Messages are regular defaults
                 }
@@ -391,8 +797,13 @@ impl<'a> Field<'a> {
         // borrow bytes and string
         if self.is_cow() { return true; }
 
+        // borrow map keys or values
+        if let Some((key, value)) = self.map_entry() {
+            return key.is_borrowed(msgs) || value.is_borrowed(msgs);
+        }
+
         // borrow messages that have lifetime (ie they have at least one borrowed field)
-        match msgs.iter().find(|m| m.name == self.typ) {
+        match msgs.iter().find(|m| m.fqn() == self.typ) {
             Some(ref m) if m.has_lifetime(msgs) => return true,
             _ => (),
         }
@@ -407,10 +818,65 @@ pub struct Message<'a> {
     pub fields: Vec<Field<'a>>,
     pub reserved_nums: Option<Vec<i32>>,
     pub reserved_names: Option<Vec<&'a str>>,
+    pub keep_unknown_fields: bool,
+    pub messages: Vec<Message<'a>>,
+    pub enums: Vec<Enumerator<'a>>,
+    pub oneofs: Vec<OneOf<'a>>,
+    /// fully qualified name of the package or message it is declared in
+    pub package: String,
+    /// rust path of the module it is generated in (e.g. `com::example::mod_Outer::`)
+    pub module: String,
+    /// generated module of the imported file defining it, if any
+    pub import: Option<String>,
 }
 
 impl<'a> Message<'a> {
-    fn write_definition<W: Write>(&self, w: &mut W, enums: &[Enumerator], msgs: &[Message]) -> Result<()> {
+    pub fn new(name: &'a str, events: Vec<MessageEvent<'a>>) -> Message<'a> {
+        let mut msg = Message {
+            name,
+            fields: Vec::new(),
+            reserved_nums: None,
+            reserved_names: None,
+            keep_unknown_fields: false,
+            messages: Vec::new(),
+            enums: Vec::new(),
+            oneofs: Vec::new(),
+            package: String::new(),
+            module: String::new(),
+            import: None,
+        };
+        for e in events {
+            match e {
+                MessageEvent::Field(f) => msg.fields.push(f),
+                MessageEvent::Group(f, m) => {
+                    msg.fields.push(f);
+                    msg.messages.push(m);
+                }
+                MessageEvent::OneOf(o) => msg.oneofs.push(o),
+                MessageEvent::Message(m) => msg.messages.push(m),
+                MessageEvent::Enumerator(e) => msg.enums.push(e),
+                MessageEvent::ReservedNums(nums) => {
+                    msg.reserved_nums.get_or_insert_with(Vec::new).extend(nums)
+                }
+                MessageEvent::ReservedNames(names) => {
+                    msg.reserved_names.get_or_insert_with(Vec::new).extend(names)
+                }
+            }
+        }
+        msg
+    }
+
+    /// The fully qualified name, e.g. `Outer.Inner`
+    pub fn fqn(&self) -> String {
+        fqn(&self.package, self.name)
+    }
+
+    /// The rust path, as seen from the generated module
+    fn rust_path(&self) -> String {
+        rust_path(&self.import, &self.module, self.name)
+    }
+
+    fn write_definition<W: Write>(&self, w: &mut W, enums: &[Enumerator], msgs: &[Message], no_std: bool) -> Result<()> {
         if self.can_derive_default(enums, msgs) {
             writeln!(w, "#[derive(Debug, Default, PartialEq, Clone)]")?;
         } else {
@@ -422,7 +888,17 @@ impl<'a> Message<'a> {
             writeln!(w, "pub struct {} {{", self.name)?;
         }
         for f in self.fields.iter().filter(|f| !f.deprecated) {
-            f.write_definition(w, msgs)?;
+            f.write_definition(w, msgs, no_std)?;
+        }
+        for o in &self.oneofs {
+            o.write_definition(w, self, msgs)?;
+        }
+        if self.keep_unknown_fields {
+            if self.has_lifetime(msgs) {
+                writeln!(w, "    pub unknown_fields: UnknownFields<'a>,")?;
+            } else {
+                writeln!(w, "    pub unknown_fields: UnknownFields<'static>,")?;
+            }
         }
         writeln!(w, "}}")?;
         Ok(())
@@ -434,15 +910,15 @@ impl<'a> Message<'a> {
 
     fn write_impl_message_read<W: Write>(&self, w: &mut W, enums: &[Enumerator], msgs: &[Message]) -> Result<()> {
         if self.has_lifetime(msgs) {
-            writeln!(w, "impl<'a> {}<'a> {{", self.name)?;
+            writeln!(w, "impl<'a> MessageRead<'a> for {}<'a> {{", self.name)?;
         } else {
-            writeln!(w, "impl {} {{", self.name)?;
+            writeln!(w, "impl<'a> MessageRead<'a> for {} {{", self.name)?;
         }
         self.write_from_reader(w, msgs)?;
         writeln!(w, "}}")?;
 
         if !self.can_derive_default(enums, msgs) {
-//             writeln!(w, "")?;
+//             writeln!(w)?;
 //             self.write_impl_default(w, msgs)?;
         }
         Ok(())
@@ -455,31 +931,140 @@ impl<'a> Message<'a> {
             writeln!(w, "impl MessageWrite for {} {{", self.name)?;
         }
         self.write_get_size(w, msgs)?;
-        writeln!(w, "")?;
+        writeln!(w)?;
         self.write_write_message(w, msgs)?;
         writeln!(w, "}}")?;
         Ok(())
     }
 
-    fn write_from_reader<W: Write>(&self, w: &mut W, msgs: &[Message]) -> Result<()> {
+    fn write_impl_json<W: Write>(&self, w: &mut W, msgs: &[Message]) -> Result<()> {
+        let fields = self.fields.iter().filter(|f| !f.deprecated).collect::<Vec<_>>();
+        if self.has_lifetime(msgs) {
+            writeln!(w, "impl<'a> JsonWrite for {}<'a> {{", self.name)?;
+        } else {
+            writeln!(w, "impl JsonWrite for {} {{", self.name)?;
+        }
+        writeln!(w, "    fn write_json(&self, w: &mut JsonWriter) {{")?;
+        writeln!(w, "        w.begin_object();")?;
+        for f in &fields {
+            f.write_write_json(w, msgs)?;
+        }
+        for o in &self.oneofs {
+            o.write_write_json(w, self)?;
+        }
+        writeln!(w, "        w.end_object();")?;
+        writeln!(w, "    }}")?;
+        writeln!(w, "}}")?;
+        writeln!(w)?;
+
         if self.has_lifetime(msgs) {
-            writeln!(w, "    pub fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {{")?;
+            writeln!(w, "impl<'a> JsonRead for {}<'a> {{", self.name)?;
         } else {
-            writeln!(w, "    pub fn from_reader(r: &mut BytesReader, bytes: &[u8]) -> Result<Self> {{")?;
+            writeln!(w, "impl JsonRead for {} {{", self.name)?;
+        }
+        writeln!(w, "    fn from_json(v: &Value) -> Result<Self> {{")?;
+        if fields.is_empty() && self.oneofs.is_empty() {
+            writeln!(w, "        let msg = Self::default();")?;
+        } else {
+            writeln!(w, "        let mut msg = Self::default();")?;
+        }
+        writeln!(w, "        for &(ref k, ref v) in v.as_object()? {{")?;
+        writeln!(w, "            if v.is_null() {{ continue; }}")?;
+        writeln!(w, "            match &**k {{")?;
+        for f in &fields {
+            f.write_match_json(w)?;
         }
+        for o in &self.oneofs {
+            o.write_match_json(w, self)?;
+        }
+        writeln!(w, "                _ => return Err(ErrorKind::Json(format!(\"unknown field '{{}}'\", k)).into()),")?;
+        writeln!(w, "            }}")?;
+        writeln!(w, "        }}")?;
+        writeln!(w, "        Ok(msg)")?;
+        writeln!(w, "    }}")?;
+        writeln!(w, "}}")?;
+        Ok(())
+    }
+
+    fn write_impl_text<W: Write>(&self, w: &mut W, msgs: &[Message]) -> Result<()> {
+        let fields = self.fields.iter().filter(|f| !f.deprecated).collect::<Vec<_>>();
+        if self.has_lifetime(msgs) {
+            writeln!(w, "impl<'a> TextWrite for {}<'a> {{", self.name)?;
+        } else {
+            writeln!(w, "impl TextWrite for {} {{", self.name)?;
+        }
+        writeln!(w, "    fn write_text(&self, w: &mut TextWriter) {{")?;
+        writeln!(w, "        w.begin_message();")?;
+        for f in &fields {
+            f.write_write_text(w)?;
+        }
+        for o in &self.oneofs {
+            o.write_write_text(w, self)?;
+        }
+        writeln!(w, "        w.end_message();")?;
+        writeln!(w, "    }}")?;
+        writeln!(w, "}}")?;
+        writeln!(w)?;
+
+        if self.has_lifetime(msgs) {
+            writeln!(w, "impl<'a> TextRead for {}<'a> {{", self.name)?;
+        } else {
+            writeln!(w, "impl TextRead for {} {{", self.name)?;
+        }
+        writeln!(w, "    fn from_text(v: &TextValue) -> Result<Self> {{")?;
+        if fields.is_empty() && self.oneofs.is_empty() {
+            writeln!(w, "        if let Some(f) = v.as_message()?.first() {{")?;
+            writeln!(w, "            return Err(f.unknown());")?;
+            writeln!(w, "        }}")?;
+            writeln!(w, "        Ok(Self::default())")?;
+        } else {
+            writeln!(w, "        let mut msg = Self::default();")?;
+            writeln!(w, "        for f in v.as_message()? {{")?;
+            writeln!(w, "            let v = &f.value;")?;
+            writeln!(w, "            match &*f.name {{")?;
+            for f in &fields {
+                f.write_match_text(w)?;
+            }
+            for o in &self.oneofs {
+                o.write_match_text(w, self)?;
+            }
+            writeln!(w, "                _ => return Err(f.unknown()),")?;
+            writeln!(w, "            }}")?;
+            writeln!(w, "        }}")?;
+            writeln!(w, "        Ok(msg)")?;
+        }
+        writeln!(w, "    }}")?;
+        writeln!(w, "}}")?;
+        Ok(())
+    }
+
+    fn write_from_reader<W: Write>(&self, w: &mut W, msgs: &[Message]) -> Result<()> {
+        writeln!(w, "    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {{")?;
         writeln!(w, "        let mut msg = Self::default();")?;
         writeln!(w, "        while !r.is_eof() {{")?;
         writeln!(w, "            match r.next_tag(bytes) {{")?;
         for f in self.fields.iter().filter(|f| !f.deprecated) {
             write!(w, "                ")?;
-            if f.is_cow() {
-                f.write_match_tag_borrowed(w, msgs)?;
+            if f.map.is_some() {
+                f.write_match_tag_map(w, self, msgs)?;
+            } else if f.is_cow() {
+                f.write_match_tag_borrowed(w, self, msgs)?;
             } else {
-                f.write_match_tag_owned(w, msgs)?;
+                f.write_match_tag_owned(w, self, msgs)?;
             }
         }
-        writeln!(w, "                Ok(t) => {{ r.read_unknown(bytes, t)?; }}")?;
-        writeln!(w, "                Err(e) => return Err(e),")?;
+        for o in &self.oneofs {
+            o.write_match_tag(w, self, msgs)?;
+        }
+        let path = format!(".map_err(|e| e.in_message(\"{}\"))", self.name);
+        if !self.keep_unknown_fields {
+            writeln!(w, "                Ok(t) => {{ r.read_unknown(bytes, t){}?; }}", path)?;
+        } else if self.has_lifetime(msgs) {
+            writeln!(w, "                Ok(t) => msg.unknown_fields.push(t, Cow::Borrowed(r.read_unknown_field(bytes, t){}?)),", path)?;
+        } else {
+            writeln!(w, "                Ok(t) => msg.unknown_fields.push(t, Cow::Owned(r.read_unknown_field(bytes, t){}?.to_vec())),", path)?;
+        }
+        writeln!(w, "                Err(e) => return Err(e.in_message(\"{}\")),", self.name)?;
         writeln!(w, "            }}")?;
         writeln!(w, "        }}")?;
         writeln!(w, "        Ok(msg)")?;
@@ -487,20 +1072,65 @@ impl<'a> Message<'a> {
         Ok(())
     }
 
+    /// Checks if the size of the message depends on the sizes of nested messages
+    fn has_nested_messages(&self, msgs: &[Message]) -> bool {
+        self.fields.iter().filter(|f| !f.deprecated).any(|f| f.has_nested_message(msgs)) ||
+            self.oneofs.iter().flat_map(|o| o.fields.iter()).any(|f| f.has_nested_message(msgs))
+    }
+
     fn write_get_size<W: Write>(&self, w: &mut W, msgs: &[Message]) -> Result<()> {
         writeln!(w, "    fn get_size(&self) -> usize {{")?;
-        for (i, f) in self.fields.iter().filter(|f| !f.deprecated).enumerate() {
-            f.write_get_size(w, msgs, i == 0)?;
-        }
+        self.write_size_terms(w, msgs, false)?;
         writeln!(w, "    }}")?;
+        if self.has_nested_messages(msgs) {
+            // nested messages sizes are recorded, so `Writer::write_message` only computes them once
+            writeln!(w)?;
+            writeln!(w, "    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {{")?;
+            self.write_size_terms(w, msgs, true)?;
+            writeln!(w, "    }}")?;
+        }
+        Ok(())
+    }
+
+    /// Writes the sum of the sizes of the fields, see `Field::write_get_size`
+    fn write_size_terms<W: Write>(&self, w: &mut W, msgs: &[Message], cached: bool) -> Result<()> {
+        let fields = self.fields.iter().filter(|f| !f.deprecated).collect::<Vec<_>>();
+        let mut is_first = true;
+        for (i, f) in fields.iter().enumerate() {
+            let is_last = i + 1 == fields.len() && self.oneofs.is_empty() && !self.keep_unknown_fields;
+            f.write_get_size(w, msgs, is_first, is_last, cached)?;
+            is_first = false;
+        }
+        if !self.oneofs.is_empty() && is_first {
+            writeln!(w, "        0")?;
+            is_first = false;
+        }
+        for o in &self.oneofs {
+            o.write_get_size(w, self, msgs, cached)?;
+        }
+        if self.keep_unknown_fields {
+            if is_first {
+                writeln!(w, "        self.unknown_fields.get_size()")?;
+            } else {
+                writeln!(w, "        + self.unknown_fields.get_size()")?;
+            }
+        } else if is_first {
+            writeln!(w, "        0")?;
+        }
         Ok(())
     }
 
     fn write_write_message<W: Write>(&self, w: &mut W, msgs: &[Message]) -> Result<()> {
-        writeln!(w, "    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {{")?;
+        writeln!(w, "    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {{")?;
         for f in self.fields.iter().filter(|f| !f.deprecated) {
             f.write_write(w, msgs)?;
         }
+        for o in &self.oneofs {
+            o.write_write(w, self, msgs)?;
+        }
+        if self.keep_unknown_fields {
+            writeln!(w, "        r.write_unknown_fields(&self.unknown_fields)?;")?;
+        }
         writeln!(w, "        Ok(())")?;
         writeln!(w, "    }}")?;
         Ok(())
@@ -526,17 +1156,19 @@ impl<'a> Message<'a> {
 //     }
 
     fn is_leaf(&self, leaf_messages: &[&str], msgs: &[Message]) -> bool {
-        self.fields.iter().all(|f| f.is_leaf(leaf_messages, msgs) || f.deprecated)
+        self.fields.iter().all(|f| f.is_leaf(leaf_messages, msgs) || f.deprecated) &&
+            self.oneofs.iter().all(|o| o.fields.iter().all(|f| f.is_leaf(leaf_messages, msgs)))
     }
 
     fn has_lifetime(&self, msgs: &[Message]) -> bool {
-        self.fields.iter().any(|f| f.typ != self.name && f.is_borrowed(msgs))
+        self.fields.iter().any(|f| f.typ != self.fqn() && f.is_borrowed(msgs)) ||
+            self.oneofs.iter().any(|o| o.borrows(self, msgs))
     }
 
     fn sanity_checks(&self) -> Result<()> {
         // checks for reserved fields
         for f in &self.fields {
-            if self.reserved_names.as_ref().map_or(false, |names| names.contains(&f.name)) || 
+            if self.reserved_names.as_ref().map_or(false, |names| names.iter().any(|n| *n == f.name)) || 
                 self.reserved_nums.as_ref().map_or(false, |nums| nums.contains(&f.number)) {
                 return Err(ErrorKind::InvalidMessage(
                         format!("Error in message {}\nField {:?} conflict with reserved fields",
@@ -551,9 +1183,25 @@ impl<'a> Message<'a> {
 pub struct Enumerator<'a> {
     pub name: &'a str,
     pub fields: Vec<(&'a str, i32)>,
+    /// fully qualified name of the package or message it is declared in
+    pub package: String,
+    /// rust path of the module it is generated in (e.g. `com::example::mod_Outer::`)
+    pub module: String,
+    /// generated module of the imported file defining it, if any
+    pub import: Option<String>,
 }
 
 impl<'a> Enumerator<'a> {
+    /// The fully qualified name, e.g. `Outer.Kind`
+    pub fn fqn(&self) -> String {
+        fqn(&self.package, self.name)
+    }
+
+    /// The rust path, as seen from the generated module
+    fn rust_path(&self) -> String {
+        rust_path(&self.import, &self.module, self.name)
+    }
+
     fn write_definition<W: Write>(&self, w: &mut W) -> Result<()> {
         writeln!(w, "#[derive(Debug, PartialEq, Eq, Clone, Copy)]")?;
         writeln!(w, "pub enum {} {{", self.name)?;
@@ -587,12 +1235,336 @@ impl<'a> Enumerator<'a> {
         writeln!(w, "}}")?;
         Ok(())
     }
+
+    fn write_impl_json<W: Write>(&self, w: &mut W) -> Result<()> {
+        writeln!(w, "impl JsonWrite for {} {{", self.name)?;
+        writeln!(w, "    fn write_json(&self, w: &mut JsonWriter) {{")?;
+        writeln!(w, "        w.write_string(match *self {{")?;
+        for &(f, _) in &self.fields {
+            writeln!(w, "            {}::{} => \"{}\",", self.name, f, f)?;
+        }
+        writeln!(w, "        }});")?;
+        writeln!(w, "    }}")?;
+        writeln!(w, "}}")?;
+        writeln!(w)?;
+        writeln!(w, "impl JsonRead for {} {{", self.name)?;
+        writeln!(w, "    fn from_json(v: &Value) -> Result<Self> {{")?;
+        writeln!(w, "        match *v {{")?;
+        writeln!(w, "            Value::String(ref s) => match &**s {{")?;
+        for &(f, _) in &self.fields {
+            writeln!(w, "                \"{}\" => Ok({}::{}),", f, self.name, f)?;
+        }
+        writeln!(w, "                _ => Err(ErrorKind::Json(format!(\"unknown {} value '{{}}'\", s)).into()),", self.name)?;
+        writeln!(w, "            }},")?;
+        writeln!(w, "            _ => v.as_i32().map({}::from),", self.name)?;
+        writeln!(w, "        }}")?;
+        writeln!(w, "    }}")?;
+        writeln!(w, "}}")?;
+        Ok(())
+    }
+    fn write_impl_text<W: Write>(&self, w: &mut W) -> Result<()> {
+        writeln!(w, "impl TextWrite for {} {{", self.name)?;
+        writeln!(w, "    fn write_text(&self, w: &mut TextWriter) {{")?;
+        writeln!(w, "        w.write_ident(match *self {{")?;
+        for &(f, _) in &self.fields {
+            writeln!(w, "            {}::{} => \"{}\",", self.name, f, f)?;
+        }
+        writeln!(w, "        }});")?;
+        writeln!(w, "    }}")?;
+        writeln!(w, "}}")?;
+        writeln!(w)?;
+        writeln!(w, "impl TextRead for {} {{", self.name)?;
+        writeln!(w, "    fn from_text(v: &TextValue) -> Result<Self> {{")?;
+        writeln!(w, "        match v.as_ident() {{")?;
+        for &(f, _) in &self.fields {
+            writeln!(w, "            Ok(\"{}\") => Ok({}::{}),", f, self.name, f)?;
+        }
+        writeln!(w, "            Ok(_) => Err(v.unknown_value(\"{}\")),", self.name)?;
+        writeln!(w, "            Err(_) => v.as_i32().map({}::from),", self.name)?;
+        writeln!(w, "        }}")?;
+        writeln!(w, "    }}")?;
+        writeln!(w, "}}")?;
+        Ok(())
+    }
+}
+
+/// A set of fields of which at most one can be set at a time
+///
+/// It is generated as an enum, in the module of its message, with a `None` variant
+#[derive(Debug, Clone)]
+pub struct OneOf<'a> {
+    pub name: &'a str,
+    pub fields: Vec<Field<'a>>,
+}
+
+impl<'a> OneOf<'a> {
+    fn borrows(&self, msg: &Message, msgs: &[Message]) -> bool {
+        self.fields.iter().any(|f| f.typ != msg.fqn() && f.is_borrowed(msgs))
+    }
+
+    fn has_lifetime(&self, msg: &Message, msgs: &[Message]) -> bool {
+        self.borrows(msg, msgs) || 
+            (self.fields.iter().any(|f| f.typ == msg.fqn()) && msg.has_lifetime(msgs))
+    }
+
+    /// The enum type path, as seen from the module of the message
+    fn rust_type(&self, msg: &Message, msgs: &[Message]) -> String {
+        if self.has_lifetime(msg, msgs) {
+            format!("mod_{}::OneOf{}<'a>", msg.name, self.name)
+        } else {
+            format!("mod_{}::OneOf{}", msg.name, self.name)
+        }
+    }
+
+    fn variant(&self, msg: &Message, f: &Field) -> String {
+        format!("mod_{}::OneOf{}::{}", msg.name, self.name, f.name)
+    }
+
+    fn write_definition<W: Write>(&self, w: &mut W, msg: &Message, msgs: &[Message]) -> Result<()> {
+        writeln!(w, "    pub {}: {},", self.name, self.rust_type(msg, msgs))?;
+        Ok(())
+    }
+
+    fn write_definition_enum<W: Write>(&self, w: &mut W, msg: &Message, msgs: &[Message]) -> Result<()> {
+        let lifetime = if self.has_lifetime(msg, msgs) { "<'a>" } else { "" };
+        writeln!(w, "#[derive(Debug, PartialEq, Clone)]")?;
+        writeln!(w, "pub enum OneOf{}{} {{", self.name, lifetime)?;
+        for f in &self.fields {
+            if f.boxed {
+                writeln!(w, "    {}(Box<{}>),", f.name, f.rust_type(msgs))?;
+            } else {
+                writeln!(w, "    {}({}),", f.name, f.rust_type(msgs))?;
+            }
+        }
+        writeln!(w, "    None,")?;
+        writeln!(w, "}}")?;
+        writeln!(w)?;
+        writeln!(w, "impl{} Default for OneOf{}{} {{", lifetime, self.name, lifetime)?;
+        writeln!(w, "    fn default() -> Self {{")?;
+        writeln!(w, "        OneOf{}::None", self.name)?;
+        writeln!(w, "    }}")?;
+        writeln!(w, "}}")?;
+        Ok(())
+    }
+
+    fn write_match_tag<W: Write>(&self, w: &mut W, msg: &Message, msgs: &[Message]) -> Result<()> {
+        for f in &self.fields {
+            let value = if f.is_cow() {
+                format!("Cow::Borrowed(r.{}{}?)", f.read_fn(msgs), f.map_err_path(msg))
+            } else if f.boxed {
+                format!("Box::new(r.{}{}?)", f.read_fn(msgs), f.map_err_path(msg))
+            } else {
+                format!("r.{}{}?", f.read_fn(msgs), f.map_err_path(msg))
+            };
+            writeln!(w, "                Ok({}) => msg.{} = {}({}),", f.tag(msgs), self.name, self.variant(msg, f), value)?;
+        }
+        Ok(())
+    }
+
+    fn write_get_size<W: Write>(&self, w: &mut W, msg: &Message, msgs: &[Message], cached: bool) -> Result<()> {
+        writeln!(w, "        + match self.{} {{", self.name)?;
+        for f in &self.fields {
+            if f.is_fixed_size(msgs) {
+                write!(w, "            {}(_) => ", self.variant(msg, f))?;
+            } else {
+                write!(w, "            {}(ref m) => ", self.variant(msg, f))?;
+            }
+            f.write_inner_get_size(w, msgs, "m", "*", cached)?;
+            writeln!(w, ",")?;
+        }
+        writeln!(w, "            mod_{}::OneOf{}::None => 0,", msg.name, self.name)?;
+        writeln!(w, "        }}")?;
+        Ok(())
+    }
+
+    fn write_write<W: Write>(&self, w: &mut W, msg: &Message, msgs: &[Message]) -> Result<()> {
+        writeln!(w, "        match self.{} {{", self.name)?;
+        for f in &self.fields {
+            let wire_type = f.wire_type_num_non_packed(msgs);
+            let r = if wire_type == 2 || f.is_group {
+                if f.boxed { "&**" } else { "" }
+            } else {
+                "*"
+            };
+            let as_enum = if f.is_enum(msgs) { " as i32" } else { "" };
+            writeln!(w, "            {}(ref m) => {{ r.write_{}_with_tag({}, {}m{})? }},",
+                     self.variant(msg, f), f.get_type(msgs), f.tag(msgs), r, as_enum)?;
+        }
+        writeln!(w, "            mod_{}::OneOf{}::None => {{}},", msg.name, self.name)?;
+        writeln!(w, "        }}")?;
+        Ok(())
+    }
+
+    fn write_write_json<W: Write>(&self, w: &mut W, msg: &Message) -> Result<()> {
+        writeln!(w, "        match self.{} {{", self.name)?;
+        for f in &self.fields {
+            writeln!(w, "            {}(ref s) => {{ w.key(\"{}\"); {}; }}",
+                     self.variant(msg, f), f.json_name(), f.value_write("s", true, "json"))?;
+        }
+        writeln!(w, "            mod_{}::OneOf{}::None => {{}},", msg.name, self.name)?;
+        writeln!(w, "        }}")?;
+        Ok(())
+    }
+
+    fn write_match_json<W: Write>(&self, w: &mut W, msg: &Message) -> Result<()> {
+        for f in &self.fields {
+            writeln!(w, "                {} => msg.{} = {}({}),", f.json_pattern(), self.name, self.variant(msg, f), f.value_read("v", "json"))?;
+        }
+        Ok(())
+    }
+    fn write_write_text<W: Write>(&self, w: &mut W, msg: &Message) -> Result<()> {
+        writeln!(w, "        match self.{} {{", self.name)?;
+        for f in &self.fields {
+            writeln!(w, "            {}(ref s) => {{ w.key(\"{}\"); {}; }}",
+                     self.variant(msg, f), f.name, f.value_write("s", true, "text"))?;
+        }
+        writeln!(w, "            mod_{}::OneOf{}::None => {{}},", msg.name, self.name)?;
+        writeln!(w, "        }}")?;
+        Ok(())
+    }
+
+    fn write_match_text<W: Write>(&self, w: &mut W, msg: &Message) -> Result<()> {
+        for f in &self.fields {
+            writeln!(w, "                \"{}\" => msg.{} = {}({}),", f.name, self.name, self.variant(msg, f), f.value_read("v", "text"))?;
+        }
+        Ok(())
+    }
+}
+
+/// A `service` definition
+///
+/// It is generated as a trait with one method per rpc, and a dispatcher routing encoded
+/// requests to an implementation of the trait
+#[derive(Debug, Clone)]
+pub struct Service<'a> {
+    pub name: &'a str,
+    pub methods: Vec<RpcMethod<'a>>,
+    /// fully qualified name of the package it is declared in
+    pub package: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct RpcMethod<'a> {
+    pub name: &'a str,
+    /// request message (fully qualified once resolved)
+    pub input: String,
+    pub input_stream: bool,
+    /// response message (fully qualified once resolved)
+    pub output: String,
+    pub output_stream: bool,
+}
+
+impl<'a> Service<'a> {
+    fn resolve_types(&mut self, names: &[(String, String)]) -> Result<()> {
+        for m in &mut self.methods {
+            for typ in [&mut m.input, &mut m.output] {
+                *typ = match resolve_name(typ, &self.package, names) {
+                    Some((fqn, _)) => fqn.clone(),
+                    None => return Err(ErrorKind::UnknownType(typ.clone(), m.name.to_string()).into()),
+                };
+            }
+        }
+        Ok(())
+    }
+
+    fn write_definition<W: Write>(&self, w: &mut W, msgs: &[Message]) -> Result<()> {
+        writeln!(w, "pub trait {} {{", self.name)?;
+        for m in &self.methods {
+            let (input, input_lifetime) = rpc_type(&m.input, m.input_stream, msgs);
+            let (output, output_lifetime) = rpc_type(&m.output, m.output_stream, msgs);
+            let arg = if m.input_stream { "requests" } else { "request" };
+            if input_lifetime || output_lifetime {
+                writeln!(w, "    fn {}<'a>(&mut self, {}: {}) -> Result<{}>;", m.name, arg, input, output)?;
+            } else {
+                writeln!(w, "    fn {}(&mut self, {}: {}) -> Result<{}>;", m.name, arg, input, output)?;
+            }
+        }
+        writeln!(w, "}}")?;
+        Ok(())
+    }
+
+    fn write_dispatcher<W: Write>(&self, w: &mut W, msgs: &[Message]) -> Result<()> {
+        writeln!(w, "pub struct {}Dispatcher<S: {}> {{", self.name, self.name)?;
+        writeln!(w, "    pub service: S,")?;
+        writeln!(w, "}}")?;
+        writeln!(w)?;
+        writeln!(w, "impl<S: {}> {}Dispatcher<S> {{", self.name, self.name)?;
+        writeln!(w, "    pub fn new(service: S) -> Self {{")?;
+        writeln!(w, "        {}Dispatcher {{ service: service }}", self.name)?;
+        writeln!(w, "    }}")?;
+        writeln!(w)?;
+        if self.methods.is_empty() {
+            writeln!(w, "    pub fn dispatch(&mut self, method: &str, _: &[u8]) -> Result<Vec<u8>> {{")?;
+            writeln!(w, "        Err(ErrorKind::UnknownMethod(method.to_string()).into())")?;
+            writeln!(w, "    }}")?;
+            writeln!(w, "}}")?;
+            return Ok(());
+        }
+        writeln!(w, "    pub fn dispatch(&mut self, method: &str, request: &[u8]) -> Result<Vec<u8>> {{")?;
+        writeln!(w, "        let mut r = BytesReader::from_bytes(request);")?;
+        writeln!(w, "        let mut response = Vec::new();")?;
+        writeln!(w, "        {{")?;
+        writeln!(w, "            let mut w = Writer::new(&mut response);")?;
+        writeln!(w, "            match method {{")?;
+        let service = fqn(&self.package, self.name);
+        for m in &self.methods {
+            let input = msgs.iter().find(|msg| msg.fqn() == m.input).map(|msg| msg.rust_path()).unwrap();
+            writeln!(w, "                \"{}\" | \"/{}/{}\" => {{", m.name, service, m.name)?;
+            if m.input_stream {
+                writeln!(w, "                    let requests = r.messages(request, {}::from_reader).collect::<Result<Vec<_>>>()?;", input)?;
+            } else {
+                writeln!(w, "                    let requests = {}::from_reader(&mut r, request)?;", input)?;
+            }
+            if m.output_stream {
+                writeln!(w, "                    w.write_messages(&self.service.{}(requests)?)?;", m.name)?;
+            } else {
+                writeln!(w, "                    self.service.{}(requests)?.write_message(&mut w)?;", m.name)?;
+            }
+            writeln!(w, "                }}")?;
+        }
+        writeln!(w, "                _ => return Err(ErrorKind::UnknownMethod(method.to_string()).into()),")?;
+        writeln!(w, "            }}")?;
+        writeln!(w, "        }}")?;
+        writeln!(w, "        Ok(response)")?;
+        writeln!(w, "    }}")?;
+        writeln!(w, "}}")?;
+        Ok(())
+    }
+}
+
+/// The rust type of a rpc request or response, and whether it has a lifetime
+fn rpc_type(typ: &str, stream: bool, msgs: &[Message]) -> (String, bool) {
+    let m = msgs.iter().find(|m| m.fqn() == typ).unwrap();
+    let has_lifetime = m.has_lifetime(msgs);
+    let mut rust_type = m.rust_path();
+    if has_lifetime {
+        rust_type.push_str("<'a>");
+    }
+    if stream {
+        rust_type = format!("Vec<{}>", rust_type);
+    }
+    (rust_type, has_lifetime)
+}
+
+/// Everything which can be declared in a message body
+#[derive(Debug)]
+pub enum MessageEvent<'a> {
+    Field(Field<'a>),
+    Group(Field<'a>, Message<'a>),
+    OneOf(OneOf<'a>),
+    Message(Message<'a>),
+    Enumerator(Enumerator<'a>),
+    ReservedNums(Vec<i32>),
+    ReservedNames(Vec<&'a str>),
 }
 
 #[derive(Debug)]
 pub enum MessageOrEnum<'a> {
     Msg(Message<'a>),
     Enum(Enumerator<'a>),
+    Import(&'a str),
+    Package(&'a str),
+    Service(Service<'a>),
     Ignore,
 }
 
@@ -602,25 +1574,79 @@ pub struct FileDescriptor<'a> {
     pub message_and_enums: Vec<MessageOrEnum<'a>>,
     pub messages: Vec<Message<'a>>,
     pub enums: Vec<Enumerator<'a>>,
+    pub imports: Vec<&'a str>,
+    /// the `package` of the file, generated as nested modules (empty if none)
+    pub package: &'a str,
+    pub services: Vec<Service<'a>>,
+    /// generates the JSON conversions of messages and enums
+    pub json: bool,
+    /// generates the text format conversions of messages and enums
+    pub text: bool,
+    /// generates code depending on `core` and `alloc` only
+    pub no_std: bool,
 }
 
 impl<'a> FileDescriptor<'a> {
 
+    /// Parses a .proto file
+    ///
+    /// Types are not resolved yet: imported files must be added first (`import`) then `resolve`
+    /// must be called.
     pub fn from_bytes(b: &'a [u8]) -> Result<FileDescriptor<'a>> {
-        let mut f = file_descriptor(b).to_result()?;
-        f.break_cycles();
-        f.set_defaults();
-        for m in &f.messages {
+        FileDescriptor::parse(b, "<input>")
+    }
+
+    /// Parses a .proto file, named `file` in parse errors
+    pub fn parse(b: &'a [u8], file: &str) -> Result<FileDescriptor<'a>> {
+        let mut f = parser::parse(b).map_err(|(offset, expected)| parse_error(b, file, offset, expected))?;
+        f.flatten();
+        Ok(f)
+    }
+
+    /// Makes the messages and enums of an imported (and already resolved) file available
+    ///
+    /// `module` is the name of the module generated for the imported file
+    pub fn import(&mut self, imported: &FileDescriptor<'a>, module: &str) {
+        for m in &imported.messages {
+            let mut m = m.clone();
+            m.import = m.import.or_else(|| Some(module.to_string()));
+            if !self.messages.iter().any(|n| n.import == m.import && n.fqn() == m.fqn()) {
+                self.messages.push(m);
+            }
+        }
+        for e in &imported.enums {
+            let mut e = e.clone();
+            e.import = e.import.or_else(|| Some(module.to_string()));
+            if !self.enums.iter().any(|n| n.import == e.import && n.fqn() == e.fqn()) {
+                self.enums.push(e);
+            }
+        }
+    }
+
+    /// Resolves the types of all fields and checks the messages
+    pub fn resolve(&mut self) -> Result<()> {
+        self.resolve_types()?;
+        self.break_cycles();
+        self.set_defaults();
+        for m in self.messages.iter().filter(|m| m.import.is_none()) {
             m.sanity_checks()?;
         }
-        Ok(f)
+        Ok(())
+    }
+
+    /// Makes every message keep the fields it doesn't know and write them back
+    pub fn keep_unknown_fields(&mut self) {
+        for m in &mut self.messages {
+            m.keep_unknown_fields = true;
+        }
     }
 
     fn set_defaults(&mut self) {
         // if proto3, then changes several defaults
         if let Syntax::Proto3 = self.syntax {
-            for m in &mut self.messages {
+            for m in self.messages.iter_mut().filter(|m| m.import.is_none()) {
                 for f in &mut m.fields {
+                    f.proto3 = true;
                     if f.packed.is_none() { 
                         if let Frequency::Repeated = f.frequency { 
                             f.packed = Some(true); 
@@ -635,58 +1661,229 @@ impl<'a> FileDescriptor<'a> {
     }
 
     pub fn write<W: Write>(&self, w: &mut W, filename: &str) -> Result<()> {
-        
-        println!("Found {} messages, and {} enums", self.messages.len(), self.enums.len());
-
         writeln!(w, "//! Automatically generated rust module for '{}' file", filename)?;
-        writeln!(w, "")?;
+        writeln!(w)?;
         writeln!(w, "#![allow(non_snake_case)]")?;
         writeln!(w, "#![allow(non_upper_case_globals)]")?;
         writeln!(w, "#![allow(non_camel_case_types)]")?;
-        writeln!(w, "")?;
-        writeln!(w, "use std::io::{{Write}};")?;
-        writeln!(w, "use std::borrow::Cow;")?;
-        writeln!(w, "use quick_protobuf::{{MessageWrite, BytesReader, Writer, Result}};")?;
+        if self.no_std {
+            writeln!(w, "#![allow(unused_imports)]")?;
+        }
+        writeln!(w)?;
+        let has_maps = self.own_messages().any(|m| m.fields.iter().any(|f| f.map.is_some()));
+        if self.no_std {
+            writeln!(w, "use alloc::borrow::Cow;")?;
+            writeln!(w, "use alloc::boxed::Box;")?;
+            writeln!(w, "use alloc::format;")?;
+            writeln!(w, "use alloc::string::{{String, ToString}};")?;
+            writeln!(w, "use alloc::vec::Vec;")?;
+            if has_maps {
+                writeln!(w, "use alloc::collections::BTreeMap;")?;
+            }
+        } else {
+            writeln!(w, "use std::borrow::Cow;")?;
+            if has_maps {
+                writeln!(w, "use std::collections::HashMap;")?;
+            }
+        }
+        if self.own_messages().any(|m| m.keep_unknown_fields) {
+            writeln!(w, "use quick_protobuf::{{MessageRead, MessageWrite, BytesReader, Writer, WriterBackend, Result, UnknownFields}};")?;
+        } else {
+            writeln!(w, "use quick_protobuf::{{MessageRead, MessageWrite, BytesReader, Writer, WriterBackend, Result}};")?;
+        }
         writeln!(w, "use quick_protobuf::sizeofs::*;")?;
+        if !self.services.is_empty() || self.json {
+            writeln!(w, "use quick_protobuf::errors::ErrorKind;")?;
+        }
+        if self.json {
+            writeln!(w, "use quick_protobuf::json::{{JsonRead, JsonWrite, JsonWriter, Value}};")?;
+        }
+        if self.text {
+            writeln!(w, "use quick_protobuf::text::{{TextRead, TextWrite, TextWriter, Value as TextValue}};")?;
+        }
+        for module in self.used_imports() {
+            writeln!(w, "use super::{};", module)?;
+        }
 
-        for m in &self.enums {
-            writeln!(w, "")?;
+        if self.package.is_empty() {
+            return self.write_package(w, "");
+        }
+        // enums only need imports for their json and text implementations
+        let uses_imports = self.own_messages().next().is_some() || !self.services.is_empty() ||
+            ((self.json || self.text) && self.enums.iter().any(|e| e.import.is_none()));
+        for p in self.package.split('.') {
+            writeln!(w)?;
+            writeln!(w, "pub mod {} {{", p)?;
+            if uses_imports {
+                writeln!(w)?;
+                writeln!(w, "use super::*;")?;
+            }
+        }
+        self.write_package(w, self.package)?;
+        for _ in self.package.split('.') {
+            writeln!(w)?;
+            writeln!(w, "}}")?;
+        }
+        Ok(())
+    }
+
+    /// Messages defined in this file (not imported)
+    fn own_messages<'b>(&'b self) -> ::std::iter::Filter<::std::slice::Iter<'b, Message<'a>>, fn(&&Message) -> bool> {
+        fn is_own(m: &&Message) -> bool { m.import.is_none() }
+        self.messages.iter().filter(is_own)
+    }
+
+    /// Modules of the imported files whose types are used by this file
+    fn used_imports(&self) -> Vec<&str> {
+        let mut modules = Vec::new();
+        let imports = self.messages.iter().filter_map(|m| m.import.as_ref())
+            .chain(self.enums.iter().filter_map(|e| e.import.as_ref()));
+        for import in imports {
+            let prefix = format!("{}::", import);
+            let is_used = self.own_messages()
+                .flat_map(|m| m.fields.iter().chain(m.oneofs.iter().flat_map(|o| o.fields.iter())))
+                .flat_map(|f| Some(f).into_iter().chain(f.map_entry().map(|(_, v)| v)))
+                .any(|f| f.typ_path.as_ref().is_some_and(|p| p.starts_with(&prefix))) ||
+                self.services.iter()
+                    .flat_map(|s| s.methods.iter().flat_map(|m| vec![&m.input, &m.output]))
+                    .any(|t| self.messages.iter().any(|m| m.fqn() == *t && m.import.as_ref() == Some(import)));
+            if is_used && !modules.contains(&&**import) {
+                modules.push(&**import);
+            }
+        }
+        modules
+    }
+
+    /// Writes the enums and messages declared in `package` (either the file or a message),
+    /// each message being followed by the module of its nested types
+    fn write_package<W: Write>(&self, w: &mut W, package: &str) -> Result<()> {
+        for m in self.enums.iter().filter(|e| e.package == package && e.import.is_none()) {
+            writeln!(w)?;
             m.write_definition(w)?;
-            writeln!(w, "")?;
+            writeln!(w)?;
             m.write_impl_default(w)?;
-            writeln!(w, "")?;
+            writeln!(w)?;
             m.write_from_i32(w)?;
+            if self.json {
+                writeln!(w)?;
+                m.write_impl_json(w)?;
+            }
+            if self.text {
+                writeln!(w)?;
+                m.write_impl_text(w)?;
+            }
         }
-        println!("Wrote enums");
-        for m in &self.messages {
-            writeln!(w, "")?;
-            m.write_definition(w, &self.enums, &self.messages)?;
-            println!("Wrote messages definitions");
-            writeln!(w, "")?;
+        for m in self.own_messages().filter(|m| m.package == package) {
+            writeln!(w)?;
+            m.write_definition(w, &self.enums, &self.messages, self.no_std)?;
+            writeln!(w)?;
             m.write_impl_message_read(w, &self.enums, &self.messages)?;
-            println!("Wrote messages impl read");
-            writeln!(w, "")?;
+            writeln!(w)?;
             m.write_impl_message_write(w, &self.messages)?;
-            println!("Wrote messages impl write");
+            if self.json {
+                writeln!(w)?;
+                m.write_impl_json(w, &self.messages)?;
+            }
+            if self.text {
+                writeln!(w)?;
+                m.write_impl_text(w, &self.messages)?;
+            }
+            self.write_mod(w, m)?;
+        }
+        for s in self.services.iter().filter(|s| s.package == package) {
+            writeln!(w)?;
+            s.write_definition(w, &self.messages)?;
+            writeln!(w)?;
+            s.write_dispatcher(w, &self.messages)?;
         }
-        println!("Wrote messages");
         Ok(())
     }
 
-    fn break_cycles(&mut self) {
+    /// Writes the `mod_<Message>` module, holding the oneofs, messages and enums scoped to `m`
+    fn write_mod<W: Write>(&self, w: &mut W, m: &Message) -> Result<()> {
+        let package = m.fqn();
+        let has_messages = self.own_messages().any(|n| n.package == package);
+        if m.oneofs.is_empty() && !has_messages && 
+            !self.enums.iter().any(|e| e.package == package && e.import.is_none()) {
+            return Ok(());
+        }
+        writeln!(w)?;
+        writeln!(w, "pub mod mod_{} {{", m.name)?;
+        if has_messages || !m.oneofs.is_empty() || self.json || self.text {
+            writeln!(w)?;
+            writeln!(w, "use super::*;")?;
+        }
+        for o in &m.oneofs {
+            writeln!(w)?;
+            o.write_definition_enum(w, m, &self.messages)?;
+        }
+        self.write_package(w, &package)?;
+        writeln!(w)?;
+        writeln!(w, "}}")?;
+        Ok(())
+    }
+
+    /// Moves nested messages and enums next to top level ones, and collects imports
+    fn flatten(&mut self) {
+        for m in &self.message_and_enums {
+            if let MessageOrEnum::Package(p) = *m {
+                self.package = p;
+            }
+        }
+        let package = self.package.to_string();
+        let module = self.package.split('.')
+            .filter(|p| !p.is_empty())
+            .map(|p| format!("{}::", p))
+            .collect::<String>();
+
         let mut messages = Vec::new();
         let mut enums = Vec::new();
         for m in self.message_and_enums.drain(..) {
             match m {
-                MessageOrEnum::Msg(m) => messages.push(m),
-                MessageOrEnum::Enum(e) => enums.push(e),
+                MessageOrEnum::Msg(m) => flatten_messages(m, &package, &module, &mut messages, &mut enums),
+                MessageOrEnum::Enum(mut e) => {
+                    e.package = package.clone();
+                    e.module = module.clone();
+                    enums.push(e);
+                }
+                MessageOrEnum::Import(i) => self.imports.push(i),
+                MessageOrEnum::Service(mut s) => {
+                    s.package = package.clone();
+                    self.services.push(s);
+                }
                 _ => (),
             }
         }
         self.messages = messages;
         self.enums = enums;
+    }
 
-        let message_names = self.messages.iter().map(|m| m.name.to_string()).collect::<Vec<_>>();
+    /// Replaces all field types by the fully qualified names of the messages/enums they refer to
+    fn resolve_types(&mut self) -> Result<()> {
+        let names = self.messages.iter().map(|m| (m.fqn(), m.rust_path()))
+            .chain(self.enums.iter().map(|e| (e.fqn(), e.rust_path())))
+            .collect::<Vec<_>>();
+        for m in self.messages.iter_mut().filter(|m| m.import.is_none()) {
+            let scope = m.fqn();
+            for f in &mut m.fields {
+                f.resolve_type(&scope, &names)?;
+            }
+            for o in &mut m.oneofs {
+                for f in &mut o.fields {
+                    f.resolve_type(&scope, &names)?;
+                }
+            }
+        }
+        // rpc requests and responses can only be messages
+        let message_names = &names[..self.messages.len()];
+        for s in &mut self.services {
+            s.resolve_types(message_names)?;
+        }
+        Ok(())
+    }
+
+    fn break_cycles(&mut self) {
+        let message_names = self.messages.iter().map(|m| m.fqn()).collect::<Vec<_>>();
 
         let mut leaf_messages = Vec::new();
         let mut undef_messages = (0..self.messages.len()).collect::<Vec<_>>();
@@ -711,9 +1908,88 @@ impl<'a> FileDescriptor<'a> {
                             f.boxed = true;
                         }
                     }
+                    for o in m.oneofs.iter_mut() {
+                        for f in o.fields.iter_mut() {
+                            if !f.is_leaf(&leaf_messages, &self.messages) {
+                                f.boxed = true;
+                            }
+                        }
+                    }
                     self.messages[k] = m;
                 }
             }
         }
     }
 }
+
+/// Moves nested messages and enums next to their parent
+///
+/// `package` and `module` are the full name and the rust module of the parent
+fn flatten_messages<'a>(mut m: Message<'a>, 
+                        package: &str, 
+                        module: &str,
+                        messages: &mut Vec<Message<'a>>, 
+                        enums: &mut Vec<Enumerator<'a>>) {
+    m.package = package.to_string();
+    m.module = module.to_string();
+    let package = m.fqn();
+    let module = format!("{}mod_{}::", module, m.name);
+    for mut e in m.enums.drain(..) {
+        e.package = package.clone();
+        e.module = module.clone();
+        enums.push(e);
+    }
+    for nested in m.messages.drain(..) {
+        flatten_messages(nested, &package, &module, messages, enums);
+    }
+    messages.push(m);
+}
+
+/// Joins a package and a name into a fully qualified name
+fn fqn(package: &str, name: &str) -> String {
+    if package.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", package, name)
+    }
+}
+
+/// Joins the rust path of a message or an enum
+fn rust_path(import: &Option<String>, module: &str, name: &str) -> String {
+    match *import {
+        Some(ref import) => format!("{}::{}{}", import, module, name),
+        None => format!("{}{}", module, name),
+    }
+}
+
+/// Searches `name` as protobuf does: from the innermost `scope` up to the top level
+///
+/// Returns the fully qualified name and the rust path of the matching item of `names`
+fn resolve_name<'b>(name: &str, scope: &str, names: &'b [(String, String)]) -> Option<&'b (String, String)> {
+    if name.starts_with('.') {
+        return names.iter().find(|n| n.0 == name[1..]);
+    }
+    let mut scope = scope;
+    loop {
+        let candidate = fqn(scope, name);
+        if let Some(n) = names.iter().find(|n| n.0 == candidate) {
+            return Some(n);
+        }
+        if scope.is_empty() {
+            return None;
+        }
+        scope = scope.rfind('.').map_or("", |i| &scope[..i]);
+    }
+}
+
+/// Locates the error at `offset` of `b`, with the faulty line and a caret under the column
+fn parse_error(b: &[u8], file: &str, offset: usize, expected: &str) -> Error {
+    let start = b[..offset].iter().rposition(|&c| c == b'\n').map_or(0, |i| i + 1);
+    let end = b[offset..].iter().position(|&c| c == b'\n').map_or(b.len(), |i| offset + i);
+    let line = b[..start].iter().filter(|&&c| c == b'\n').count() + 1;
+    let prefix = String::from_utf8_lossy(&b[start..offset]);
+    let column = prefix.chars().count() + 1;
+    let indent = prefix.chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect::<String>();
+    let snippet = format!("{}\n{}^", String::from_utf8_lossy(&b[start..end]).trim_end_matches('\r'), indent);
+    ErrorKind::ParseError(file.to_string(), line, column, expected.to_string(), snippet).into()
+}
@@ -0,0 +1,107 @@
+//! Generates modules with `Config` and builds them in a crate depending on quick-protobuf
+
+extern crate pb_rs;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use pb_rs::Config;
+
+/// Creates an empty crate in a temporary directory, with `protos` written in its `protos`
+/// directory and a lib.rs including the modules generated in its `out` directory
+///
+/// `no_std` crates depend on quick-protobuf without its default `std` feature.
+fn new_crate(name: &str, protos: &[(&str, &str)], no_std: bool) -> PathBuf {
+    let dir = env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("protos")).unwrap();
+    fs::create_dir_all(dir.join("out")).unwrap();
+    for &(file, proto) in protos {
+        File::create(dir.join("protos").join(file)).unwrap().write_all(proto.as_bytes()).unwrap();
+    }
+
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+    let features = if no_std { ", default-features = false" } else { "" };
+    write!(File::create(dir.join("Cargo.toml")).unwrap(),
+           "[package]\nname = {:?}\nversion = \"0.1.0\"\nauthors = []\n\n\
+            [lib]\npath = \"lib.rs\"\n\n\
+            [dependencies]\nquick-protobuf = {{ path = {:?}{} }}\n\n\
+            [workspace]\n",
+           name, root.display().to_string(), features).unwrap();
+    let mut lib = File::create(dir.join("lib.rs")).unwrap();
+    if no_std {
+        writeln!(lib, "#![no_std]\nextern crate alloc;").unwrap();
+    }
+    writeln!(lib, "extern crate quick_protobuf;\ninclude!(\"out/mod.rs\");").unwrap();
+    // reuse the versions of quick-protobuf dependencies when they are already resolved
+    let _ = fs::copy(root.join("Cargo.lock"), dir.join("Cargo.lock"));
+    dir
+}
+
+/// Builds the crate in `dir`, panicking with the compiler output on errors
+fn build_crate(dir: &Path) {
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let target_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/compile-tests");
+    let output = Command::new(cargo)
+        .arg("build")
+        .arg("--manifest-path").arg(dir.join("Cargo.toml"))
+        .env("CARGO_TARGET_DIR", target_dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "cannot build {}:\n{}",
+            dir.display(), String::from_utf8_lossy(&output.stderr));
+}
+
+fn read_file(path: &Path) -> String {
+    let mut content = String::new();
+    File::open(path).unwrap().read_to_string(&mut content).unwrap();
+    content
+}
+
+#[test]
+fn test_compile_out_dir() {
+    let dir = new_crate("pb-rs-test-compile-out-dir", &[
+        ("common.proto", "package common; message Id { required int32 id = 1; }"),
+        ("a.proto", "import \"common.proto\"; message A { optional common.Id id = 1; }"),
+    ], false);
+
+    Config::default()
+        .out_dir(dir.join("out"))
+        .compile(&[dir.join("protos/a.proto")])
+        .unwrap();
+
+    assert!(dir.join("out/common.rs").is_file());
+    assert!(dir.join("out/a.rs").is_file());
+    let mod_rs = read_file(&dir.join("out/mod.rs"));
+    assert!(mod_rs.contains("pub mod common;"));
+    assert!(mod_rs.find("pub mod common;") < mod_rs.find("pub mod a;"));
+    build_crate(&dir);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_compile_no_std() {
+    let dir = new_crate("pb-rs-test-compile-no-std", &[
+        ("a.proto", "message A { map<string, int32> counts = 1; repeated B bs = 2; oneof o { B b = 3; } } \
+                     message B { optional bytes data = 1; repeated fixed32 ids = 2 [packed=true]; \
+                     repeated double xs = 3 [packed=true]; repeated sfixed64 ys = 4 [packed=true]; \
+                     repeated E es = 5 [packed=true]; } \
+                     enum E { X = 0; Y = 1; }"),
+    ], true);
+
+    Config::default()
+        .out_dir(dir.join("out"))
+        .no_std(true)
+        .compile(&[dir.join("protos/a.proto")])
+        .unwrap();
+
+    let a_rs = read_file(&dir.join("out/a.rs"));
+    assert!(!a_rs.contains("std::"));
+    assert!(a_rs.contains("use alloc::collections::BTreeMap;"));
+    assert!(a_rs.contains("pub counts: BTreeMap<Cow<'a, str>, i32>,"));
+    build_crate(&dir);
+    let _ = fs::remove_dir_all(&dir);
+}
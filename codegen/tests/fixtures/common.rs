@@ -0,0 +1,65 @@
+//! Automatically generated rust module for 'common.proto' file
+
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+
+use std::borrow::Cow;
+use quick_protobuf::{MessageRead, MessageWrite, BytesReader, Writer, WriterBackend, Result};
+use quick_protobuf::sizeofs::*;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Unit {
+    METER = 1,
+    SECOND = 2,
+}
+
+impl Default for Unit {
+    fn default() -> Self {
+        Unit::METER
+    }
+}
+
+impl From<i32> for Unit {
+    fn from(i: i32) -> Self {
+        match i {
+            1 => Unit::METER,
+            2 => Unit::SECOND,
+            _ => Self::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Measure {
+    pub value: Option<f64>,
+    pub unit: Option<Unit>,
+}
+
+impl<'a> MessageRead<'a> for Measure {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(9) => msg.value = Some(r.read_double(bytes).map_err(|e| e.in_field("Measure", "value"))?),
+                Ok(16) => msg.unit = Some(r.read_enum(bytes).map_err(|e| e.in_field("Measure", "unit"))?),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("Measure"))?; }
+                Err(e) => return Err(e.in_message("Measure")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for Measure {
+    fn get_size(&self) -> usize {
+        self.value.as_ref().map_or(0, |_| 1 + 8)
+        + self.unit.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.value { r.write_double_with_tag(9, *s)?; }
+        if let Some(ref s) = self.unit { r.write_enum_with_tag(16, *s as i32)?; }
+        Ok(())
+    }
+}
@@ -0,0 +1,12 @@
+cd ../..
+cargo run tests/fixtures/groups.proto
+cargo run tests/fixtures/oneof.proto
+cargo run tests/fixtures/maps.proto
+cargo run tests/fixtures/nested.proto
+cargo run tests/fixtures/imports.proto
+cargo run tests/fixtures/package.proto
+cargo run tests/fixtures/service.proto
+cargo run -- --json tests/fixtures/json.proto
+cargo run -- --text tests/fixtures/text.proto
+cargo run -- --keep-unknown-fields tests/fixtures/unknown.proto
+cd tests/fixtures
@@ -0,0 +1,134 @@
+//! Automatically generated rust module for 'groups.proto' file
+
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+
+use std::borrow::Cow;
+use quick_protobuf::{MessageRead, MessageWrite, BytesReader, Writer, WriterBackend, Result};
+use quick_protobuf::sizeofs::*;
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct SearchResponse<'a> {
+    pub hit: Vec<mod_SearchResponse::Hit<'a>>,
+    pub total: Option<i32>,
+}
+
+impl<'a> MessageRead<'a> for SearchResponse<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(11) => msg.hit.push(r.read_group(bytes, 11, mod_SearchResponse::Hit::from_reader).map_err(|e| e.in_repeated_field("SearchResponse", "hit", msg.hit.len()))?),
+                Ok(48) => msg.total = Some(r.read_int32(bytes).map_err(|e| e.in_field("SearchResponse", "total"))?),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("SearchResponse"))?; }
+                Err(e) => return Err(e.in_message("SearchResponse")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for SearchResponse<'a> {
+    fn get_size(&self) -> usize {
+        self.hit.iter().map(|s| 2 + s.get_size()).sum::<usize>()
+        + self.total.as_ref().map_or(0, |m| 1 + sizeof_int32(*m))
+    }
+
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        self.hit.iter().map(|s| 2 + s.get_size_cached(sizes)).sum::<usize>()
+        + self.total.as_ref().map_or(0, |m| 1 + sizeof_int32(*m))
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        for s in &self.hit { r.write_group_with_tag(11, s)? }
+        if let Some(ref s) = self.total { r.write_int32_with_tag(48, *s)?; }
+        Ok(())
+    }
+}
+
+pub mod mod_SearchResponse {
+
+use super::*;
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Hit<'a> {
+    pub url: Cow<'a, str>,
+    pub title: Option<Cow<'a, str>>,
+    pub snippet: Option<mod_SearchResponse::mod_Hit::Snippet<'a>>,
+}
+
+impl<'a> MessageRead<'a> for Hit<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(18) => msg.url = Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_field("Hit", "url"))?),
+                Ok(26) => msg.title = Some(Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_field("Hit", "title"))?)),
+                Ok(35) => msg.snippet = Some(r.read_group(bytes, 35, mod_SearchResponse::mod_Hit::Snippet::from_reader).map_err(|e| e.in_field("Hit", "snippet"))?),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("Hit"))?; }
+                Err(e) => return Err(e.in_message("Hit")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for Hit<'a> {
+    fn get_size(&self) -> usize {
+        1 + sizeof_var_length(self.url.len())
+        + self.title.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + self.snippet.as_ref().map_or(0, |m| 2 + m.get_size())
+    }
+
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        1 + sizeof_var_length(self.url.len())
+        + self.title.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + self.snippet.as_ref().map_or(0, |m| 2 + m.get_size_cached(sizes))
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        r.write_string_with_tag(18, &self.url)?;
+        if let Some(ref s) = self.title { r.write_string_with_tag(26, s)?; }
+        if let Some(ref s) = self.snippet { r.write_group_with_tag(35, s)?; }
+        Ok(())
+    }
+}
+
+pub mod mod_Hit {
+
+use super::*;
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Snippet<'a> {
+    pub lines: Vec<Cow<'a, str>>,
+}
+
+impl<'a> MessageRead<'a> for Snippet<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(42) => msg.lines.push(Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_repeated_field("Snippet", "lines", msg.lines.len()))?)),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("Snippet"))?; }
+                Err(e) => return Err(e.in_message("Snippet")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for Snippet<'a> {
+    fn get_size(&self) -> usize {
+        self.lines.iter().map(|s| 1 + sizeof_var_length(s.len())).sum::<usize>()
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        for s in &self.lines { r.write_string_with_tag(42, s)? }
+        Ok(())
+    }
+}
+
+}
+
+}
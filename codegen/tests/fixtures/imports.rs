@@ -0,0 +1,54 @@
+//! Automatically generated rust module for 'imports.proto' file
+
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+
+use std::borrow::Cow;
+use quick_protobuf::{MessageRead, MessageWrite, BytesReader, Writer, WriterBackend, Result};
+use quick_protobuf::sizeofs::*;
+use super::common;
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Reading<'a> {
+    pub sensor: Option<Cow<'a, str>>,
+    pub measures: Vec<common::Measure>,
+    pub default_unit: Option<common::Unit>,
+}
+
+impl<'a> MessageRead<'a> for Reading<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => msg.sensor = Some(Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_field("Reading", "sensor"))?)),
+                Ok(18) => msg.measures.push(r.read_message(bytes, common::Measure::from_reader).map_err(|e| e.in_repeated_field("Reading", "measures", msg.measures.len()))?),
+                Ok(24) => msg.default_unit = Some(r.read_enum(bytes).map_err(|e| e.in_field("Reading", "default_unit"))?),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("Reading"))?; }
+                Err(e) => return Err(e.in_message("Reading")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for Reading<'a> {
+    fn get_size(&self) -> usize {
+        self.sensor.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + self.measures.iter().map(|s| 1 + sizeof_var_length(s.get_size())).sum::<usize>()
+        + self.default_unit.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+    }
+
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        self.sensor.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + self.measures.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+        + self.default_unit.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.sensor { r.write_string_with_tag(10, s)?; }
+        for s in &self.measures { r.write_message_with_tag(18, s)? }
+        if let Some(ref s) = self.default_unit { r.write_enum_with_tag(24, *s as i32)?; }
+        Ok(())
+    }
+}
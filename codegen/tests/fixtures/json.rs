@@ -0,0 +1,246 @@
+//! Automatically generated rust module for 'json.proto' file
+
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use quick_protobuf::{MessageRead, MessageWrite, BytesReader, Writer, WriterBackend, Result};
+use quick_protobuf::sizeofs::*;
+use quick_protobuf::errors::ErrorKind;
+use quick_protobuf::json::{JsonRead, JsonWrite, JsonWriter, Value};
+use super::json_enums;
+
+pub mod json {
+
+use super::*;
+
+pub mod test {
+
+use super::*;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Level {
+    LOW = 0,
+    HIGH = 1,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Level::LOW
+    }
+}
+
+impl From<i32> for Level {
+    fn from(i: i32) -> Self {
+        match i {
+            0 => Level::LOW,
+            1 => Level::HIGH,
+            _ => Self::default(),
+        }
+    }
+}
+
+impl JsonWrite for Level {
+    fn write_json(&self, w: &mut JsonWriter) {
+        w.write_string(match *self {
+            Level::LOW => "LOW",
+            Level::HIGH => "HIGH",
+        });
+    }
+}
+
+impl JsonRead for Level {
+    fn from_json(v: &Value) -> Result<Self> {
+        match *v {
+            Value::String(ref s) => match &**s {
+                "LOW" => Ok(Level::LOW),
+                "HIGH" => Ok(Level::HIGH),
+                _ => Err(ErrorKind::Json(format!("unknown Level value '{}'", s)).into()),
+            },
+            _ => v.as_i32().map(Level::from),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Alert<'a> {
+    pub title: Option<Cow<'a, str>>,
+    pub kind: Option<json::test::mod_Alert::Kind>,
+    pub level: Option<json::test::Level>,
+    pub count: i64,
+    pub payload: Option<Cow<'a, [u8]>>,
+    pub children: Vec<json::test::Alert<'a>>,
+    pub labels: HashMap<Cow<'a, str>, i32>,
+    pub priority: Option<json_enums::json::enums::Priority>,
+    pub muted: Option<bool>,
+}
+
+impl<'a> MessageRead<'a> for Alert<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => msg.title = Some(Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_field("Alert", "title"))?)),
+                Ok(16) => msg.kind = Some(r.read_enum(bytes).map_err(|e| e.in_field("Alert", "kind"))?),
+                Ok(24) => msg.level = Some(r.read_enum(bytes).map_err(|e| e.in_field("Alert", "level"))?),
+                Ok(32) => msg.count = r.read_int64(bytes).map_err(|e| e.in_field("Alert", "count"))?,
+                Ok(42) => msg.payload = Some(Cow::Borrowed(r.read_bytes(bytes).map_err(|e| e.in_field("Alert", "payload"))?)),
+                Ok(50) => msg.children = r.read_packed(bytes, |r, bytes| r.read_message(bytes, json::test::Alert::from_reader)).map_err(|e| e.in_field("Alert", "children"))?,
+                Ok(58) => {
+                    let (key, value) = r.read_map(bytes, |r, bytes| r.read_string(bytes).map(Cow::Borrowed), |r, bytes| r.read_int32(bytes)).map_err(|e| e.in_field("Alert", "labels"))?;
+                    msg.labels.insert(key, value);
+                }
+                Ok(64) => msg.priority = Some(r.read_enum(bytes).map_err(|e| e.in_field("Alert", "priority"))?),
+                Ok(72) => msg.muted = Some(r.read_bool(bytes).map_err(|e| e.in_field("Alert", "muted"))?),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("Alert"))?; }
+                Err(e) => return Err(e.in_message("Alert")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for Alert<'a> {
+    fn get_size(&self) -> usize {
+        self.title.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + self.kind.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+        + self.level.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+        + if self.count == 0 { 0 } else {1 + sizeof_int64(self.count)}
+        + self.payload.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + if self.children.is_empty() { 0 } else { 1 + sizeof_var_length(self.children.iter().map(|s| sizeof_var_length(s.get_size())).sum::<usize>()) }
+        + self.labels.iter().map(|(k, v)| 1 + sizeof_map_entry(1 + sizeof_var_length(k.len()), 1 + sizeof_int32(*v))).sum::<usize>()
+        + self.priority.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+        + self.muted.as_ref().map_or(0, |m| 1 + sizeof_bool(*m))
+    }
+
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        self.title.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + self.kind.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+        + self.level.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+        + if self.count == 0 { 0 } else {1 + sizeof_int64(self.count)}
+        + self.payload.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + if self.children.is_empty() { 0 } else { 1 + sizeof_var_length(self.children.iter().map(|s| sizeof_message_cached(s, sizes)).sum::<usize>()) }
+        + self.labels.iter().map(|(k, v)| 1 + sizeof_map_entry(1 + sizeof_var_length(k.len()), 1 + sizeof_int32(*v))).sum::<usize>()
+        + self.priority.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+        + self.muted.as_ref().map_or(0, |m| 1 + sizeof_bool(*m))
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.title { r.write_string_with_tag(10, s)?; }
+        if let Some(ref s) = self.kind { r.write_enum_with_tag(16, *s as i32)?; }
+        if let Some(ref s) = self.level { r.write_enum_with_tag(24, *s as i32)?; }
+        if self.count != 0 { r.write_int64_with_tag(32, self.count)?; }
+        if let Some(ref s) = self.payload { r.write_bytes_with_tag(42, s)?; }
+        r.write_packed_repeated_field_with_tag(50, &self.children, |r, m| r.write_message(m), &|m| sizeof_var_length(m.get_size()))?;
+        for (k, v) in self.labels.iter() { r.write_tag(58)?; r.write_map(1 + sizeof_var_length(k.len()) + 1 + sizeof_int32(*v), 10, |r| r.write_string(k), 16, |r| r.write_int32(*v))?; }
+        if let Some(ref s) = self.priority { r.write_enum_with_tag(64, *s as i32)?; }
+        if let Some(ref s) = self.muted { r.write_bool_with_tag(72, *s)?; }
+        Ok(())
+    }
+}
+
+impl<'a> JsonWrite for Alert<'a> {
+    fn write_json(&self, w: &mut JsonWriter) {
+        w.begin_object();
+        if let Some(ref s) = self.title { if !s.is_empty() { w.key("title"); w.write_string(s); } }
+        if let Some(ref s) = self.kind { if *s as i32 != 0 { w.key("kind"); s.write_json(w); } }
+        if let Some(ref s) = self.level { if *s as i32 != 0 { w.key("level"); s.write_json(w); } }
+        if self.count != 0 { w.key("count"); w.write_i64(self.count); }
+        if let Some(ref s) = self.payload { if !s.is_empty() { w.key("payload"); w.write_bytes(s); } }
+        if !self.children.is_empty() {
+            w.key("children");
+            w.begin_array();
+            for s in &self.children { s.write_json(w); }
+            w.end_array();
+        }
+        if !self.labels.is_empty() {
+            w.key("labels");
+            w.begin_object();
+            for (k, v) in &self.labels { w.key(k); w.write_i32(*v); }
+            w.end_object();
+        }
+        if let Some(ref s) = self.priority { if *s as i32 != 0 { w.key("priority"); s.write_json(w); } }
+        if let Some(ref s) = self.muted { if *s { w.key("muted"); w.write_bool(*s); } }
+        w.end_object();
+    }
+}
+
+impl<'a> JsonRead for Alert<'a> {
+    fn from_json(v: &Value) -> Result<Self> {
+        let mut msg = Self::default();
+        for &(ref k, ref v) in v.as_object()? {
+            if v.is_null() { continue; }
+            match &**k {
+                "title" => msg.title = Some(Cow::Owned(v.as_str()?.to_string())),
+                "kind" => msg.kind = Some(json::test::mod_Alert::Kind::from_json(v)?),
+                "level" => msg.level = Some(json::test::Level::from_json(v)?),
+                "count" => msg.count = v.as_i64()?,
+                "payload" => msg.payload = Some(Cow::Owned(v.as_bytes()?)),
+                "children" => msg.children = v.as_array()?.iter().map(|v| Ok(json::test::Alert::from_json(v)?)).collect::<Result<Vec<_>>>()?,
+                "labels" => for &(ref k, ref v) in v.as_object()? {
+                    msg.labels.insert(Cow::Owned(k.clone()), v.as_i32()?);
+                },
+                "priority" => msg.priority = Some(json_enums::json::enums::Priority::from_json(v)?),
+                "muted" => msg.muted = Some(v.as_bool()?),
+                _ => return Err(ErrorKind::Json(format!("unknown field '{}'", k)).into()),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+pub mod mod_Alert {
+
+use super::*;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Kind {
+    INFO = 0,
+    ERROR = 1,
+}
+
+impl Default for Kind {
+    fn default() -> Self {
+        Kind::INFO
+    }
+}
+
+impl From<i32> for Kind {
+    fn from(i: i32) -> Self {
+        match i {
+            0 => Kind::INFO,
+            1 => Kind::ERROR,
+            _ => Self::default(),
+        }
+    }
+}
+
+impl JsonWrite for Kind {
+    fn write_json(&self, w: &mut JsonWriter) {
+        w.write_string(match *self {
+            Kind::INFO => "INFO",
+            Kind::ERROR => "ERROR",
+        });
+    }
+}
+
+impl JsonRead for Kind {
+    fn from_json(v: &Value) -> Result<Self> {
+        match *v {
+            Value::String(ref s) => match &**s {
+                "INFO" => Ok(Kind::INFO),
+                "ERROR" => Ok(Kind::ERROR),
+                _ => Err(ErrorKind::Json(format!("unknown Kind value '{}'", s)).into()),
+            },
+            _ => v.as_i32().map(Kind::from),
+        }
+    }
+}
+
+}
+
+}
+
+}
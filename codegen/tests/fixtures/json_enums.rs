@@ -0,0 +1,67 @@
+//! Automatically generated rust module for 'json_enums.proto' file
+
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+
+use std::borrow::Cow;
+use quick_protobuf::{MessageRead, MessageWrite, BytesReader, Writer, WriterBackend, Result};
+use quick_protobuf::sizeofs::*;
+use quick_protobuf::errors::ErrorKind;
+use quick_protobuf::json::{JsonRead, JsonWrite, JsonWriter, Value};
+
+pub mod json {
+
+use super::*;
+
+pub mod enums {
+
+use super::*;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Priority {
+    NORMAL = 0,
+    URGENT = 1,
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Priority::NORMAL
+    }
+}
+
+impl From<i32> for Priority {
+    fn from(i: i32) -> Self {
+        match i {
+            0 => Priority::NORMAL,
+            1 => Priority::URGENT,
+            _ => Self::default(),
+        }
+    }
+}
+
+impl JsonWrite for Priority {
+    fn write_json(&self, w: &mut JsonWriter) {
+        w.write_string(match *self {
+            Priority::NORMAL => "NORMAL",
+            Priority::URGENT => "URGENT",
+        });
+    }
+}
+
+impl JsonRead for Priority {
+    fn from_json(v: &Value) -> Result<Self> {
+        match *v {
+            Value::String(ref s) => match &**s {
+                "NORMAL" => Ok(Priority::NORMAL),
+                "URGENT" => Ok(Priority::URGENT),
+                _ => Err(ErrorKind::Json(format!("unknown Priority value '{}'", s)).into()),
+            },
+            _ => v.as_i32().map(Priority::from),
+        }
+    }
+}
+
+}
+
+}
@@ -0,0 +1,131 @@
+//! Automatically generated rust module for 'maps.proto' file
+
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use quick_protobuf::{MessageRead, MessageWrite, BytesReader, Writer, WriterBackend, Result};
+use quick_protobuf::sizeofs::*;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Color {
+    RED = 0,
+    GREEN = 1,
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Color::RED
+    }
+}
+
+impl From<i32> for Color {
+    fn from(i: i32) -> Self {
+        match i {
+            0 => Color::RED,
+            1 => Color::GREEN,
+            _ => Self::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Value<'a> {
+    pub text: Option<Cow<'a, str>>,
+}
+
+impl<'a> MessageRead<'a> for Value<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => msg.text = Some(Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_field("Value", "text"))?)),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("Value"))?; }
+                Err(e) => return Err(e.in_message("Value")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for Value<'a> {
+    fn get_size(&self) -> usize {
+        self.text.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.text { r.write_string_with_tag(10, s)?; }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Maps<'a> {
+    pub counts: HashMap<Cow<'a, str>, i32>,
+    pub names: HashMap<i64, Cow<'a, str>>,
+    pub values: HashMap<Cow<'a, str>, Value<'a>>,
+    pub colors: HashMap<u32, Color>,
+    pub blobs: HashMap<bool, Cow<'a, [u8]>>,
+}
+
+impl<'a> MessageRead<'a> for Maps<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => {
+                    let (key, value) = r.read_map(bytes, |r, bytes| r.read_string(bytes).map(Cow::Borrowed), |r, bytes| r.read_int32(bytes)).map_err(|e| e.in_field("Maps", "counts"))?;
+                    msg.counts.insert(key, value);
+                }
+                Ok(18) => {
+                    let (key, value) = r.read_map(bytes, |r, bytes| r.read_int64(bytes), |r, bytes| r.read_string(bytes).map(Cow::Borrowed)).map_err(|e| e.in_field("Maps", "names"))?;
+                    msg.names.insert(key, value);
+                }
+                Ok(26) => {
+                    let (key, value) = r.read_map(bytes, |r, bytes| r.read_string(bytes).map(Cow::Borrowed), |r, bytes| r.read_message(bytes, Value::from_reader)).map_err(|e| e.in_field("Maps", "values"))?;
+                    msg.values.insert(key, value);
+                }
+                Ok(34) => {
+                    let (key, value) = r.read_map(bytes, |r, bytes| r.read_uint32(bytes), |r, bytes| r.read_enum(bytes)).map_err(|e| e.in_field("Maps", "colors"))?;
+                    msg.colors.insert(key, value);
+                }
+                Ok(42) => {
+                    let (key, value) = r.read_map(bytes, |r, bytes| r.read_bool(bytes), |r, bytes| r.read_bytes(bytes).map(Cow::Borrowed)).map_err(|e| e.in_field("Maps", "blobs"))?;
+                    msg.blobs.insert(key, value);
+                }
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("Maps"))?; }
+                Err(e) => return Err(e.in_message("Maps")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for Maps<'a> {
+    fn get_size(&self) -> usize {
+        self.counts.iter().map(|(k, v)| 1 + sizeof_map_entry(1 + sizeof_var_length(k.len()), 1 + sizeof_int32(*v))).sum::<usize>()
+        + self.names.iter().map(|(k, v)| 1 + sizeof_map_entry(1 + sizeof_int64(*k), 1 + sizeof_var_length(v.len()))).sum::<usize>()
+        + self.values.iter().map(|(k, v)| 1 + sizeof_map_entry(1 + sizeof_var_length(k.len()), 1 + sizeof_var_length(v.get_size()))).sum::<usize>()
+        + self.colors.iter().map(|(k, v)| 1 + sizeof_map_entry(1 + sizeof_uint32(*k), 1 + sizeof_enum(*v as i32))).sum::<usize>()
+        + self.blobs.iter().map(|(k, v)| 1 + sizeof_map_entry(1 + sizeof_bool(*k), 1 + sizeof_var_length(v.len()))).sum::<usize>()
+    }
+
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        self.counts.iter().map(|(k, v)| 1 + sizeof_map_entry(1 + sizeof_var_length(k.len()), 1 + sizeof_int32(*v))).sum::<usize>()
+        + self.names.iter().map(|(k, v)| 1 + sizeof_map_entry(1 + sizeof_int64(*k), 1 + sizeof_var_length(v.len()))).sum::<usize>()
+        + self.values.iter().map(|(k, v)| 1 + sizeof_map_entry(1 + sizeof_var_length(k.len()), 1 + sizeof_message_cached(v, sizes))).sum::<usize>()
+        + self.colors.iter().map(|(k, v)| 1 + sizeof_map_entry(1 + sizeof_uint32(*k), 1 + sizeof_enum(*v as i32))).sum::<usize>()
+        + self.blobs.iter().map(|(k, v)| 1 + sizeof_map_entry(1 + sizeof_bool(*k), 1 + sizeof_var_length(v.len()))).sum::<usize>()
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        for (k, v) in self.counts.iter() { r.write_tag(10)?; r.write_map(1 + sizeof_var_length(k.len()) + 1 + sizeof_int32(*v), 10, |r| r.write_string(k), 16, |r| r.write_int32(*v))?; }
+        for (k, v) in self.names.iter() { r.write_tag(18)?; r.write_map(1 + sizeof_int64(*k) + 1 + sizeof_var_length(v.len()), 8, |r| r.write_int64(*k), 18, |r| r.write_string(v))?; }
+        for (k, v) in self.values.iter() { r.write_tag(26)?; r.write_map(1 + sizeof_var_length(k.len()) + 1 + sizeof_var_length(r.message_size(v)), 10, |r| r.write_string(k), 18, |r| r.write_message(v))?; }
+        for (k, v) in self.colors.iter() { r.write_tag(34)?; r.write_map(1 + sizeof_uint32(*k) + 1 + sizeof_enum(*v as i32), 8, |r| r.write_uint32(*k), 16, |r| r.write_enum(*v as i32))?; }
+        for (k, v) in self.blobs.iter() { r.write_tag(42)?; r.write_map(1 + sizeof_bool(*k) + 1 + sizeof_var_length(v.len()), 8, |r| r.write_bool(*k), 18, |r| r.write_bytes(v))?; }
+        Ok(())
+    }
+}
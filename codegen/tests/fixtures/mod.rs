@@ -0,0 +1,19 @@
+//! Modules generated by pb-rs from the .proto files of this directory, see `generate_modules.sh`
+
+// generated modules are checked by building them, not by linting them
+#![allow(unused_imports)]
+#![allow(clippy::all)]
+
+pub mod groups;
+pub mod oneof;
+pub mod maps;
+pub mod nested;
+pub mod common;
+pub mod imports;
+pub mod package;
+pub mod service;
+pub mod json_enums;
+pub mod json;
+pub mod text_enums;
+pub mod text;
+pub mod unknown;
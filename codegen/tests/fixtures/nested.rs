@@ -0,0 +1,231 @@
+//! Automatically generated rust module for 'nested.proto' file
+
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+
+use std::borrow::Cow;
+use quick_protobuf::{MessageRead, MessageWrite, BytesReader, Writer, WriterBackend, Result};
+use quick_protobuf::sizeofs::*;
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Document<'a> {
+    pub status: Option<mod_Document::Status>,
+    pub sections: Vec<mod_Document::Section<'a>>,
+    pub summary: Option<mod_Document::mod_Section::Line<'a>>,
+    pub default_kind: Option<mod_Document::mod_Section::Kind>,
+}
+
+impl<'a> MessageRead<'a> for Document<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.status = Some(r.read_enum(bytes).map_err(|e| e.in_field("Document", "status"))?),
+                Ok(18) => msg.sections.push(r.read_message(bytes, mod_Document::Section::from_reader).map_err(|e| e.in_repeated_field("Document", "sections", msg.sections.len()))?),
+                Ok(26) => msg.summary = Some(r.read_message(bytes, mod_Document::mod_Section::Line::from_reader).map_err(|e| e.in_field("Document", "summary"))?),
+                Ok(32) => msg.default_kind = Some(r.read_enum(bytes).map_err(|e| e.in_field("Document", "default_kind"))?),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("Document"))?; }
+                Err(e) => return Err(e.in_message("Document")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for Document<'a> {
+    fn get_size(&self) -> usize {
+        self.status.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+        + self.sections.iter().map(|s| 1 + sizeof_var_length(s.get_size())).sum::<usize>()
+        + self.summary.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.get_size()))
+        + self.default_kind.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+    }
+
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        self.status.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+        + self.sections.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+        + self.summary.as_ref().map_or(0, |m| 1 + sizeof_message_cached(m, sizes))
+        + self.default_kind.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.status { r.write_enum_with_tag(8, *s as i32)?; }
+        for s in &self.sections { r.write_message_with_tag(18, s)? }
+        if let Some(ref s) = self.summary { r.write_message_with_tag(26, s)?; }
+        if let Some(ref s) = self.default_kind { r.write_enum_with_tag(32, *s as i32)?; }
+        Ok(())
+    }
+}
+
+pub mod mod_Document {
+
+use super::*;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Status {
+    DRAFT = 1,
+    PUBLISHED = 2,
+}
+
+impl Default for Status {
+    fn default() -> Self {
+        Status::DRAFT
+    }
+}
+
+impl From<i32> for Status {
+    fn from(i: i32) -> Self {
+        match i {
+            1 => Status::DRAFT,
+            2 => Status::PUBLISHED,
+            _ => Self::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Section<'a> {
+    pub title: Option<Cow<'a, str>>,
+    pub lines: Vec<mod_Document::mod_Section::Line<'a>>,
+    pub subsections: Vec<mod_Document::Section<'a>>,
+}
+
+impl<'a> MessageRead<'a> for Section<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => msg.title = Some(Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_field("Section", "title"))?)),
+                Ok(18) => msg.lines.push(r.read_message(bytes, mod_Document::mod_Section::Line::from_reader).map_err(|e| e.in_repeated_field("Section", "lines", msg.lines.len()))?),
+                Ok(26) => msg.subsections.push(r.read_message(bytes, mod_Document::Section::from_reader).map_err(|e| e.in_repeated_field("Section", "subsections", msg.subsections.len()))?),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("Section"))?; }
+                Err(e) => return Err(e.in_message("Section")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for Section<'a> {
+    fn get_size(&self) -> usize {
+        self.title.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + self.lines.iter().map(|s| 1 + sizeof_var_length(s.get_size())).sum::<usize>()
+        + self.subsections.iter().map(|s| 1 + sizeof_var_length(s.get_size())).sum::<usize>()
+    }
+
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        self.title.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + self.lines.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+        + self.subsections.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.title { r.write_string_with_tag(10, s)?; }
+        for s in &self.lines { r.write_message_with_tag(18, s)? }
+        for s in &self.subsections { r.write_message_with_tag(26, s)? }
+        Ok(())
+    }
+}
+
+pub mod mod_Section {
+
+use super::*;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Kind {
+    TEXT = 1,
+    CODE = 2,
+}
+
+impl Default for Kind {
+    fn default() -> Self {
+        Kind::TEXT
+    }
+}
+
+impl From<i32> for Kind {
+    fn from(i: i32) -> Self {
+        match i {
+            1 => Kind::TEXT,
+            2 => Kind::CODE,
+            _ => Self::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Line<'a> {
+    pub content: Option<Cow<'a, str>>,
+    pub kind: Option<mod_Document::mod_Section::Kind>,
+}
+
+impl<'a> MessageRead<'a> for Line<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => msg.content = Some(Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_field("Line", "content"))?)),
+                Ok(16) => msg.kind = Some(r.read_enum(bytes).map_err(|e| e.in_field("Line", "kind"))?),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("Line"))?; }
+                Err(e) => return Err(e.in_message("Line")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for Line<'a> {
+    fn get_size(&self) -> usize {
+        self.content.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + self.kind.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.content { r.write_string_with_tag(10, s)?; }
+        if let Some(ref s) = self.kind { r.write_enum_with_tag(16, *s as i32)?; }
+        Ok(())
+    }
+}
+
+}
+
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Index<'a> {
+    pub lines: Vec<mod_Document::mod_Section::Line<'a>>,
+    pub status: Option<mod_Document::Status>,
+}
+
+impl<'a> MessageRead<'a> for Index<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => msg.lines.push(r.read_message(bytes, mod_Document::mod_Section::Line::from_reader).map_err(|e| e.in_repeated_field("Index", "lines", msg.lines.len()))?),
+                Ok(16) => msg.status = Some(r.read_enum(bytes).map_err(|e| e.in_field("Index", "status"))?),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("Index"))?; }
+                Err(e) => return Err(e.in_message("Index")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for Index<'a> {
+    fn get_size(&self) -> usize {
+        self.lines.iter().map(|s| 1 + sizeof_var_length(s.get_size())).sum::<usize>()
+        + self.status.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+    }
+
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        self.lines.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+        + self.status.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        for s in &self.lines { r.write_message_with_tag(10, s)? }
+        if let Some(ref s) = self.status { r.write_enum_with_tag(16, *s as i32)?; }
+        Ok(())
+    }
+}
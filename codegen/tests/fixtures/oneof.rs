@@ -0,0 +1,124 @@
+//! Automatically generated rust module for 'oneof.proto' file
+
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+
+use std::borrow::Cow;
+use quick_protobuf::{MessageRead, MessageWrite, BytesReader, Writer, WriterBackend, Result};
+use quick_protobuf::sizeofs::*;
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl<'a> MessageRead<'a> for Point {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.x = r.read_int32(bytes).map_err(|e| e.in_field("Point", "x"))?,
+                Ok(16) => msg.y = r.read_int32(bytes).map_err(|e| e.in_field("Point", "y"))?,
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("Point"))?; }
+                Err(e) => return Err(e.in_message("Point")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for Point {
+    fn get_size(&self) -> usize {
+        (if self.x == 0 { 0 } else {1 + sizeof_int32(self.x)})
+        + if self.y == 0 { 0 } else {1 + sizeof_int32(self.y)}
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if self.x != 0 { r.write_int32_with_tag(8, self.x)?; }
+        if self.y != 0 { r.write_int32_with_tag(16, self.y)?; }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Shape<'a> {
+    pub name: Option<Cow<'a, str>>,
+    pub tags: Vec<i32>,
+    pub geometry: mod_Shape::OneOfgeometry<'a>,
+}
+
+impl<'a> MessageRead<'a> for Shape<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => msg.name = Some(Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_field("Shape", "name"))?)),
+                Ok(42) => msg.tags = r.read_packed(bytes, |r, bytes| r.read_int32(bytes)).map_err(|e| e.in_field("Shape", "tags"))?,
+                Ok(18) => msg.geometry = mod_Shape::OneOfgeometry::point(r.read_message(bytes, Point::from_reader).map_err(|e| e.in_field("Shape", "point"))?),
+                Ok(25) => msg.geometry = mod_Shape::OneOfgeometry::radius(r.read_double(bytes).map_err(|e| e.in_field("Shape", "radius"))?),
+                Ok(34) => msg.geometry = mod_Shape::OneOfgeometry::path(Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_field("Shape", "path"))?)),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("Shape"))?; }
+                Err(e) => return Err(e.in_message("Shape")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for Shape<'a> {
+    fn get_size(&self) -> usize {
+        self.name.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + if self.tags.is_empty() { 0 } else { 1 + sizeof_var_length(self.tags.iter().map(|s| sizeof_int32(*s)).sum::<usize>()) }
+        + match self.geometry {
+            mod_Shape::OneOfgeometry::point(ref m) => 1 + sizeof_var_length(m.get_size()),
+            mod_Shape::OneOfgeometry::radius(_) => 1 + 8,
+            mod_Shape::OneOfgeometry::path(ref m) => 1 + sizeof_var_length(m.len()),
+            mod_Shape::OneOfgeometry::None => 0,
+        }
+    }
+
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        self.name.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + if self.tags.is_empty() { 0 } else { 1 + sizeof_var_length(self.tags.iter().map(|s| sizeof_int32(*s)).sum::<usize>()) }
+        + match self.geometry {
+            mod_Shape::OneOfgeometry::point(ref m) => 1 + sizeof_message_cached(m, sizes),
+            mod_Shape::OneOfgeometry::radius(_) => 1 + 8,
+            mod_Shape::OneOfgeometry::path(ref m) => 1 + sizeof_var_length(m.len()),
+            mod_Shape::OneOfgeometry::None => 0,
+        }
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.name { r.write_string_with_tag(10, s)?; }
+        r.write_packed_repeated_field_with_tag(42, &self.tags, |r, m| r.write_int32(*m), &|m| sizeof_int32(*m))?;
+        match self.geometry {
+            mod_Shape::OneOfgeometry::point(ref m) => { r.write_message_with_tag(18, m)? },
+            mod_Shape::OneOfgeometry::radius(ref m) => { r.write_double_with_tag(25, *m)? },
+            mod_Shape::OneOfgeometry::path(ref m) => { r.write_string_with_tag(34, m)? },
+            mod_Shape::OneOfgeometry::None => {},
+        }
+        Ok(())
+    }
+}
+
+pub mod mod_Shape {
+
+use super::*;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum OneOfgeometry<'a> {
+    point(Point),
+    radius(f64),
+    path(Cow<'a, str>),
+    None,
+}
+
+impl<'a> Default for OneOfgeometry<'a> {
+    fn default() -> Self {
+        OneOfgeometry::None
+    }
+}
+
+}
@@ -0,0 +1,173 @@
+//! Automatically generated rust module for 'package.proto' file
+
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+
+use std::borrow::Cow;
+use quick_protobuf::{MessageRead, MessageWrite, BytesReader, Writer, WriterBackend, Result};
+use quick_protobuf::sizeofs::*;
+use super::common;
+
+pub mod app {
+
+use super::*;
+
+pub mod v1 {
+
+use super::*;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Role {
+    GUEST = 1,
+    ADMIN = 2,
+}
+
+impl Default for Role {
+    fn default() -> Self {
+        Role::GUEST
+    }
+}
+
+impl From<i32> for Role {
+    fn from(i: i32) -> Self {
+        match i {
+            1 => Role::GUEST,
+            2 => Role::ADMIN,
+            _ => Self::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct User<'a> {
+    pub id: Option<u64>,
+    pub address: Option<app::v1::mod_User::Address<'a>>,
+    pub role: Option<app::v1::Role>,
+    pub heights: Vec<common::Measure>,
+}
+
+impl<'a> MessageRead<'a> for User<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.id = Some(r.read_uint64(bytes).map_err(|e| e.in_field("User", "id"))?),
+                Ok(18) => msg.address = Some(r.read_message(bytes, app::v1::mod_User::Address::from_reader).map_err(|e| e.in_field("User", "address"))?),
+                Ok(24) => msg.role = Some(r.read_enum(bytes).map_err(|e| e.in_field("User", "role"))?),
+                Ok(34) => msg.heights.push(r.read_message(bytes, common::Measure::from_reader).map_err(|e| e.in_repeated_field("User", "heights", msg.heights.len()))?),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("User"))?; }
+                Err(e) => return Err(e.in_message("User")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for User<'a> {
+    fn get_size(&self) -> usize {
+        self.id.as_ref().map_or(0, |m| 1 + sizeof_uint64(*m))
+        + self.address.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.get_size()))
+        + self.role.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+        + self.heights.iter().map(|s| 1 + sizeof_var_length(s.get_size())).sum::<usize>()
+    }
+
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        self.id.as_ref().map_or(0, |m| 1 + sizeof_uint64(*m))
+        + self.address.as_ref().map_or(0, |m| 1 + sizeof_message_cached(m, sizes))
+        + self.role.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+        + self.heights.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.id { r.write_uint64_with_tag(8, *s)?; }
+        if let Some(ref s) = self.address { r.write_message_with_tag(18, s)?; }
+        if let Some(ref s) = self.role { r.write_enum_with_tag(24, *s as i32)?; }
+        for s in &self.heights { r.write_message_with_tag(34, s)? }
+        Ok(())
+    }
+}
+
+pub mod mod_User {
+
+use super::*;
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Address<'a> {
+    pub city: Option<Cow<'a, str>>,
+}
+
+impl<'a> MessageRead<'a> for Address<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => msg.city = Some(Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_field("Address", "city"))?)),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("Address"))?; }
+                Err(e) => return Err(e.in_message("Address")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for Address<'a> {
+    fn get_size(&self) -> usize {
+        self.city.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.city { r.write_string_with_tag(10, s)?; }
+        Ok(())
+    }
+}
+
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Team<'a> {
+    pub users: Vec<app::v1::User<'a>>,
+    pub office: Option<app::v1::mod_User::Address<'a>>,
+    pub default_role: Option<app::v1::Role>,
+}
+
+impl<'a> MessageRead<'a> for Team<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => msg.users.push(r.read_message(bytes, app::v1::User::from_reader).map_err(|e| e.in_repeated_field("Team", "users", msg.users.len()))?),
+                Ok(18) => msg.office = Some(r.read_message(bytes, app::v1::mod_User::Address::from_reader).map_err(|e| e.in_field("Team", "office"))?),
+                Ok(24) => msg.default_role = Some(r.read_enum(bytes).map_err(|e| e.in_field("Team", "default_role"))?),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("Team"))?; }
+                Err(e) => return Err(e.in_message("Team")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for Team<'a> {
+    fn get_size(&self) -> usize {
+        self.users.iter().map(|s| 1 + sizeof_var_length(s.get_size())).sum::<usize>()
+        + self.office.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.get_size()))
+        + self.default_role.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+    }
+
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        self.users.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+        + self.office.as_ref().map_or(0, |m| 1 + sizeof_message_cached(m, sizes))
+        + self.default_role.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        for s in &self.users { r.write_message_with_tag(10, s)? }
+        if let Some(ref s) = self.office { r.write_message_with_tag(18, s)?; }
+        if let Some(ref s) = self.default_role { r.write_enum_with_tag(24, *s as i32)?; }
+        Ok(())
+    }
+}
+
+}
+
+}
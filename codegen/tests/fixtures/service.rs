@@ -0,0 +1,111 @@
+//! Automatically generated rust module for 'service.proto' file
+
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+
+use std::borrow::Cow;
+use quick_protobuf::{MessageRead, MessageWrite, BytesReader, Writer, WriterBackend, Result};
+use quick_protobuf::sizeofs::*;
+use quick_protobuf::errors::ErrorKind;
+
+pub mod greet {
+
+use super::*;
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct HelloRequest<'a> {
+    pub name: Option<Cow<'a, str>>,
+}
+
+impl<'a> MessageRead<'a> for HelloRequest<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => msg.name = Some(Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_field("HelloRequest", "name"))?)),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("HelloRequest"))?; }
+                Err(e) => return Err(e.in_message("HelloRequest")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for HelloRequest<'a> {
+    fn get_size(&self) -> usize {
+        self.name.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.name { r.write_string_with_tag(10, s)?; }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct HelloReply<'a> {
+    pub message: Option<Cow<'a, str>>,
+}
+
+impl<'a> MessageRead<'a> for HelloReply<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => msg.message = Some(Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_field("HelloReply", "message"))?)),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("HelloReply"))?; }
+                Err(e) => return Err(e.in_message("HelloReply")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for HelloReply<'a> {
+    fn get_size(&self) -> usize {
+        self.message.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.message { r.write_string_with_tag(10, s)?; }
+        Ok(())
+    }
+}
+
+pub trait Greeter {
+    fn SayHello<'a>(&mut self, request: greet::HelloRequest<'a>) -> Result<greet::HelloReply<'a>>;
+    fn SayHellos<'a>(&mut self, requests: Vec<greet::HelloRequest<'a>>) -> Result<Vec<greet::HelloReply<'a>>>;
+}
+
+pub struct GreeterDispatcher<S: Greeter> {
+    pub service: S,
+}
+
+impl<S: Greeter> GreeterDispatcher<S> {
+    pub fn new(service: S) -> Self {
+        GreeterDispatcher { service: service }
+    }
+
+    pub fn dispatch(&mut self, method: &str, request: &[u8]) -> Result<Vec<u8>> {
+        let mut r = BytesReader::from_bytes(request);
+        let mut response = Vec::new();
+        {
+            let mut w = Writer::new(&mut response);
+            match method {
+                "SayHello" | "/greet.Greeter/SayHello" => {
+                    let requests = greet::HelloRequest::from_reader(&mut r, request)?;
+                    self.service.SayHello(requests)?.write_message(&mut w)?;
+                }
+                "SayHellos" | "/greet.Greeter/SayHellos" => {
+                    let requests = r.messages(request, greet::HelloRequest::from_reader).collect::<Result<Vec<_>>>()?;
+                    w.write_messages(&self.service.SayHellos(requests)?)?;
+                }
+                _ => return Err(ErrorKind::UnknownMethod(method.to_string()).into()),
+            }
+        }
+        Ok(response)
+    }
+}
+
+}
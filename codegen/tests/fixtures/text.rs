@@ -0,0 +1,346 @@
+//! Automatically generated rust module for 'text.proto' file
+
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+
+use std::borrow::Cow;
+use quick_protobuf::{MessageRead, MessageWrite, BytesReader, Writer, WriterBackend, Result};
+use quick_protobuf::sizeofs::*;
+use quick_protobuf::text::{TextRead, TextWrite, TextWriter, Value as TextValue};
+use super::text_enums;
+
+pub mod text {
+
+use super::*;
+
+pub mod test {
+
+use super::*;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Level {
+    LOW = 1,
+    HIGH = 2,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Level::LOW
+    }
+}
+
+impl From<i32> for Level {
+    fn from(i: i32) -> Self {
+        match i {
+            1 => Level::LOW,
+            2 => Level::HIGH,
+            _ => Self::default(),
+        }
+    }
+}
+
+impl TextWrite for Level {
+    fn write_text(&self, w: &mut TextWriter) {
+        w.write_ident(match *self {
+            Level::LOW => "LOW",
+            Level::HIGH => "HIGH",
+        });
+    }
+}
+
+impl TextRead for Level {
+    fn from_text(v: &TextValue) -> Result<Self> {
+        match v.as_ident() {
+            Ok("LOW") => Ok(Level::LOW),
+            Ok("HIGH") => Ok(Level::HIGH),
+            Ok(_) => Err(v.unknown_value("Level")),
+            Err(_) => v.as_i32().map(Level::from),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Alert<'a> {
+    pub title: Option<Cow<'a, str>>,
+    pub kind: Option<text::test::mod_Alert::Kind>,
+    pub level: Option<text::test::Level>,
+    pub count: Option<i64>,
+    pub payload: Option<Cow<'a, [u8]>>,
+    pub children: Vec<text::test::Alert<'a>>,
+    pub values: Vec<i32>,
+    pub priority: Option<text_enums::text::enums::Priority>,
+    pub source: Option<text::test::mod_Alert::Source<'a>>,
+}
+
+impl<'a> MessageRead<'a> for Alert<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => msg.title = Some(Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_field("Alert", "title"))?)),
+                Ok(16) => msg.kind = Some(r.read_enum(bytes).map_err(|e| e.in_field("Alert", "kind"))?),
+                Ok(24) => msg.level = Some(r.read_enum(bytes).map_err(|e| e.in_field("Alert", "level"))?),
+                Ok(32) => msg.count = Some(r.read_int64(bytes).map_err(|e| e.in_field("Alert", "count"))?),
+                Ok(42) => msg.payload = Some(Cow::Borrowed(r.read_bytes(bytes).map_err(|e| e.in_field("Alert", "payload"))?)),
+                Ok(50) => msg.children.push(r.read_message(bytes, text::test::Alert::from_reader).map_err(|e| e.in_repeated_field("Alert", "children", msg.children.len()))?),
+                Ok(58) => msg.values = r.read_packed(bytes, |r, bytes| r.read_int32(bytes)).map_err(|e| e.in_field("Alert", "values"))?,
+                Ok(64) => msg.priority = Some(r.read_enum(bytes).map_err(|e| e.in_field("Alert", "priority"))?),
+                Ok(75) => msg.source = Some(r.read_group(bytes, 75, text::test::mod_Alert::Source::from_reader).map_err(|e| e.in_field("Alert", "source"))?),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("Alert"))?; }
+                Err(e) => return Err(e.in_message("Alert")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for Alert<'a> {
+    fn get_size(&self) -> usize {
+        self.title.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + self.kind.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+        + self.level.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+        + self.count.as_ref().map_or(0, |m| 1 + sizeof_int64(*m))
+        + self.payload.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + self.children.iter().map(|s| 1 + sizeof_var_length(s.get_size())).sum::<usize>()
+        + if self.values.is_empty() { 0 } else { 1 + sizeof_var_length(self.values.iter().map(|s| sizeof_int32(*s)).sum::<usize>()) }
+        + self.priority.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+        + self.source.as_ref().map_or(0, |m| 2 + m.get_size())
+    }
+
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        self.title.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + self.kind.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+        + self.level.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+        + self.count.as_ref().map_or(0, |m| 1 + sizeof_int64(*m))
+        + self.payload.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + self.children.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+        + if self.values.is_empty() { 0 } else { 1 + sizeof_var_length(self.values.iter().map(|s| sizeof_int32(*s)).sum::<usize>()) }
+        + self.priority.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+        + self.source.as_ref().map_or(0, |m| 2 + m.get_size_cached(sizes))
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.title { r.write_string_with_tag(10, s)?; }
+        if let Some(ref s) = self.kind { r.write_enum_with_tag(16, *s as i32)?; }
+        if let Some(ref s) = self.level { r.write_enum_with_tag(24, *s as i32)?; }
+        if let Some(ref s) = self.count { r.write_int64_with_tag(32, *s)?; }
+        if let Some(ref s) = self.payload { r.write_bytes_with_tag(42, s)?; }
+        for s in &self.children { r.write_message_with_tag(50, s)? }
+        r.write_packed_repeated_field_with_tag(58, &self.values, |r, m| r.write_int32(*m), &|m| sizeof_int32(*m))?;
+        if let Some(ref s) = self.priority { r.write_enum_with_tag(64, *s as i32)?; }
+        if let Some(ref s) = self.source { r.write_group_with_tag(75, s)?; }
+        Ok(())
+    }
+}
+
+impl<'a> TextWrite for Alert<'a> {
+    fn write_text(&self, w: &mut TextWriter) {
+        w.begin_message();
+        if let Some(ref s) = self.title { w.key("title"); w.write_string(s); }
+        if let Some(ref s) = self.kind { w.key("kind"); s.write_text(w); }
+        if let Some(ref s) = self.level { w.key("level"); s.write_text(w); }
+        if let Some(ref s) = self.count { w.key("count"); w.write_i64(*s); }
+        if let Some(ref s) = self.payload { w.key("payload"); w.write_bytes(s); }
+        for s in &self.children { w.key("children"); s.write_text(w); }
+        for s in &self.values { w.key("values"); w.write_i32(*s); }
+        if let Some(ref s) = self.priority { w.key("priority"); s.write_text(w); }
+        if let Some(ref s) = self.source { w.key("Source"); s.write_text(w); }
+        w.end_message();
+    }
+}
+
+impl<'a> TextRead for Alert<'a> {
+    fn from_text(v: &TextValue) -> Result<Self> {
+        let mut msg = Self::default();
+        for f in v.as_message()? {
+            let v = &f.value;
+            match &*f.name {
+                "title" => msg.title = Some(Cow::Owned(v.as_str()?.to_string())),
+                "kind" => msg.kind = Some(text::test::mod_Alert::Kind::from_text(v)?),
+                "level" => msg.level = Some(text::test::Level::from_text(v)?),
+                "count" => msg.count = Some(v.as_i64()?),
+                "payload" => msg.payload = Some(Cow::Owned(v.as_bytes()?.to_vec())),
+                "children" => for v in v.values() { msg.children.push(text::test::Alert::from_text(v)?); },
+                "values" => for v in v.values() { msg.values.push(v.as_i32()?); },
+                "priority" => msg.priority = Some(text_enums::text::enums::Priority::from_text(v)?),
+                "Source" | "source" => msg.source = Some(text::test::mod_Alert::Source::from_text(v)?),
+                _ => return Err(f.unknown()),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+pub mod mod_Alert {
+
+use super::*;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Kind {
+    INFO = 1,
+    ERROR = 2,
+}
+
+impl Default for Kind {
+    fn default() -> Self {
+        Kind::INFO
+    }
+}
+
+impl From<i32> for Kind {
+    fn from(i: i32) -> Self {
+        match i {
+            1 => Kind::INFO,
+            2 => Kind::ERROR,
+            _ => Self::default(),
+        }
+    }
+}
+
+impl TextWrite for Kind {
+    fn write_text(&self, w: &mut TextWriter) {
+        w.write_ident(match *self {
+            Kind::INFO => "INFO",
+            Kind::ERROR => "ERROR",
+        });
+    }
+}
+
+impl TextRead for Kind {
+    fn from_text(v: &TextValue) -> Result<Self> {
+        match v.as_ident() {
+            Ok("INFO") => Ok(Kind::INFO),
+            Ok("ERROR") => Ok(Kind::ERROR),
+            Ok(_) => Err(v.unknown_value("Kind")),
+            Err(_) => v.as_i32().map(Kind::from),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Source<'a> {
+    pub host: Option<Cow<'a, str>>,
+    pub port: Vec<text::test::mod_Alert::mod_Source::Port>,
+}
+
+impl<'a> MessageRead<'a> for Source<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(82) => msg.host = Some(Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_field("Source", "host"))?)),
+                Ok(91) => msg.port.push(r.read_group(bytes, 91, text::test::mod_Alert::mod_Source::Port::from_reader).map_err(|e| e.in_repeated_field("Source", "port", msg.port.len()))?),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("Source"))?; }
+                Err(e) => return Err(e.in_message("Source")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for Source<'a> {
+    fn get_size(&self) -> usize {
+        self.host.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + self.port.iter().map(|s| 2 + s.get_size()).sum::<usize>()
+    }
+
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        self.host.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + self.port.iter().map(|s| 2 + s.get_size_cached(sizes)).sum::<usize>()
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.host { r.write_string_with_tag(82, s)?; }
+        for s in &self.port { r.write_group_with_tag(91, s)? }
+        Ok(())
+    }
+}
+
+impl<'a> TextWrite for Source<'a> {
+    fn write_text(&self, w: &mut TextWriter) {
+        w.begin_message();
+        if let Some(ref s) = self.host { w.key("host"); w.write_string(s); }
+        for s in &self.port { w.key("Port"); s.write_text(w); }
+        w.end_message();
+    }
+}
+
+impl<'a> TextRead for Source<'a> {
+    fn from_text(v: &TextValue) -> Result<Self> {
+        let mut msg = Self::default();
+        for f in v.as_message()? {
+            let v = &f.value;
+            match &*f.name {
+                "host" => msg.host = Some(Cow::Owned(v.as_str()?.to_string())),
+                "Port" | "port" => for v in v.values() { msg.port.push(text::test::mod_Alert::mod_Source::Port::from_text(v)?); },
+                _ => return Err(f.unknown()),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+pub mod mod_Source {
+
+use super::*;
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Port {
+    pub number: u32,
+}
+
+impl<'a> MessageRead<'a> for Port {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(96) => msg.number = r.read_uint32(bytes).map_err(|e| e.in_field("Port", "number"))?,
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("Port"))?; }
+                Err(e) => return Err(e.in_message("Port")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for Port {
+    fn get_size(&self) -> usize {
+        1 + sizeof_uint32(self.number)
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        r.write_uint32_with_tag(96, self.number)?;
+        Ok(())
+    }
+}
+
+impl TextWrite for Port {
+    fn write_text(&self, w: &mut TextWriter) {
+        w.begin_message();
+        w.key("number"); w.write_u32(self.number);
+        w.end_message();
+    }
+}
+
+impl TextRead for Port {
+    fn from_text(v: &TextValue) -> Result<Self> {
+        let mut msg = Self::default();
+        for f in v.as_message()? {
+            let v = &f.value;
+            match &*f.name {
+                "number" => msg.number = v.as_u32()?,
+                _ => return Err(f.unknown()),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+}
+
+}
+
+}
+
+}
@@ -0,0 +1,64 @@
+//! Automatically generated rust module for 'text_enums.proto' file
+
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+
+use std::borrow::Cow;
+use quick_protobuf::{MessageRead, MessageWrite, BytesReader, Writer, WriterBackend, Result};
+use quick_protobuf::sizeofs::*;
+use quick_protobuf::text::{TextRead, TextWrite, TextWriter, Value as TextValue};
+
+pub mod text {
+
+use super::*;
+
+pub mod enums {
+
+use super::*;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Priority {
+    NORMAL = 1,
+    URGENT = 2,
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Priority::NORMAL
+    }
+}
+
+impl From<i32> for Priority {
+    fn from(i: i32) -> Self {
+        match i {
+            1 => Priority::NORMAL,
+            2 => Priority::URGENT,
+            _ => Self::default(),
+        }
+    }
+}
+
+impl TextWrite for Priority {
+    fn write_text(&self, w: &mut TextWriter) {
+        w.write_ident(match *self {
+            Priority::NORMAL => "NORMAL",
+            Priority::URGENT => "URGENT",
+        });
+    }
+}
+
+impl TextRead for Priority {
+    fn from_text(v: &TextValue) -> Result<Self> {
+        match v.as_ident() {
+            Ok("NORMAL") => Ok(Priority::NORMAL),
+            Ok("URGENT") => Ok(Priority::URGENT),
+            Ok(_) => Err(v.unknown_value("Priority")),
+            Err(_) => v.as_i32().map(Priority::from),
+        }
+    }
+}
+
+}
+
+}
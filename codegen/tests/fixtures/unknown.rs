@@ -0,0 +1,105 @@
+//! Automatically generated rust module for 'unknown.proto' file
+
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+
+use std::borrow::Cow;
+use quick_protobuf::{MessageRead, MessageWrite, BytesReader, Writer, WriterBackend, Result, UnknownFields};
+use quick_protobuf::sizeofs::*;
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Old {
+    pub id: Option<u32>,
+    pub unknown_fields: UnknownFields<'static>,
+}
+
+impl<'a> MessageRead<'a> for Old {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.id = Some(r.read_uint32(bytes).map_err(|e| e.in_field("Old", "id"))?),
+                Ok(t) => msg.unknown_fields.push(t, Cow::Owned(r.read_unknown_field(bytes, t).map_err(|e| e.in_message("Old"))?.to_vec())),
+                Err(e) => return Err(e.in_message("Old")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for Old {
+    fn get_size(&self) -> usize {
+        self.id.as_ref().map_or(0, |m| 1 + sizeof_uint32(*m))
+        + self.unknown_fields.get_size()
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.id { r.write_uint32_with_tag(8, *s)?; }
+        r.write_unknown_fields(&self.unknown_fields)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct New<'a> {
+    pub id: Option<u32>,
+    pub name: Option<Cow<'a, str>>,
+    pub children: Vec<New<'a>>,
+    pub stamp: Option<u64>,
+    pub flags: Option<u32>,
+    pub deltas: Vec<i32>,
+    pub unknown_fields: UnknownFields<'a>,
+}
+
+impl<'a> MessageRead<'a> for New<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.id = Some(r.read_uint32(bytes).map_err(|e| e.in_field("New", "id"))?),
+                Ok(18) => msg.name = Some(Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_field("New", "name"))?)),
+                Ok(26) => msg.children.push(r.read_message(bytes, New::from_reader).map_err(|e| e.in_repeated_field("New", "children", msg.children.len()))?),
+                Ok(33) => msg.stamp = Some(r.read_fixed64(bytes).map_err(|e| e.in_field("New", "stamp"))?),
+                Ok(45) => msg.flags = Some(r.read_fixed32(bytes).map_err(|e| e.in_field("New", "flags"))?),
+                Ok(50) => msg.deltas = r.read_packed(bytes, |r, bytes| r.read_sint32(bytes)).map_err(|e| e.in_field("New", "deltas"))?,
+                Ok(t) => msg.unknown_fields.push(t, Cow::Borrowed(r.read_unknown_field(bytes, t).map_err(|e| e.in_message("New"))?)),
+                Err(e) => return Err(e.in_message("New")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for New<'a> {
+    fn get_size(&self) -> usize {
+        self.id.as_ref().map_or(0, |m| 1 + sizeof_uint32(*m))
+        + self.name.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + self.children.iter().map(|s| 1 + sizeof_var_length(s.get_size())).sum::<usize>()
+        + self.stamp.as_ref().map_or(0, |_| 1 + 8)
+        + self.flags.as_ref().map_or(0, |_| 1 + 4)
+        + if self.deltas.is_empty() { 0 } else { 1 + sizeof_var_length(self.deltas.iter().map(|s| sizeof_sint32(*s)).sum::<usize>()) }
+        + self.unknown_fields.get_size()
+    }
+
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        self.id.as_ref().map_or(0, |m| 1 + sizeof_uint32(*m))
+        + self.name.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + self.children.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+        + self.stamp.as_ref().map_or(0, |_| 1 + 8)
+        + self.flags.as_ref().map_or(0, |_| 1 + 4)
+        + if self.deltas.is_empty() { 0 } else { 1 + sizeof_var_length(self.deltas.iter().map(|s| sizeof_sint32(*s)).sum::<usize>()) }
+        + self.unknown_fields.get_size()
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.id { r.write_uint32_with_tag(8, *s)?; }
+        if let Some(ref s) = self.name { r.write_string_with_tag(18, s)?; }
+        for s in &self.children { r.write_message_with_tag(26, s)? }
+        if let Some(ref s) = self.stamp { r.write_fixed64_with_tag(33, *s)?; }
+        if let Some(ref s) = self.flags { r.write_fixed32_with_tag(45, *s)?; }
+        r.write_packed_repeated_field_with_tag(50, &self.deltas, |r, m| r.write_sint32(*m), &|m| sizeof_sint32(*m))?;
+        r.write_unknown_fields(&self.unknown_fields)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,368 @@
+//! Builds the modules generated from the .proto files of `tests/fixtures` and round-trips
+//! messages through them
+
+extern crate pb_rs;
+extern crate quick_protobuf;
+
+mod fixtures;
+
+use std::borrow::Cow;
+use std::env;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+use pb_rs::Config;
+use quick_protobuf::{BytesReader, MessageRead, MessageWrite, Writer, Result, deserialize_from_slice};
+use quick_protobuf::errors::ErrorKind;
+
+/// The fixtures, with the options they are generated with in `generate_modules.sh`
+fn fixtures() -> Vec<(&'static str, Config)> {
+    let mut json = Config::default();
+    json.json(true);
+    let mut text = Config::default();
+    text.text(true);
+    let mut unknown = Config::default();
+    unknown.keep_unknown_fields(true);
+    vec![
+        ("groups.proto", Config::default()),
+        ("oneof.proto", Config::default()),
+        ("maps.proto", Config::default()),
+        ("nested.proto", Config::default()),
+        ("imports.proto", Config::default()),
+        ("package.proto", Config::default()),
+        ("service.proto", Config::default()),
+        ("json.proto", json),
+        ("text.proto", text),
+        ("unknown.proto", unknown),
+    ]
+}
+
+fn read_file(path: &Path) -> String {
+    let mut content = String::new();
+    File::open(path).and_then(|mut f| f.read_to_string(&mut content))
+        .unwrap_or_else(|e| panic!("cannot read {}: {}", path.display(), e));
+    content
+}
+
+fn serialize<M: MessageWrite>(m: &M) -> Vec<u8> {
+    let mut buf = Vec::new();
+    m.write_message(&mut Writer::new(&mut buf)).unwrap();
+    assert_eq!(buf.len(), m.get_size());
+    buf
+}
+
+fn roundtrip<'a, M>(m: &M, buf: &'a mut Vec<u8>) -> M
+    where M: MessageWrite + MessageRead<'a>
+{
+    *buf = serialize(m);
+    deserialize_from_slice(buf).unwrap()
+}
+
+#[test]
+fn fixtures_are_up_to_date(){
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    for (proto, mut config) in fixtures() {
+        let out_dir = env::temp_dir().join(format!("pb-rs-fixtures-{}", proto));
+        let _ = fs::remove_dir_all(&out_dir);
+        fs::create_dir_all(&out_dir).unwrap();
+        config.out_dir(&out_dir).compile(&[dir.join(proto)]).unwrap();
+        for entry in fs::read_dir(&out_dir).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            if name == "mod.rs" {
+                continue;
+            }
+            assert!(read_file(&path) == read_file(&dir.join(&name)),
+                    "tests/fixtures/{} is out of date, run generate_modules.sh", name);
+        }
+        let _ = fs::remove_dir_all(&out_dir);
+    }
+}
+
+#[test]
+fn wr_groups(){
+    use fixtures::groups::SearchResponse;
+    use fixtures::groups::mod_SearchResponse::Hit;
+    use fixtures::groups::mod_SearchResponse::mod_Hit::Snippet;
+
+    let v = SearchResponse {
+        hit: vec![
+            Hit {
+                url: Cow::Borrowed("a"),
+                title: Some(Cow::Borrowed("b")),
+                snippet: Some(Snippet { lines: vec![Cow::Borrowed("c"), Cow::Borrowed("d")] }),
+            },
+            Hit { url: Cow::Borrowed("e"), ..Hit::default() },
+        ],
+        total: Some(2),
+    };
+    let mut buf = Vec::new();
+    assert_eq!(v, roundtrip(&v, &mut buf));
+}
+
+#[test]
+fn wr_oneof(){
+    use fixtures::oneof::{Point, Shape};
+    use fixtures::oneof::mod_Shape::OneOfgeometry;
+
+    let shapes = vec![
+        Shape { name: Some(Cow::Borrowed("p")), geometry: OneOfgeometry::point(Point { x: 1, y: -2 }), tags: vec![1, 2] },
+        Shape { geometry: OneOfgeometry::radius(1.5), ..Shape::default() },
+        Shape { geometry: OneOfgeometry::path(Cow::Borrowed("M 0 0")), ..Shape::default() },
+        Shape::default(),
+    ];
+    for v in &shapes {
+        let mut buf = Vec::new();
+        assert_eq!(*v, roundtrip(v, &mut buf));
+    }
+
+    // the last field of the oneof read wins
+    let mut buf = serialize(&shapes[0]);
+    buf.extend(serialize(&shapes[1]));
+    let v: Shape = deserialize_from_slice(&buf).unwrap();
+    assert_eq!(OneOfgeometry::radius(1.5), v.geometry);
+    assert_eq!(Some(Cow::Borrowed("p")), v.name);
+}
+
+#[test]
+fn wr_maps(){
+    use fixtures::maps::{Color, Maps, Value};
+
+    let mut v = Maps::default();
+    v.counts.insert(Cow::Borrowed("a"), 1);
+    v.counts.insert(Cow::Borrowed(""), 0);
+    v.names.insert(-1, Cow::Borrowed("minus one"));
+    v.values.insert(Cow::Borrowed("v"), Value { text: Some(Cow::Borrowed("text")) });
+    v.values.insert(Cow::Borrowed("empty"), Value::default());
+    v.colors.insert(3, Color::GREEN);
+    v.blobs.insert(true, Cow::Borrowed(b"\x00\x01"));
+    let mut buf = Vec::new();
+    assert_eq!(v, roundtrip(&v, &mut buf));
+
+    // a later entry replaces an earlier one with the same key
+    let mut w = Maps::default();
+    w.counts.insert(Cow::Borrowed("a"), 2);
+    let mut buf = serialize(&v);
+    buf.extend(serialize(&w));
+    let read: Maps = deserialize_from_slice(&buf).unwrap();
+    assert_eq!(Some(&2), read.counts.get("a"));
+    assert_eq!(v.names, read.names);
+}
+
+#[test]
+fn wr_nested(){
+    use fixtures::nested::{Document, Index};
+    use fixtures::nested::mod_Document::{Section, Status};
+    use fixtures::nested::mod_Document::mod_Section::{Kind, Line};
+
+    let line = |content, kind| Line { content: Some(Cow::Borrowed(content)), kind: Some(kind) };
+    let v = Document {
+        status: Some(Status::PUBLISHED),
+        sections: vec![Section {
+            title: Some(Cow::Borrowed("a")),
+            lines: vec![line("fn main() {}", Kind::CODE)],
+            subsections: vec![Section { lines: vec![line("b", Kind::TEXT)], ..Section::default() }],
+        }],
+        summary: Some(line("c", Kind::TEXT)),
+        default_kind: Some(Kind::CODE),
+    };
+    let mut buf = Vec::new();
+    assert_eq!(v, roundtrip(&v, &mut buf));
+
+    let v = Index { lines: vec![line("d", Kind::CODE)], status: Some(Status::DRAFT) };
+    let mut buf = Vec::new();
+    assert_eq!(v, roundtrip(&v, &mut buf));
+}
+
+#[test]
+fn wr_imports(){
+    use fixtures::common::{Measure, Unit};
+    use fixtures::imports::Reading;
+
+    let v = Reading {
+        sensor: Some(Cow::Borrowed("s")),
+        measures: vec![
+            Measure { value: Some(1.5), unit: Some(Unit::METER) },
+            Measure { value: Some(-2.), unit: None },
+        ],
+        default_unit: Some(Unit::SECOND),
+    };
+    let mut buf = Vec::new();
+    assert_eq!(v, roundtrip(&v, &mut buf));
+}
+
+#[test]
+fn wr_package(){
+    use fixtures::common::Measure;
+    use fixtures::package::app::v1::{Role, Team, User};
+    use fixtures::package::app::v1::mod_User::Address;
+
+    let address = Address { city: Some(Cow::Borrowed("Paris")) };
+    let v = Team {
+        users: vec![
+            User { id: Some(1), address: Some(address.clone()), role: Some(Role::ADMIN), heights: vec![] },
+            User { id: Some(2), heights: vec![Measure { value: Some(1.8), unit: None }], ..User::default() },
+        ],
+        office: Some(address),
+        default_role: Some(Role::GUEST),
+    };
+    let mut buf = Vec::new();
+    assert_eq!(v, roundtrip(&v, &mut buf));
+}
+
+struct Greeter;
+
+impl fixtures::service::greet::Greeter for Greeter {
+    fn SayHello<'a>(&mut self, request: fixtures::service::greet::HelloRequest<'a>)
+        -> Result<fixtures::service::greet::HelloReply<'a>>
+    {
+        let name = request.name.unwrap_or_default();
+        Ok(fixtures::service::greet::HelloReply { message: Some(Cow::Owned(format!("Hello {}", name))) })
+    }
+
+    fn SayHellos<'a>(&mut self, requests: Vec<fixtures::service::greet::HelloRequest<'a>>)
+        -> Result<Vec<fixtures::service::greet::HelloReply<'a>>>
+    {
+        requests.into_iter().map(|r| self.SayHello(r)).collect()
+    }
+}
+
+#[test]
+fn dispatch_service(){
+    use fixtures::service::greet::{GreeterDispatcher, HelloReply, HelloRequest};
+
+    let mut dispatcher = GreeterDispatcher::new(Greeter);
+    let request = serialize(&HelloRequest { name: Some(Cow::Borrowed("you")) });
+    for method in &["SayHello", "/greet.Greeter/SayHello"] {
+        let response = dispatcher.dispatch(method, &request).unwrap();
+        let reply: HelloReply = deserialize_from_slice(&response).unwrap();
+        assert_eq!(Some(Cow::Borrowed("Hello you")), reply.message);
+    }
+
+    let mut requests = Vec::new();
+    {
+        let names = ["a", "b"].iter().map(|n| HelloRequest { name: Some(Cow::Borrowed(*n)) }).collect::<Vec<_>>();
+        Writer::new(&mut requests).write_messages(&names).unwrap();
+    }
+    let response = dispatcher.dispatch("SayHellos", &requests).unwrap();
+    let replies = BytesReader::from_bytes(&response).messages(&response, HelloReply::from_reader)
+        .map(|r| r.unwrap().message.unwrap().into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["Hello a", "Hello b"], replies);
+
+    match dispatcher.dispatch("SayGoodbye", &request).unwrap_err().into_kind() {
+        ErrorKind::UnknownMethod(ref m) if m == "SayGoodbye" => (),
+        e => panic!("Expecting UnknownMethod, got {:?}", e),
+    }
+}
+
+#[test]
+fn json_roundtrip(){
+    use fixtures::json::json::test::{Alert, Level};
+    use fixtures::json::json::test::mod_Alert::Kind;
+    use fixtures::json_enums::json::enums::Priority;
+
+    let mut v = Alert {
+        title: Some(Cow::Borrowed("disk \"full\"")),
+        kind: Some(Kind::ERROR),
+        level: Some(Level::HIGH),
+        count: -1 << 40,
+        payload: Some(Cow::Borrowed(b"\x00\xff")),
+        children: vec![Alert { title: Some(Cow::Borrowed("child")), ..Alert::default() }],
+        priority: Some(Priority::URGENT),
+        ..Alert::default()
+    };
+    v.labels.insert(Cow::Borrowed("host"), 3);
+    let json = quick_protobuf::json::to_string(&v);
+    assert!(json.contains("\"kind\":\"ERROR\""), "{}", json);
+    assert!(json.contains("\"priority\":\"URGENT\""), "{}", json);
+    assert!(json.contains("\"count\":\"-1099511627776\""), "{}", json);
+    let read: Alert = quick_protobuf::json::from_str(&json).unwrap();
+    assert_eq!(v, read);
+}
+
+#[test]
+fn json_proto3_defaults(){
+    use fixtures::json::json::test::{Alert, Level};
+    use fixtures::json::json::test::mod_Alert::Kind;
+    use fixtures::json_enums::json::enums::Priority;
+
+    // proto3 fields set to their type default are not written, as if they were not set
+    let v = Alert {
+        title: Some(Cow::Borrowed("")),
+        kind: Some(Kind::INFO),
+        level: Some(Level::LOW),
+        count: 0,
+        payload: Some(Cow::Borrowed(b"")),
+        muted: Some(false),
+        ..Alert::default()
+    };
+    assert_eq!("{}", quick_protobuf::json::to_string(&v));
+
+    let v = Alert { muted: Some(true), priority: Some(Priority::URGENT), ..Alert::default() };
+    assert_eq!(r#"{"priority":"URGENT","muted":true}"#, quick_protobuf::json::to_string(&v));
+}
+
+#[test]
+fn text_roundtrip(){
+    use fixtures::text::text::test::{Alert, Level};
+    use fixtures::text::text::test::mod_Alert::{Kind, Source};
+    use fixtures::text::text::test::mod_Alert::mod_Source::Port;
+    use fixtures::text_enums::text::enums::Priority;
+
+    let v = Alert {
+        title: Some(Cow::Borrowed("disk \"full\"")),
+        kind: Some(Kind::ERROR),
+        level: Some(Level::HIGH),
+        count: Some(-1 << 40),
+        payload: Some(Cow::Borrowed(b"\x00\xff")),
+        children: vec![Alert { title: Some(Cow::Borrowed("child")), ..Alert::default() }],
+        values: vec![1, -2],
+        priority: Some(Priority::URGENT),
+        source: Some(Source { host: Some(Cow::Borrowed("db")), port: vec![Port { number: 5432 }] }),
+    };
+    let text = quick_protobuf::text::to_string(&v);
+    assert!(text.contains("kind: ERROR\n"), "{}", text);
+    assert!(text.contains("priority: URGENT\n"), "{}", text);
+    assert!(text.contains("children {\n  title: \"child\"\n}\n"), "{}", text);
+    // groups are named after their type
+    assert!(text.contains("Source {\n  host: \"db\"\n  Port {\n    number: 5432\n  }\n}\n"), "{}", text);
+    let read: Alert = quick_protobuf::text::from_str(&text).unwrap();
+    assert_eq!(v, read);
+
+    // the lowercased field names of groups are accepted too
+    let read: Alert = quick_protobuf::text::from_str("source { host: \"db\" port { number: 5432 } }").unwrap();
+    assert_eq!(v.source, read.source);
+}
+
+#[test]
+fn unknown_fields_roundtrip(){
+    use fixtures::unknown::{New, Old};
+
+    let v = New {
+        id: Some(1),
+        name: Some(Cow::Borrowed("new")),
+        children: vec![New { id: Some(2), deltas: vec![-1, 1], ..New::default() }, New::default()],
+        stamp: Some(u64::MAX),
+        flags: Some(7),
+        deltas: vec![3, -300],
+        ..New::default()
+    };
+
+    // an old reader keeps the fields it doesn't know, and writes them back
+    let old: Old = deserialize_from_slice(&serialize(&v)).unwrap();
+    assert_eq!(Some(1), old.id);
+    assert!(!old.unknown_fields.is_empty());
+    let buf = serialize(&old);
+    let new: New = deserialize_from_slice(&buf).unwrap();
+    assert_eq!(v, new);
+    assert!(new.unknown_fields.is_empty());
+
+    // even once modified
+    let mut old = old;
+    old.id = Some(3);
+    let buf = serialize(&old);
+    let new: New = deserialize_from_slice(&buf).unwrap();
+    assert_eq!(New { id: Some(3), ..v }, new);
+}
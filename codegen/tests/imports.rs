@@ -0,0 +1,62 @@
+//! Loads the .proto files of `tests/imports` along with the files they import
+
+extern crate pb_rs;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use pb_rs::errors::ErrorKind;
+use pb_rs::imports::{load, resolve};
+
+fn dir() -> PathBuf {
+    fs::canonicalize(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/imports")).unwrap()
+}
+
+#[test]
+fn imports_are_loaded_before_importing_files() {
+    let dir = dir();
+    let files = load(&dir.join("main.proto"), &[dir.join("include")]).unwrap();
+    let paths = files.iter().map(|f| f.path.clone()).collect::<Vec<_>>();
+    assert_eq!(paths, vec![dir.join("include/a.proto"), dir.join("include/lib/b.proto"), dir.join("main.proto")]);
+    assert_eq!(files[0].imports, Vec::<usize>::new());
+    assert_eq!(files[1].imports, vec![0]);
+    assert_eq!(files[2].imports, vec![1, 0]);
+
+    // `lib.A` only resolves with `include/a.proto`, not the shadowed `a.proto` of package `local`
+    let descriptors = resolve(&files).unwrap();
+    assert_eq!(descriptors.len(), 3);
+    let main = &descriptors[2].messages[0];
+    assert_eq!(main.name, "Main");
+    assert_eq!(main.fields.iter().map(|f| &*f.typ).collect::<Vec<_>>(), vec!["lib.B", "lib.A"]);
+}
+
+#[test]
+fn imports_not_found() {
+    let dir = dir();
+    let err = load(&dir.join("main.proto"), &[]).unwrap_err();
+    match *err.kind() {
+        ErrorKind::ImportNotFound(ref import, ref file) => {
+            assert_eq!(import, "lib/b.proto");
+            assert_eq!(Path::new(file), dir.join("main.proto"));
+        }
+        ref e => panic!("expecting ImportNotFound, got {:?}", e),
+    }
+    assert_eq!(err.to_string(),
+               format!("cannot find 'lib/b.proto' imported by '{}' in the include paths",
+                       dir.join("main.proto").display()));
+}
+
+#[test]
+fn imports_cycle() {
+    let dir = dir();
+    let err = load(&dir.join("cycle_a.proto"), &[]).unwrap_err();
+    let cycle = format!("{} -> {} -> {}",
+                        dir.join("cycle_a.proto").display(),
+                        dir.join("cycle_b.proto").display(),
+                        dir.join("cycle_a.proto").display());
+    match *err.kind() {
+        ErrorKind::ImportCycle(ref c) => assert_eq!(*c, cycle),
+        ref e => panic!("expecting ImportCycle, got {:?}", e),
+    }
+    assert_eq!(err.to_string(), format!("import cycle: {}", cycle));
+}
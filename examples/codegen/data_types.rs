@@ -4,9 +4,8 @@
 #![allow(non_upper_case_globals)]
 #![allow(non_camel_case_types)]
 
-use std::io::{Write};
 use std::borrow::Cow;
-use quick_protobuf::{MessageWrite, BytesReader, Writer, Result};
+use quick_protobuf::{MessageRead, MessageWrite, BytesReader, Writer, WriterBackend, Result};
 use quick_protobuf::sizeofs::*;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -36,14 +35,14 @@ pub struct BarMessage {
     pub b_required_int32: i32,
 }
 
-impl BarMessage {
-    pub fn from_reader(r: &mut BytesReader, bytes: &[u8]) -> Result<Self> {
+impl<'a> MessageRead<'a> for BarMessage {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
         let mut msg = Self::default();
         while !r.is_eof() {
             match r.next_tag(bytes) {
-                Ok(8) => msg.b_required_int32 = r.read_int32(bytes)?,
-                Ok(t) => { r.read_unknown(bytes, t)?; }
-                Err(e) => return Err(e),
+                Ok(8) => msg.b_required_int32 = r.read_int32(bytes).map_err(|e| e.in_field("BarMessage", "b_required_int32"))?,
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("BarMessage"))?; }
+                Err(e) => return Err(e.in_message("BarMessage")),
             }
         }
         Ok(msg)
@@ -55,7 +54,7 @@ impl MessageWrite for BarMessage {
         1 + sizeof_int32(self.b_required_int32)
     }
 
-    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
         r.write_int32_with_tag(8, self.b_required_int32)?;
         Ok(())
     }
@@ -85,33 +84,33 @@ pub struct FooMessage<'a> {
     pub f_repeated_packed_int32: Vec<i32>,
 }
 
-impl<'a> FooMessage<'a> {
-    pub fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+impl<'a> MessageRead<'a> for FooMessage<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
         let mut msg = Self::default();
         while !r.is_eof() {
             match r.next_tag(bytes) {
-                Ok(8) => msg.f_int32 = Some(r.read_int32(bytes)?),
-                Ok(16) => msg.f_int64 = Some(r.read_int64(bytes)?),
-                Ok(24) => msg.f_uint32 = Some(r.read_uint32(bytes)?),
-                Ok(32) => msg.f_uint64 = Some(r.read_uint64(bytes)?),
-                Ok(40) => msg.f_sint32 = Some(r.read_sint32(bytes)?),
-                Ok(48) => msg.f_sint64 = Some(r.read_sint64(bytes)?),
-                Ok(56) => msg.f_bool = Some(r.read_bool(bytes)?),
-                Ok(64) => msg.f_FooEnum = Some(r.read_enum(bytes)?),
-                Ok(73) => msg.f_fixed64 = Some(r.read_fixed64(bytes)?),
-                Ok(81) => msg.f_sfixed64 = Some(r.read_sfixed64(bytes)?),
-                Ok(93) => msg.f_fixed32 = Some(r.read_fixed32(bytes)?),
-                Ok(101) => msg.f_sfixed32 = Some(r.read_sfixed32(bytes)?),
-                Ok(105) => msg.f_double = Some(r.read_double(bytes)?),
-                Ok(117) => msg.f_float = Some(r.read_float(bytes)?),
-                Ok(122) => msg.f_bytes = Some(Cow::Borrowed(r.read_bytes(bytes)?)),
-                Ok(130) => msg.f_string = Some(Cow::Borrowed(r.read_string(bytes)?)),
-                Ok(138) => msg.f_self_message = Some(Box::new(r.read_message(bytes, FooMessage::from_reader)?)),
-                Ok(146) => msg.f_bar_message = Some(r.read_message(bytes, BarMessage::from_reader)?),
-                Ok(152) => msg.f_repeated_int32.push(r.read_int32(bytes)?),
-                Ok(162) => msg.f_repeated_packed_int32 = r.read_packed(bytes, |r, bytes| r.read_int32(bytes))?,
-                Ok(t) => { r.read_unknown(bytes, t)?; }
-                Err(e) => return Err(e),
+                Ok(8) => msg.f_int32 = Some(r.read_int32(bytes).map_err(|e| e.in_field("FooMessage", "f_int32"))?),
+                Ok(16) => msg.f_int64 = Some(r.read_int64(bytes).map_err(|e| e.in_field("FooMessage", "f_int64"))?),
+                Ok(24) => msg.f_uint32 = Some(r.read_uint32(bytes).map_err(|e| e.in_field("FooMessage", "f_uint32"))?),
+                Ok(32) => msg.f_uint64 = Some(r.read_uint64(bytes).map_err(|e| e.in_field("FooMessage", "f_uint64"))?),
+                Ok(40) => msg.f_sint32 = Some(r.read_sint32(bytes).map_err(|e| e.in_field("FooMessage", "f_sint32"))?),
+                Ok(48) => msg.f_sint64 = Some(r.read_sint64(bytes).map_err(|e| e.in_field("FooMessage", "f_sint64"))?),
+                Ok(56) => msg.f_bool = Some(r.read_bool(bytes).map_err(|e| e.in_field("FooMessage", "f_bool"))?),
+                Ok(64) => msg.f_FooEnum = Some(r.read_enum(bytes).map_err(|e| e.in_field("FooMessage", "f_FooEnum"))?),
+                Ok(73) => msg.f_fixed64 = Some(r.read_fixed64(bytes).map_err(|e| e.in_field("FooMessage", "f_fixed64"))?),
+                Ok(81) => msg.f_sfixed64 = Some(r.read_sfixed64(bytes).map_err(|e| e.in_field("FooMessage", "f_sfixed64"))?),
+                Ok(93) => msg.f_fixed32 = Some(r.read_fixed32(bytes).map_err(|e| e.in_field("FooMessage", "f_fixed32"))?),
+                Ok(101) => msg.f_sfixed32 = Some(r.read_sfixed32(bytes).map_err(|e| e.in_field("FooMessage", "f_sfixed32"))?),
+                Ok(105) => msg.f_double = Some(r.read_double(bytes).map_err(|e| e.in_field("FooMessage", "f_double"))?),
+                Ok(117) => msg.f_float = Some(r.read_float(bytes).map_err(|e| e.in_field("FooMessage", "f_float"))?),
+                Ok(122) => msg.f_bytes = Some(Cow::Borrowed(r.read_bytes(bytes).map_err(|e| e.in_field("FooMessage", "f_bytes"))?)),
+                Ok(130) => msg.f_string = Some(Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_field("FooMessage", "f_string"))?)),
+                Ok(138) => msg.f_self_message = Some(Box::new(r.read_message(bytes, FooMessage::from_reader).map_err(|e| e.in_field("FooMessage", "f_self_message"))?)),
+                Ok(146) => msg.f_bar_message = Some(r.read_message(bytes, BarMessage::from_reader).map_err(|e| e.in_field("FooMessage", "f_bar_message"))?),
+                Ok(152) => msg.f_repeated_int32.push(r.read_int32(bytes).map_err(|e| e.in_repeated_field("FooMessage", "f_repeated_int32", msg.f_repeated_int32.len()))?),
+                Ok(162) => msg.f_repeated_packed_int32 = r.read_packed(bytes, |r, bytes| r.read_int32(bytes)).map_err(|e| e.in_field("FooMessage", "f_repeated_packed_int32"))?,
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("FooMessage"))?; }
+                Err(e) => return Err(e.in_message("FooMessage")),
             }
         }
         Ok(msg)
@@ -142,7 +141,30 @@ impl<'a> MessageWrite for FooMessage<'a> {
         + if self.f_repeated_packed_int32.is_empty() { 0 } else { 2 + sizeof_var_length(self.f_repeated_packed_int32.iter().map(|s| sizeof_int32(*s)).sum::<usize>()) }
     }
 
-    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        self.f_int32.as_ref().map_or(0, |m| 1 + sizeof_int32(*m))
+        + self.f_int64.as_ref().map_or(0, |m| 1 + sizeof_int64(*m))
+        + self.f_uint32.as_ref().map_or(0, |m| 1 + sizeof_uint32(*m))
+        + self.f_uint64.as_ref().map_or(0, |m| 1 + sizeof_uint64(*m))
+        + self.f_sint32.as_ref().map_or(0, |m| 1 + sizeof_sint32(*m))
+        + self.f_sint64.as_ref().map_or(0, |m| 1 + sizeof_sint64(*m))
+        + self.f_bool.as_ref().map_or(0, |m| 1 + sizeof_bool(*m))
+        + self.f_FooEnum.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+        + self.f_fixed64.as_ref().map_or(0, |_| 1 + 8)
+        + self.f_sfixed64.as_ref().map_or(0, |_| 1 + 8)
+        + self.f_fixed32.as_ref().map_or(0, |_| 1 + 4)
+        + self.f_sfixed32.as_ref().map_or(0, |_| 1 + 4)
+        + self.f_double.as_ref().map_or(0, |_| 1 + 8)
+        + self.f_float.as_ref().map_or(0, |_| 1 + 4)
+        + self.f_bytes.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + self.f_string.as_ref().map_or(0, |m| 2 + sizeof_var_length(m.len()))
+        + self.f_self_message.as_ref().map_or(0, |m| 2 + sizeof_message_cached(&**m, sizes))
+        + self.f_bar_message.as_ref().map_or(0, |m| 2 + sizeof_message_cached(m, sizes))
+        + self.f_repeated_int32.iter().map(|s| 2 + sizeof_int32(*s)).sum::<usize>()
+        + if self.f_repeated_packed_int32.is_empty() { 0 } else { 2 + sizeof_var_length(self.f_repeated_packed_int32.iter().map(|s| sizeof_int32(*s)).sum::<usize>()) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
         if let Some(ref s) = self.f_int32 { r.write_int32_with_tag(8, *s)?; }
         if let Some(ref s) = self.f_int64 { r.write_int64_with_tag(16, *s)?; }
         if let Some(ref s) = self.f_uint32 { r.write_uint32_with_tag(24, *s)?; }
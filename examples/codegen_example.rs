@@ -5,7 +5,7 @@ mod codegen;
 use std::borrow::Cow;
 
 use codegen::data_types::FooMessage;
-use quick_protobuf::{BytesReader, Writer};
+use quick_protobuf::{BytesReader, MessageRead, Writer};
 
 fn main() {
 
@@ -1,32 +1,325 @@
-//! A module to handle all errors via error-chain crate
+//! A module to handle all errors
 
 #![allow(missing_docs)]
 
-error_chain! {
-    foreign_links {
-        Io(::std::io::Error);
-        Utf8(::std::string::FromUtf8Error);
-        StrUtf8(::std::str::Utf8Error);
+use core::fmt;
+use core::result;
+use core::str::Utf8Error;
+use alloc::boxed::Box;
+use alloc::string::{String, FromUtf8Error, ToString};
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::{error, io};
+
+/// The result type of all quick-protobuf operations
+pub type Result<T> = result::Result<T, Error>;
+
+/// The error type of all quick-protobuf operations
+///
+/// On top of its `ErrorKind`, an error may know where it happened:
+/// - the byte offset in the decoded buffer (`BytesReader` errors record it)
+/// - the path of the field being decoded, e.g. `Outer.items[17].name`: pb-rs generated
+///   `from_reader` functions add their message and field names as the error propagates
+///
+/// ```rust
+/// use quick_protobuf::errors::{Error, ErrorKind};
+///
+/// let err = Error::from(ErrorKind::Eof).at(42)
+///     .in_field("Inner", "name")
+///     .in_repeated_field("Outer", "items", 17);
+/// assert_eq!(Some(42), err.offset());
+/// assert_eq!(Some("Outer.items[17].name".to_string()), err.path());
+/// assert_eq!("offset 42, field Outer.items[17].name: unexpected end of file", err.to_string());
+/// ```
+///
+/// What went wrong is the plain `ErrorKind` enum, to match on through `kind()` or `into_kind()`
+/// as with the error-chain `Error` this type replaces:
+///
+/// ```rust
+/// use quick_protobuf::BytesReader;
+/// use quick_protobuf::errors::ErrorKind;
+///
+/// let bytes = [0x80];
+/// let err = BytesReader::from_bytes(&bytes).read_varint32(&bytes).unwrap_err();
+/// match *err.kind() {
+///     ErrorKind::Eof => assert_eq!("offset 1: unexpected end of file", err.to_string()),
+///     ref e => panic!("Expecting Eof, got {:?}", e),
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Error {
+    // boxed to keep `Result`s small, errors are not on the hot path
+    inner: Box<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    kind: ErrorKind,
+    offset: Option<usize>,
+    message: Option<&'static str>,
+    fields: Vec<PathField>,
+}
+
+/// A field in the path of an `Error`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathField {
+    /// The name of the field, as in the .proto file
+    pub name: &'static str,
+    /// The index of the item being decoded, for repeated fields
+    pub index: Option<usize>,
+}
+
+impl fmt::Display for PathField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.index {
+            Some(i) => write!(f, "{}[{}]", self.name, i),
+            None => f.write_str(self.name),
+        }
+    }
+}
+
+impl Error {
+
+    /// The kind of this error
+    pub fn kind(&self) -> &ErrorKind {
+        &self.inner.kind
+    }
+
+    /// Converts the error into its kind
+    pub fn into_kind(self) -> ErrorKind {
+        self.inner.kind
+    }
+
+    /// The offset in the decoded buffer (the `bytes` read by a `BytesReader`) where the error
+    /// happened, if known
+    pub fn offset(&self) -> Option<usize> {
+        self.inner.offset
+    }
+
+    /// The outermost message being decoded when the error happened, if known
+    pub fn message(&self) -> Option<&'static str> {
+        self.inner.message
+    }
+
+    /// The fields being decoded when the error happened, from the outermost message
+    pub fn fields(&self) -> &[PathField] {
+        &self.inner.fields
+    }
+
+    /// The path of the field being decoded when the error happened (`Outer.items[17].name`),
+    /// starting with the outermost message
+    pub fn path(&self) -> Option<String> {
+        if self.inner.message.is_none() && self.inner.fields.is_empty() {
+            return None;
+        }
+        let mut path = String::from(self.inner.message.unwrap_or(""));
+        for field in &self.inner.fields {
+            if !path.is_empty() {
+                path.push('.');
+            }
+            path.push_str(&field.to_string());
+        }
+        Some(path)
+    }
+
+    /// Records the offset where the error happened, unless it is already known
+    pub fn at(mut self, offset: usize) -> Error {
+        self.inner.offset = self.inner.offset.or(Some(offset));
+        self
+    }
+
+    /// Records that the error happened while decoding `message`
+    ///
+    /// Messages are recorded from the innermost one, so `message` replaces any previous one.
+    pub fn in_message(mut self, message: &'static str) -> Error {
+        self.inner.message = Some(message);
+        self
+    }
+
+    /// Records that the error happened while decoding the field `name` of `message`
+    pub fn in_field(self, message: &'static str, name: &'static str) -> Error {
+        self.in_path(message, PathField { name, index: None })
+    }
+
+    /// Records that the error happened while decoding the item `index` of the repeated field
+    /// `name` of `message`
+    pub fn in_repeated_field(self, message: &'static str, name: &'static str, index: usize) -> Error {
+        self.in_path(message, PathField { name, index: Some(index) })
+    }
+
+    fn in_path(mut self, message: &'static str, field: PathField) -> Error {
+        self.inner.fields.insert(0, field);
+        self.in_message(message)
     }
-    errors {
-        Deprecated(feat: &'static str) {
-            description("deprecated feature")
-            display("feature '{}' has been deprecated", feat)
+}
+
+/// The kinds of quick-protobuf errors
+#[derive(Debug)]
+pub enum ErrorKind {
+    Msg(String),
+    #[cfg(feature = "std")]
+    Io(io::Error),
+    Utf8(FromUtf8Error),
+    StrUtf8(Utf8Error),
+    Deprecated(&'static str),
+    UnknownWireType(u8),
+    UnexpectedEndGroup(u32),
+    InvalidFieldNumber(u32),
+    Varint,
+    Eof,
+    BufferOverflow(usize, usize),
+    UnknownMethod(String),
+    MessageTooLarge(usize, usize),
+    InvalidCompressedFlag(u8),
+    CompressedMessage,
+    Json(String),
+    Text(String),
+    ParseMessage(String),
+    RecursionLimitExceeded(usize),
+    InputTooLarge(usize, usize),
+    FieldTooLarge(u64, usize),
+}
+
+impl ErrorKind {
+    /// A short description of the error
+    pub fn description(&self) -> &str {
+        match *self {
+            ErrorKind::Msg(ref s) => s,
+            #[cfg(feature = "std")]
+            ErrorKind::Io(_) => "io error",
+            ErrorKind::Utf8(_) | ErrorKind::StrUtf8(_) => "invalid utf8",
+            ErrorKind::Deprecated(_) => "deprecated feature",
+            ErrorKind::UnknownWireType(_) => "unknown wire type",
+            ErrorKind::UnexpectedEndGroup(_) => "unexpected end group tag",
+            ErrorKind::InvalidFieldNumber(_) => "invalid field number",
+            ErrorKind::Varint => "cannot decode varint",
+            ErrorKind::Eof => "unexpected end of file",
+            ErrorKind::BufferOverflow(..) => "output buffer too small",
+            ErrorKind::UnknownMethod(_) => "unknown rpc method",
+            ErrorKind::MessageTooLarge(..) => "message too large",
+            ErrorKind::InvalidCompressedFlag(_) => "invalid grpc compressed flag",
+            ErrorKind::CompressedMessage => "cannot read a compressed message, it must be decompressed first",
+            ErrorKind::Json(_) => "invalid json",
+            ErrorKind::Text(_) => "invalid text format",
+            ErrorKind::ParseMessage(_) => "error while parsing message",
+            ErrorKind::RecursionLimitExceeded(_) => "recursion limit exceeded",
+            ErrorKind::InputTooLarge(..) => "input too large",
+            ErrorKind::FieldTooLarge(..) => "length delimited field too large",
         }
-        UnknownWireType(t: u8) {
-            description("unknown wire type")
-            display("wire type must be less than 6, found {}", t)
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            #[cfg(feature = "std")]
+            ErrorKind::Io(ref e) => e.fmt(f),
+            ErrorKind::Utf8(ref e) => e.fmt(f),
+            ErrorKind::StrUtf8(ref e) => e.fmt(f),
+            ErrorKind::Deprecated(feat) => write!(f, "feature '{}' has been deprecated", feat),
+            ErrorKind::UnknownWireType(t) => write!(f, "wire type must be less than 6, found {}", t),
+            ErrorKind::UnexpectedEndGroup(field_number) => {
+                write!(f, "unexpected end group tag for field {}", field_number)
+            }
+            ErrorKind::InvalidFieldNumber(field_number) => {
+                write!(f, "field numbers must be positive, found {}", field_number)
+            }
+            ErrorKind::BufferOverflow(needed, len) => {
+                write!(f, "output buffer of {} bytes is too small, at least {} bytes are needed", len, needed)
+            }
+            ErrorKind::UnknownMethod(ref method) => write!(f, "unknown rpc method '{}'", method),
+            ErrorKind::MessageTooLarge(size, max) => {
+                write!(f, "message of {} bytes exceeds the maximum size of {} bytes", size, max)
+            }
+            ErrorKind::InvalidCompressedFlag(flag) => {
+                write!(f, "grpc compressed flag must be 0 or 1, found {}", flag)
+            }
+            ErrorKind::Json(ref msg) => write!(f, "invalid json: {}", msg),
+            ErrorKind::Text(ref msg) => write!(f, "invalid text format: {}", msg),
+            ErrorKind::ParseMessage(ref s) => write!(f, "error while parsing message: {}", s),
+            ErrorKind::RecursionLimitExceeded(max) => {
+                write!(f, "messages are nested deeper than the limit of {}", max)
+            }
+            ErrorKind::InputTooLarge(size, max) => {
+                write!(f, "input of {} bytes exceeds the maximum size of {} bytes", size, max)
+            }
+            ErrorKind::FieldTooLarge(len, max) => {
+                write!(f, "field of {} bytes exceeds the maximum length of {} bytes", len, max)
+            }
+            ErrorKind::Msg(_) | ErrorKind::Varint | ErrorKind::Eof | ErrorKind::CompressedMessage => {
+                f.write_str(self.description())
+            }
         }
-        Varint {
-            description("cannot decode varint")
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let field = if self.inner.fields.is_empty() { "message" } else { "field" };
+        match (self.inner.offset, self.path()) {
+            (Some(offset), Some(path)) => write!(f, "offset {}, {} {}: ", offset, field, path)?,
+            (Some(offset), None) => write!(f, "offset {}: ", offset)?,
+            (None, Some(path)) => write!(f, "{} {}: ", field, path)?,
+            (None, None) => (),
         }
-        Eof {
-            description("unexpected end of file")
+        self.inner.kind.fmt(f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self.inner.kind {
+            ErrorKind::Io(ref e) => Some(e),
+            ErrorKind::Utf8(ref e) => Some(e),
+            ErrorKind::StrUtf8(ref e) => Some(e),
+            _ => None,
         }
-        ParseMessage(s: String) {
-            description("error while parsing message")
-            display("error while parsing message: {}", s)
+    }
+}
 
+impl From<ErrorKind> for Error {
+    #[cold]
+    fn from(kind: ErrorKind) -> Error {
+        Error {
+            inner: Box::new(Inner {
+                kind,
+                offset: None,
+                message: None,
+                fields: Vec::new(),
+            }),
         }
     }
 }
+
+impl<'a> From<&'a str> for Error {
+    fn from(s: &'a str) -> Error {
+        ErrorKind::Msg(s.into()).into()
+    }
+}
+
+impl From<String> for Error {
+    fn from(s: String) -> Error {
+        ErrorKind::Msg(s).into()
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        ErrorKind::Io(e).into()
+    }
+}
+
+impl From<FromUtf8Error> for Error {
+    fn from(e: FromUtf8Error) -> Error {
+        ErrorKind::Utf8(e).into()
+    }
+}
+
+impl From<Utf8Error> for Error {
+    fn from(e: Utf8Error) -> Error {
+        ErrorKind::StrUtf8(e).into()
+    }
+}
@@ -0,0 +1,181 @@
+//! A module to handle the gRPC framing of messages
+//!
+//! gRPC prefixes each message with a 5 bytes header: a compressed flag (`0` or `1`) followed by
+//! the length of the message as a big-endian `u32`. This module only handles the framing, the
+//! transport (e.g. HTTP/2) and the decompression of compressed messages are up to the caller.
+//!
+//! - `write_frame` / `encode_frame` frame a `MessageWrite`
+//! - `decode_frame` decodes a frame out of a slice
+//! - `FrameDecoder` buffers partially received frames, for streaming calls
+
+#[cfg(feature = "std")]
+use std::io::Write;
+use alloc::vec::Vec;
+
+use errors::{Result, ErrorKind};
+use message::{MessageRead, MessageWrite};
+use reader::deserialize_from_slice;
+use writer::Writer;
+
+use byteorder::{BigEndian, ByteOrder};
+
+/// The size of a frame header
+pub const HEADER_LEN: usize = 5;
+
+/// The default maximum size of a received message (4MB, as most gRPC implementations)
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
+/// Gets the header of an uncompressed frame holding a message of `len` bytes
+fn header(len: usize) -> Result<[u8; HEADER_LEN]> {
+    if len > u32::MAX as usize {
+        return Err(ErrorKind::MessageTooLarge(len, u32::MAX as usize).into());
+    }
+    let mut header = [0; HEADER_LEN];
+    BigEndian::write_u32(&mut header[1..], len as u32);
+    Ok(header)
+}
+
+/// Writes `m` as an uncompressed frame
+#[cfg(feature = "std")]
+pub fn write_frame<W: Write, M: MessageWrite>(w: &mut W, m: &M) -> Result<()> {
+    w.write_all(&header(m.get_size())?)?;
+    m.write_message(&mut Writer::new(w))
+}
+
+/// Encodes `m` into a new uncompressed frame
+pub fn encode_frame<M: MessageWrite>(m: &M) -> Result<Vec<u8>> {
+    let len = m.get_size();
+    let mut buf = Vec::with_capacity(HEADER_LEN + len);
+    buf.extend_from_slice(&header(len)?);
+    m.write_message(&mut Writer::new(&mut buf))?;
+    Ok(buf)
+}
+
+/// A decoded frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame<'a> {
+    /// Is the message compressed (with the `grpc-encoding` negociated by the transport)
+    pub compressed: bool,
+    /// The message, without the header
+    pub data: &'a [u8],
+}
+
+impl<'a> Frame<'a> {
+
+    /// Gets the size of the whole frame, header included
+    pub fn len(&self) -> usize {
+        HEADER_LEN + self.data.len()
+    }
+
+    /// Checks if the message is empty
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Deserializes the message
+    ///
+    /// Compressed messages must be decompressed first, this fails with
+    /// `ErrorKind::CompressedMessage` on them.
+    pub fn read_message<M: MessageRead<'a>>(&self) -> Result<M> {
+        if self.compressed {
+            return Err(ErrorKind::CompressedMessage.into());
+        }
+        deserialize_from_slice(self.data)
+    }
+}
+
+/// Decodes the frame at the start of `bytes`
+///
+/// Returns `None` if `bytes` doesn't hold a complete frame yet. Frames with a message larger than
+/// `max_message_size` are rejected as soon as their header is available.
+pub fn decode_frame<'a>(bytes: &'a [u8], max_message_size: usize) -> Result<Option<Frame<'a>>> {
+    if bytes.len() < HEADER_LEN {
+        return Ok(None);
+    }
+    let compressed = match bytes[0] {
+        0 => false,
+        1 => true,
+        flag => return Err(ErrorKind::InvalidCompressedFlag(flag).into()),
+    };
+    let len = BigEndian::read_u32(&bytes[1..HEADER_LEN]) as usize;
+    if len > max_message_size {
+        return Err(ErrorKind::MessageTooLarge(len, max_message_size).into());
+    }
+    if bytes.len() - HEADER_LEN < len {
+        return Ok(None);
+    }
+    Ok(Some(Frame {
+        compressed,
+        data: &bytes[HEADER_LEN..HEADER_LEN + len],
+    }))
+}
+
+/// A struct to decode frames out of data received in arbitrary chunks
+///
+/// # Examples
+///
+/// ```rust
+/// use quick_protobuf::grpc::FrameDecoder;
+///
+/// let mut decoder = FrameDecoder::new();
+///
+/// // a frame holding a 2 bytes message, received in 2 chunks
+/// decoder.push(&[0, 0, 0]);
+/// assert!(decoder.next_frame().unwrap().is_none());
+/// decoder.push(&[0, 2, 8, 1]);
+/// assert_eq!(&[8, 1], decoder.next_frame().unwrap().unwrap().data);
+/// assert!(decoder.is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct FrameDecoder {
+    buf: Vec<u8>,
+    start: usize,
+    max_message_size: usize,
+}
+
+impl FrameDecoder {
+
+    /// Creates a new `FrameDecoder` accepting messages up to `DEFAULT_MAX_MESSAGE_SIZE`
+    pub fn new() -> FrameDecoder {
+        FrameDecoder::with_max_message_size(DEFAULT_MAX_MESSAGE_SIZE)
+    }
+
+    /// Creates a new `FrameDecoder` accepting messages up to `max_message_size` bytes
+    pub fn with_max_message_size(max_message_size: usize) -> FrameDecoder {
+        FrameDecoder {
+            buf: Vec::new(),
+            start: 0,
+            max_message_size,
+        }
+    }
+
+    /// Appends received data
+    pub fn push(&mut self, data: &[u8]) {
+        if self.start > 0 {
+            // drop already decoded frames
+            self.buf.drain(..self.start);
+            self.start = 0;
+        }
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Decodes the next frame, if it has been completely received
+    pub fn next_frame<'a>(&'a mut self) -> Result<Option<Frame<'a>>> {
+        let frame = decode_frame(&self.buf[self.start..], self.max_message_size)?;
+        if let Some(ref f) = frame {
+            self.start += f.len();
+        }
+        Ok(frame)
+    }
+
+    /// Checks if there is no pending data, i.e. the stream ends on a frame boundary
+    pub fn is_empty(&self) -> bool {
+        self.start == self.buf.len()
+    }
+}
+
+impl Default for FrameDecoder {
+    fn default() -> FrameDecoder {
+        FrameDecoder::new()
+    }
+}
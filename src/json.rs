@@ -0,0 +1,650 @@
+//! A module to convert messages from and to the canonical proto3 JSON mapping
+//!
+//! pb-rs generates `JsonWrite` and `JsonRead` implementations for all messages and enums when
+//! run with `--json`. The mapping follows the protobuf specification:
+//! - field names are converted to lowerCamelCase (the original names are accepted when parsing)
+//! - 64 bits integers are written as strings (numbers and strings are accepted when parsing)
+//! - `bytes` are written as base64 strings
+//! - enums are written with their names (names and numbers are accepted when parsing)
+//! - fields with a default value (`None`, empty repeated fields etc ...) are omitted
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::char;
+use core::str;
+
+use errors::{Result, ErrorKind};
+
+/// A trait to write a message or an enum as JSON
+pub trait JsonWrite {
+
+    /// Writes `self` as a JSON value
+    fn write_json(&self, w: &mut JsonWriter);
+}
+
+/// A trait to read a message or an enum out of a parsed JSON value
+pub trait JsonRead: Sized {
+
+    /// Converts a JSON value into `Self`
+    fn from_json(v: &Value) -> Result<Self>;
+}
+
+/// Serializes a message into a JSON string
+pub fn to_string<M: JsonWrite>(m: &M) -> String {
+    let mut w = JsonWriter::new();
+    m.write_json(&mut w);
+    w.into_string()
+}
+
+/// Deserializes a message out of a JSON string
+pub fn from_str<M: JsonRead>(s: &str) -> Result<M> {
+    M::from_json(&Value::parse(s)?)
+}
+
+/// The maximum nesting of arrays and objects, deeper documents are rejected
+pub const MAX_DEPTH: usize = 64;
+
+fn error<T>(msg: String) -> Result<T> {
+    Err(ErrorKind::Json(msg).into())
+}
+
+/// A parsed JSON value
+///
+/// Numbers are kept as found in the input, so 64 bits integers don't lose precision
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// `null`
+    Null,
+    /// `true` or `false`
+    Bool(bool),
+    /// A number, as written in the input
+    Number(String),
+    /// A string, unescaped
+    String(String),
+    /// An array
+    Array(Vec<Value>),
+    /// An object, with its members in the input order
+    Object(Vec<(String, Value)>),
+}
+
+macro_rules! as_integer {
+    ($name:ident, $ty:ty) => (
+    /// Converts a number, or a string holding a number, into an integer
+    pub fn $name(&self) -> Result<$ty> {
+        let s = match *self {
+            Value::Number(ref s) | Value::String(ref s) => s,
+            _ => return error(format!("expecting an integer, found {}", self.kind())),
+        };
+        if let Ok(v) = s.parse::<$ty>() {
+            return Ok(v);
+        }
+        // integers may be written with a fraction or an exponent, e.g. `1e3`
+        match s.parse::<f64>() {
+            Ok(f) if f >= <$ty>::MIN as f64 && f <= <$ty>::MAX as f64 && f as $ty as f64 == f => Ok(f as $ty),
+            _ => error(format!("expecting an integer, found '{}'", s)),
+        }
+    }
+    );
+}
+
+macro_rules! as_float {
+    ($name:ident, $ty:ty) => (
+    /// Converts a number, or a string holding a number or `NaN`/`Infinity`/`-Infinity`, into a float
+    pub fn $name(&self) -> Result<$ty> {
+        match *self {
+            Value::String(ref s) if s == "NaN" => Ok(<$ty>::NAN),
+            Value::String(ref s) if s == "Infinity" => Ok(<$ty>::INFINITY),
+            Value::String(ref s) if s == "-Infinity" => Ok(<$ty>::NEG_INFINITY),
+            Value::Number(ref s) | Value::String(ref s) => match s.parse::<$ty>() {
+                Ok(f) => Ok(f),
+                Err(_) => error(format!("expecting a float, found '{}'", s)),
+            },
+            _ => error(format!("expecting a float, found {}", self.kind())),
+        }
+    }
+    );
+}
+
+impl Value {
+
+    /// Parses a JSON document
+    ///
+    /// Fails if arrays and objects are nested deeper than `MAX_DEPTH`.
+    pub fn parse(s: &str) -> Result<Value> {
+        let mut p = Parser { bytes: s.as_bytes(), pos: 0, depth: 0 };
+        let v = p.parse_value()?;
+        p.skip_whitespaces();
+        if p.pos < p.bytes.len() {
+            return p.error("end of input");
+        }
+        Ok(v)
+    }
+
+    fn kind(&self) -> &'static str {
+        match *self {
+            Value::Null => "null",
+            Value::Bool(_) => "a boolean",
+            Value::Number(_) => "a number",
+            Value::String(_) => "a string",
+            Value::Array(_) => "an array",
+            Value::Object(_) => "an object",
+        }
+    }
+
+    /// Checks if the value is `null`
+    pub fn is_null(&self) -> bool {
+        *self == Value::Null
+    }
+
+    as_integer!(as_i32, i32);
+    as_integer!(as_i64, i64);
+    as_integer!(as_u32, u32);
+    as_integer!(as_u64, u64);
+    as_float!(as_f32, f32);
+    as_float!(as_f64, f64);
+
+    /// Converts a boolean (or a `"true"`/`"false"` map key) into a `bool`
+    pub fn as_bool(&self) -> Result<bool> {
+        match *self {
+            Value::Bool(b) => Ok(b),
+            Value::String(ref s) if s == "true" => Ok(true),
+            Value::String(ref s) if s == "false" => Ok(false),
+            _ => error(format!("expecting a boolean, found {}", self.kind())),
+        }
+    }
+
+    /// Gets a string
+    pub fn as_str(&self) -> Result<&str> {
+        match *self {
+            Value::String(ref s) => Ok(s),
+            _ => error(format!("expecting a string, found {}", self.kind())),
+        }
+    }
+
+    /// Decodes a base64 string (standard or url safe alphabet, with or without padding)
+    pub fn as_bytes(&self) -> Result<Vec<u8>> {
+        base64_decode(self.as_str()?)
+    }
+
+    /// Gets the items of an array
+    pub fn as_array(&self) -> Result<&[Value]> {
+        match *self {
+            Value::Array(ref a) => Ok(a),
+            _ => error(format!("expecting an array, found {}", self.kind())),
+        }
+    }
+
+    /// Gets the members of an object
+    pub fn as_object(&self) -> Result<&[(String, Value)]> {
+        match *self {
+            Value::Object(ref o) => Ok(o),
+            _ => error(format!("expecting an object, found {}", self.kind())),
+        }
+    }
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+
+    fn error<T>(&self, expected: &str) -> Result<T> {
+        error(format!("expecting {} at offset {}", expected, self.pos))
+    }
+
+    fn skip_whitespaces(&mut self) {
+        while self.pos < self.bytes.len() {
+            match self.bytes[self.pos] {
+                b' ' | b'\t' | b'\n' | b'\r' => self.pos += 1,
+                _ => break,
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespaces();
+        self.bytes.get(self.pos).cloned()
+    }
+
+    fn consume(&mut self, token: &str) -> bool {
+        if self.bytes[self.pos..].starts_with(token.as_bytes()) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, b: u8) -> Result<()> {
+        if self.peek() == Some(b) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            self.error(&format!("'{}'", b as char))
+        }
+    }
+
+    /// Runs `parse` one nesting level deeper, checking the depth against `MAX_DEPTH`
+    fn nested<F>(&mut self, parse: F) -> Result<Value>
+        where F: FnOnce(&mut Parser<'a>) -> Result<Value>,
+    {
+        if self.depth >= MAX_DEPTH {
+            return error(format!("values nested deeper than {} levels at offset {}", MAX_DEPTH, self.pos));
+        }
+        self.depth += 1;
+        let v = parse(self);
+        self.depth -= 1;
+        v
+    }
+
+    fn parse_value(&mut self) -> Result<Value> {
+        match self.peek() {
+            Some(b'{') => self.nested(Parser::parse_object),
+            Some(b'[') => self.nested(Parser::parse_array),
+            Some(b'"') => self.parse_string().map(Value::String),
+            Some(b'-') | Some(b'0'..=b'9') => self.parse_number(),
+            _ if self.consume("null") => Ok(Value::Null),
+            _ if self.consume("true") => Ok(Value::Bool(true)),
+            _ if self.consume("false") => Ok(Value::Bool(false)),
+            _ => self.error("a value"),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Value> {
+        self.expect(b'{')?;
+        let mut members = Vec::new();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(members));
+        }
+        loop {
+            if self.peek() != Some(b'"') {
+                return self.error("a string");
+            }
+            let key = self.parse_string()?;
+            self.expect(b':')?;
+            members.push((key, self.parse_value()?));
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(members));
+                }
+                _ => return self.error("',' or '}'"),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Value> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.parse_value()?);
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                }
+                _ => return self.error("',' or ']'"),
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Value> {
+        let start = self.pos;
+        while self.pos < self.bytes.len() {
+            match self.bytes[self.pos] {
+                b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E' => self.pos += 1,
+                _ => break,
+            }
+        }
+        let number = str::from_utf8(&self.bytes[start..self.pos])?;
+        if number.parse::<f64>().is_err() {
+            self.pos = start;
+            return self.error("a number");
+        }
+        Ok(Value::Number(number.to_string()))
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32> {
+        let hex = self.bytes.get(self.pos..self.pos + 4)
+            .and_then(|h| str::from_utf8(h).ok())
+            .and_then(|h| u32::from_str_radix(h, 16).ok());
+        match hex {
+            Some(h) => {
+                self.pos += 4;
+                Ok(h)
+            }
+            None => self.error("4 hexadecimal digits"),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String> {
+        self.expect(b'"')?;
+        let mut s = Vec::new();
+        loop {
+            let b = match self.bytes.get(self.pos) {
+                Some(b) => *b,
+                None => return self.error("'\"'"),
+            };
+            self.pos += 1;
+            match b {
+                b'"' => break,
+                b'\\' => {
+                    let e = match self.bytes.get(self.pos) {
+                        Some(e) => *e,
+                        None => return self.error("an escape sequence"),
+                    };
+                    self.pos += 1;
+                    let c = match e {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\x08',
+                        b'f' => '\x0c',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut c = self.parse_hex4()?;
+                            if (0xD800..0xDC00).contains(&c) && self.consume("\\u") {
+                                // surrogate pair
+                                let low = self.parse_hex4()?;
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    self.pos -= 6;
+                                    return self.error("a low surrogate");
+                                }
+                                c = 0x10000 + ((c - 0xD800) << 10) + (low - 0xDC00);
+                            }
+                            match char::from_u32(c) {
+                                Some(c) => c,
+                                None => return self.error("a valid unicode escape"),
+                            }
+                        }
+                        _ => {
+                            self.pos -= 1;
+                            return self.error("an escape sequence");
+                        }
+                    };
+                    let mut buf = [0; 4];
+                    s.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+                b => s.push(b),
+            }
+        }
+        Ok(String::from_utf8(s)?)
+    }
+}
+
+/// A struct to write JSON values into a `String`
+///
+/// Commas are added automatically between members of objects and items of arrays
+#[derive(Debug, Default)]
+pub struct JsonWriter {
+    out: String,
+    /// for each open object or array, is it still empty
+    empty: Vec<bool>,
+    after_key: bool,
+}
+
+impl JsonWriter {
+
+    /// Creates a new, empty, `JsonWriter`
+    pub fn new() -> JsonWriter {
+        JsonWriter::default()
+    }
+
+    /// Gets the JSON written so far
+    pub fn into_string(self) -> String {
+        self.out
+    }
+
+    fn before_value(&mut self) {
+        if self.after_key {
+            self.after_key = false;
+            return;
+        }
+        if let Some(empty) = self.empty.last_mut() {
+            if !*empty {
+                self.out.push(',');
+            }
+            *empty = false;
+        }
+    }
+
+    /// Starts an object
+    pub fn begin_object(&mut self) {
+        self.before_value();
+        self.out.push('{');
+        self.empty.push(true);
+    }
+
+    /// Ends the current object
+    pub fn end_object(&mut self) {
+        self.empty.pop();
+        self.out.push('}');
+    }
+
+    /// Starts an array
+    pub fn begin_array(&mut self) {
+        self.before_value();
+        self.out.push('[');
+        self.empty.push(true);
+    }
+
+    /// Ends the current array
+    pub fn end_array(&mut self) {
+        self.empty.pop();
+        self.out.push(']');
+    }
+
+    /// Writes the name of the next member of the current object
+    pub fn key(&mut self, name: &str) {
+        self.write_string(name);
+        self.out.push(':');
+        self.after_key = true;
+    }
+
+    /// Writes `null`
+    pub fn write_null(&mut self) {
+        self.before_value();
+        self.out.push_str("null");
+    }
+
+    /// Writes a `bool`
+    pub fn write_bool(&mut self, v: bool) {
+        self.before_value();
+        self.out.push_str(if v { "true" } else { "false" });
+    }
+
+    /// Writes a 32 bits integer, as a number
+    pub fn write_i32(&mut self, v: i32) {
+        self.before_value();
+        self.out.push_str(&v.to_string());
+    }
+
+    /// Writes a 32 bits unsigned integer, as a number
+    pub fn write_u32(&mut self, v: u32) {
+        self.before_value();
+        self.out.push_str(&v.to_string());
+    }
+
+    /// Writes a 64 bits integer, as a string
+    pub fn write_i64(&mut self, v: i64) {
+        self.write_string(&v.to_string());
+    }
+
+    /// Writes a 64 bits unsigned integer, as a string
+    pub fn write_u64(&mut self, v: u64) {
+        self.write_string(&v.to_string());
+    }
+
+    /// Writes a `f32`, non finite values being written as strings
+    pub fn write_f32(&mut self, v: f32) {
+        if v.is_finite() {
+            self.before_value();
+            self.out.push_str(&v.to_string());
+        } else {
+            self.write_f64(v as f64)
+        }
+    }
+
+    /// Writes a `f64`, non finite values being written as strings
+    pub fn write_f64(&mut self, v: f64) {
+        if v.is_nan() {
+            self.write_string("NaN");
+        } else if v.is_infinite() {
+            self.write_string(if v > 0. { "Infinity" } else { "-Infinity" });
+        } else {
+            self.before_value();
+            self.out.push_str(&v.to_string());
+        }
+    }
+
+    /// Writes an escaped string
+    pub fn write_string(&mut self, s: &str) {
+        self.before_value();
+        self.out.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => self.out.push_str("\\\""),
+                '\\' => self.out.push_str("\\\\"),
+                '\n' => self.out.push_str("\\n"),
+                '\r' => self.out.push_str("\\r"),
+                '\t' => self.out.push_str("\\t"),
+                c if (c as u32) < 0x20 => self.out.push_str(&format!("\\u{:04x}", c as u32)),
+                c => self.out.push(c),
+            }
+        }
+        self.out.push('"');
+    }
+
+    /// Writes bytes, as a base64 string
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_string(&base64_encode(bytes));
+    }
+}
+
+const BASE64_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as usize) << 16 | (b[1] as usize) << 8 | b[2] as usize;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                s.push(BASE64_CHARS[(n >> (18 - 6 * i)) & 0x3F] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+fn base64_decode(s: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(s.len() * 3 / 4);
+    let mut n = 0u32;
+    let mut bits = 0;
+    for c in s.bytes().take_while(|c| *c != b'=') {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return error(format!("invalid base64 character '{}'", c as char)),
+        };
+        n = n << 6 | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((n >> bits) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+#[test]
+fn test_base64() {
+    for &(bytes, encoded) in &[(&b""[..], ""), (b"f", "Zg=="), (b"fo", "Zm8="), (b"foo", "Zm9v"),
+                               (b"foob", "Zm9vYg=="), (b"\xfb\xff", "+/8=")] {
+        assert_eq!(encoded, base64_encode(bytes));
+        assert_eq!(bytes, &*base64_decode(encoded).unwrap());
+    }
+    assert_eq!(b"\xfb\xff", &*base64_decode("-_8").unwrap());
+}
+
+#[test]
+fn test_parse() {
+    let v = Value::parse(r#" { "a": [1, -2.5e3, "x\"é😀"], "b": {}, "c": null, "d": true } "#).unwrap();
+    assert_eq!(Value::Object(vec![
+        ("a".to_string(), Value::Array(vec![
+            Value::Number("1".to_string()),
+            Value::Number("-2.5e3".to_string()),
+            Value::String("x\"\u{e9}\u{1f600}".to_string()),
+        ])),
+        ("b".to_string(), Value::Object(vec![])),
+        ("c".to_string(), Value::Null),
+        ("d".to_string(), Value::Bool(true)),
+    ]), v);
+
+    assert!(Value::parse("{\"a\": 1,}").is_err());
+    assert!(Value::parse("[1] 2").is_err());
+    assert!(Value::parse("\"abc").is_err());
+}
+
+#[test]
+fn test_parse_surrogates() {
+    assert_eq!(Value::String("\u{1f600}".to_string()), Value::parse(r#""\ud83d\ude00""#).unwrap());
+    for &(s, msg) in &[(r#""\ud83d\u0041""#, "expecting a low surrogate at offset 7"),
+                       (r#""\ud83d\ud83d""#, "expecting a low surrogate at offset 7"),
+                       (r#""\ud83d""#, "expecting a valid unicode escape at offset 7"),
+                       (r#""\ude00""#, "expecting a valid unicode escape at offset 7")] {
+        match *Value::parse(s).unwrap_err().kind() {
+            ErrorKind::Json(ref m) => assert_eq!(msg, m),
+            ref e => panic!("Expecting a json error, got {:?}", e),
+        }
+    }
+}
+
+#[test]
+fn test_parse_max_depth() {
+    let arrays = |depth| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    assert!(Value::parse(&arrays(MAX_DEPTH)).is_ok());
+    assert!(Value::parse(&arrays(MAX_DEPTH + 1)).is_err());
+    let objects = |depth| format!("{}1{}", "{\"a\":".repeat(depth), "}".repeat(depth));
+    assert!(Value::parse(&objects(MAX_DEPTH)).is_ok());
+    assert!(Value::parse(&objects(MAX_DEPTH + 1)).is_err());
+
+    // must not overflow the stack
+    match *Value::parse(&"[".repeat(1_000_000)).unwrap_err().kind() {
+        ErrorKind::Json(ref msg) => assert!(msg.starts_with("values nested deeper than 64 levels"), "{}", msg),
+        ref e => panic!("Expecting a json error, got {:?}", e),
+    }
+}
+
+#[test]
+fn test_write() {
+    let mut w = JsonWriter::new();
+    w.begin_object();
+    w.key("a");
+    w.begin_array();
+    w.write_i32(1);
+    w.write_i64(-2);
+    w.write_f64(f64::NAN);
+    w.end_array();
+    w.key("b\n");
+    w.write_bytes(b"foo");
+    w.end_object();
+    assert_eq!(r#"{"a":[1,"-2","NaN"],"b\n":"Zm9v"}"#, w.into_string());
+}
@@ -3,12 +3,15 @@
 //! This reader is developed similarly to a pull reader
 
 #![deny(missing_docs)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
 #![recursion_limit = "1024"]
 #![allow(dead_code)]
 
+#[cfg(feature = "std")]
+extern crate core;
 #[macro_use]
-extern crate error_chain;
+extern crate alloc;
 extern crate byteorder;
 
 pub mod errors;
@@ -16,8 +19,14 @@ pub mod message;
 pub mod reader;
 pub mod writer;
 pub mod sizeofs;
+pub mod grpc;
+pub mod json;
+pub mod text;
+pub mod raw;
 
 pub use errors::Result;
-pub use message::{MessageWrite};
-pub use reader::{Reader, BytesReader};
-pub use writer::Writer;
+pub use message::{MessageRead, MessageWrite, UnknownFields};
+pub use reader::{BytesReader, ReaderLimits, DEFAULT_MAX_DEPTH, Messages, deserialize_from_slice};
+#[cfg(feature = "std")]
+pub use reader::{Reader, StreamReader, DEFAULT_STREAM_MAX_MESSAGE_SIZE};
+pub use writer::{Writer, WriterBackend, BytesWriter, serialize_into_slice};
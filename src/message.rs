@@ -2,26 +2,107 @@
 //!
 //! Creates the struct and implements a reader
 
-use std::io::{Write, BufWriter};
+#[cfg(feature = "std")]
+use std::io::BufWriter;
+#[cfg(feature = "std")]
 use std::path::Path;
+#[cfg(feature = "std")]
 use std::fs::File;
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::slice;
 
 use errors::Result;
-use writer::Writer;
+use reader::BytesReader;
+use writer::{Writer, WriterBackend};
+use sizeofs::sizeof_varint;
+
+/// A trait to handle deserialization of a message out of a `BytesReader`
+///
+/// `'a` is the lifetime of the bytes the message may borrow from (`Cow::Borrowed` fields)
+pub trait MessageRead<'a>: Sized {
+
+    /// Reads `Self` from the current position of `r` up to its end
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self>;
+}
 
 /// A trait to handle deserialization based on parsed `Field`s
 pub trait MessageWrite: Sized {
 
     /// Writes `Self` into W writer
-    fn write_message<W: Write>(&self, w: &mut Writer<W>) -> Result<()>;
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()>;
 
     /// Computes necessary binary size of self once serialized in protobuf
     fn get_size(&self) -> usize;
 
+    /// Computes the binary size of self, like `get_size`, recording the sizes of nested messages
+    ///
+    /// Every length delimited nested message must be sized with
+    /// `sizeofs::sizeof_message_cached`, in the order the messages are written, which records
+    /// its size into `sizes`. `Writer::write_message` then reuses these sizes instead of calling
+    /// `get_size` again for every nested message, which is quadratic in the depth of the message.
+    ///
+    /// The default implementation records nothing: `Writer::write_message` sizes the nested
+    /// messages of `self` when they are written, with their own `get_size_cached`.
+    fn get_size_cached(&self, _sizes: &mut Vec<usize>) -> usize {
+        self.get_size()
+    }
+
     /// Writes self into a file
+    #[cfg(feature = "std")]
     fn write_file<P: AsRef<Path>>(&self, p: P) -> Result<()> {
         let file = BufWriter::new(File::create(p)?);
         let mut writer = Writer::new(file);
         self.write_message(&mut writer)
     }
 }
+
+/// Fields of a message which are not known by its generated code
+///
+/// Each field is kept as its tag and its raw data as found on the wire, so it can be written
+/// back verbatim, after the known fields. This is what pb-rs generated messages hold when
+/// generated with `--keep-unknown-fields`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct UnknownFields<'a> {
+    fields: Vec<(u32, Cow<'a, [u8]>)>,
+}
+
+impl<'a> UnknownFields<'a> {
+
+    /// Appends a field, `data` being everything following the tag on the wire
+    pub fn push(&mut self, tag: u32, data: Cow<'a, [u8]>) {
+        self.fields.push((tag, data));
+    }
+
+    /// Iterates over the `(tag, data)` of the unknown fields, in the order they were read
+    pub fn iter<'b>(&'b self) -> slice::Iter<'b, (u32, Cow<'a, [u8]>)> {
+        self.fields.iter()
+    }
+
+    /// Gets the number of unknown fields
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Checks if there is no unknown field
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Removes all unknown fields
+    pub fn clear(&mut self) {
+        self.fields.clear();
+    }
+
+    /// Computes the binary size of the unknown fields, tags included
+    pub fn get_size(&self) -> usize {
+        self.fields.iter().map(|&(tag, ref data)| sizeof_varint(tag as u64) + data.len()).sum()
+    }
+
+    /// Converts all borrowed data into owned data
+    pub fn into_owned(self) -> UnknownFields<'static> {
+        UnknownFields {
+            fields: self.fields.into_iter().map(|(tag, data)| (tag, Cow::Owned(data.into_owned()))).collect(),
+        }
+    }
+}
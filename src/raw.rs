@@ -0,0 +1,149 @@
+//! A module to decode messages without their schema, similarly to `protoc --decode_raw`
+//!
+//! Without a schema, the wire format only tells the field numbers, the wire types and the
+//! encoded values. Length delimited fields are either strings, bytes, packed repeated fields or
+//! nested messages: they are decoded as messages when they parse as such and are not printable
+//! text, which is a guess.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quick_protobuf::raw::{RawMessage, RawValue};
+//!
+//! // field 1: varint 150, field 2: "hi"
+//! let bytes = [0x08, 0x96, 0x01, 0x12, 0x02, b'h', b'i'];
+//! let msg = RawMessage::decode(&bytes).unwrap();
+//! assert_eq!(RawValue::Varint(150), msg.fields[0].value);
+//! assert_eq!(RawValue::Bytes(b"hi"), msg.fields[1].value);
+//! assert_eq!("1: 150\n2: \"hi\"\n", quick_protobuf::text::to_string(&msg));
+//! ```
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::str;
+
+use errors::{Result, ErrorKind};
+use reader::{BytesReader, WIRE_TYPE_VARINT, WIRE_TYPE_FIXED64, WIRE_TYPE_LENGTH_DELIMITED,
+             WIRE_TYPE_START_GROUP, WIRE_TYPE_END_GROUP, WIRE_TYPE_FIXED32};
+use text::{TextWrite, TextWriter};
+
+/// The maximum nesting of decoded messages, deeper length delimited fields are kept as bytes
+pub const MAX_DEPTH: usize = 64;
+
+/// A message decoded without schema
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RawMessage<'a> {
+    /// The fields, in the order they were found
+    pub fields: Vec<RawField<'a>>,
+}
+
+/// A field decoded without schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawField<'a> {
+    /// The field number
+    pub number: u32,
+    /// The wire type (one of the `reader::WIRE_TYPE_*`)
+    pub wire_type: u8,
+    /// The decoded value
+    pub value: RawValue<'a>,
+}
+
+/// A value decoded without schema
+#[derive(Debug, Clone, PartialEq)]
+pub enum RawValue<'a> {
+    /// A varint (any integer, `bool` or enum)
+    Varint(u64),
+    /// A 8 bytes value (`fixed64`, `sfixed64` or `double`)
+    Fixed64(u64),
+    /// A 4 bytes value (`fixed32`, `sfixed32` or `float`)
+    Fixed32(u32),
+    /// Length delimited data which is printable text or does not parse as a message
+    Bytes(&'a [u8]),
+    /// Length delimited data parsed as a message
+    Message(RawMessage<'a>),
+    /// A group
+    Group(RawMessage<'a>),
+}
+
+impl<'a> RawMessage<'a> {
+
+    /// Decodes `bytes` into a tree of fields
+    pub fn decode(bytes: &'a [u8]) -> Result<RawMessage<'a>> {
+        let mut r = BytesReader::from_bytes(bytes);
+        RawMessage::from_reader(&mut r, bytes, None, 0)
+    }
+
+    /// Reads fields until the end of `r`, or until the end group tag of `group`
+    fn from_reader(r: &mut BytesReader,
+                   bytes: &'a [u8],
+                   group: Option<u32>,
+                   depth: usize) -> Result<RawMessage<'a>> {
+
+        let mut fields = Vec::new();
+        while !r.is_eof() {
+            let tag = r.next_tag(bytes)?;
+            let number = tag >> 3;
+            let wire_type = (tag & 0x7) as u8;
+            if number == 0 {
+                return Err(ErrorKind::InvalidFieldNumber(number).into());
+            }
+            let value = match wire_type {
+                WIRE_TYPE_VARINT => RawValue::Varint(r.read_varint64(bytes)?),
+                WIRE_TYPE_FIXED64 => RawValue::Fixed64(r.read_fixed64(bytes)?),
+                WIRE_TYPE_FIXED32 => RawValue::Fixed32(r.read_fixed32(bytes)?),
+                WIRE_TYPE_LENGTH_DELIMITED => {
+                    let data = r.read_bytes(bytes)?;
+                    let msg = if depth >= MAX_DEPTH || is_text(data) {
+                        None
+                    } else {
+                        let mut r = BytesReader::from_bytes(data);
+                        RawMessage::from_reader(&mut r, data, None, depth + 1).ok()
+                    };
+                    match msg {
+                        Some(msg) => RawValue::Message(msg),
+                        None => RawValue::Bytes(data),
+                    }
+                }
+                WIRE_TYPE_START_GROUP if depth < MAX_DEPTH => {
+                    RawValue::Group(RawMessage::from_reader(r, bytes, Some(number), depth + 1)?)
+                }
+                WIRE_TYPE_START_GROUP => RawValue::Bytes(r.read_unknown_field(bytes, tag)?),
+                WIRE_TYPE_END_GROUP if group == Some(number) => return Ok(RawMessage { fields }),
+                WIRE_TYPE_END_GROUP => return Err(ErrorKind::UnexpectedEndGroup(number).into()),
+                t => return Err(ErrorKind::UnknownWireType(t).into()),
+            };
+            fields.push(RawField { number, wire_type, value });
+        }
+        match group {
+            Some(_) => Err(ErrorKind::Eof.into()),
+            None => Ok(RawMessage { fields }),
+        }
+    }
+}
+
+/// Checks if `data` is a (possibly empty) utf8 string without control characters but whitespaces
+fn is_text(data: &[u8]) -> bool {
+    match str::from_utf8(data) {
+        Ok(s) => s.chars().all(|c| !c.is_control() || c == '\n' || c == '\r' || c == '\t'),
+        Err(_) => false,
+    }
+}
+
+/// Writes the fields as `protoc --decode_raw` does: field numbers as names, fixed size values
+/// in hexadecimal and length delimited data either as nested messages or as strings
+impl<'a> TextWrite for RawMessage<'a> {
+    fn write_text(&self, w: &mut TextWriter) {
+        w.begin_message();
+        for f in &self.fields {
+            w.key(&f.number.to_string());
+            match f.value {
+                RawValue::Varint(v) => w.write_u64(v),
+                RawValue::Fixed64(v) => w.write_ident(&format!("0x{:016x}", v)),
+                RawValue::Fixed32(v) => w.write_ident(&format!("0x{:08x}", v)),
+                RawValue::Bytes(b) => w.write_bytes(b),
+                RawValue::Message(ref m) | RawValue::Group(ref m) => m.write_text(w),
+            }
+        }
+        w.end_message();
+    }
+}
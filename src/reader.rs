@@ -1,27 +1,80 @@
 //! A module to manage protobuf deserialization
 //!
-//! There are actually two main *readers*
+//! There are actually three main *readers*
 //! - a `BytesReader` which parses data from a `&[u8]`
 //! - a `Reader` which is a wrapper on `BytesReader` which has its own buffer. It provides
-//! convenient functions to the user suche as `from_file`
+//!   convenient functions to the user suche as `from_file`
+//! - a `StreamReader` which decodes length delimited messages out of any `Read`, refilling
+//!   its buffer on demand instead of loading the whole input
 //!
 //! It is advised, for convenience to directly work with a `Reader`.
 
-use std::io::Read;
+#[cfg(feature = "std")]
+use std::io::{self, Read};
+#[cfg(feature = "std")]
 use std::path::Path;
+#[cfg(feature = "std")]
 use std::fs::File;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::str;
 
-use errors::{Result, ErrorKind};
+use errors::{Error, Result, ErrorKind};
+use message::MessageRead;
 
 use byteorder::LittleEndian as LE;
 use byteorder::ByteOrder;
 
-const WIRE_TYPE_VARINT: u8 = 0;
-const WIRE_TYPE_FIXED64: u8 = 1;
-const WIRE_TYPE_LENGTH_DELIMITED: u8 = 2;
-const WIRE_TYPE_START_GROUP: u8 = 3;
-const WIRE_TYPE_END_GROUP: u8 = 4;
-const WIRE_TYPE_FIXED32: u8 = 5;
+/// Wire type of varint encoded fields (`int32`, `uint64`, `sint32`, `bool`, enums etc ...)
+pub const WIRE_TYPE_VARINT: u8 = 0;
+/// Wire type of 8 bytes fields (`fixed64`, `sfixed64` and `double`)
+pub const WIRE_TYPE_FIXED64: u8 = 1;
+/// Wire type of length delimited fields (`string`, `bytes`, messages and packed repeated fields)
+pub const WIRE_TYPE_LENGTH_DELIMITED: u8 = 2;
+/// Wire type of the tag starting a group
+pub const WIRE_TYPE_START_GROUP: u8 = 3;
+/// Wire type of the tag ending a group
+pub const WIRE_TYPE_END_GROUP: u8 = 4;
+/// Wire type of 4 bytes fields (`fixed32`, `sfixed32` and `float`)
+pub const WIRE_TYPE_FIXED32: u8 = 5;
+
+/// The default maximum nesting of messages and groups, as in other protobuf implementations
+pub const DEFAULT_MAX_DEPTH: usize = 100;
+
+/// Limits checked by a `BytesReader`, to reject malicious inputs before they exhaust the stack
+/// or the memory
+///
+/// # Examples
+///
+/// ```rust
+/// use quick_protobuf::{BytesReader, ReaderLimits};
+///
+/// let bytes = [0x0a, 0x03, b'a', b'b', b'c'];
+/// let limits = ReaderLimits { max_field_len: 2, ..ReaderLimits::default() };
+/// let mut reader = BytesReader::with_limits(&bytes, limits).unwrap();
+/// assert_eq!(10, reader.next_tag(&bytes).unwrap());
+/// assert!(reader.read_string(&bytes).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReaderLimits {
+    /// The maximum nesting of messages and groups (`DEFAULT_MAX_DEPTH` by default)
+    pub max_depth: usize,
+    /// The maximum size of the whole input (unlimited by default)
+    pub max_input_size: usize,
+    /// The maximum length of a single length delimited field: string, bytes, message, packed
+    /// repeated field or map entry (unlimited by default)
+    pub max_field_len: usize,
+}
+
+impl Default for ReaderLimits {
+    fn default() -> ReaderLimits {
+        ReaderLimits {
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_input_size: usize::MAX,
+            max_field_len: usize::MAX,
+        }
+    }
+}
 
 /// A struct to read protocol binary files
 ///
@@ -64,18 +117,42 @@ const WIRE_TYPE_FIXED32: u8 = 5;
 pub struct BytesReader {
     start: usize,
     end: usize,
+    depth: usize,
+    limits: ReaderLimits,
 }
 
 impl BytesReader {
 
-    /// Creates a new reader from chunks of data
+    /// Creates a new reader from chunks of data, with the default `ReaderLimits`
     pub fn from_bytes(bytes: &[u8]) -> BytesReader {
+        BytesReader::new(0, bytes.len())
+    }
+
+    /// Creates a new reader from chunks of data, checking `limits`
+    ///
+    /// Fails with `ErrorKind::InputTooLarge` if `bytes` is larger than `limits.max_input_size`.
+    pub fn with_limits(bytes: &[u8], limits: ReaderLimits) -> Result<BytesReader> {
+        if bytes.len() > limits.max_input_size {
+            return Err(ErrorKind::InputTooLarge(bytes.len(), limits.max_input_size).into());
+        }
+        Ok(BytesReader { limits, ..BytesReader::from_bytes(bytes) })
+    }
+
+    /// Creates a new reader of `start..end`, with the default `ReaderLimits`
+    fn new(start: usize, end: usize) -> BytesReader {
         BytesReader {
-            start: 0,
-            end: bytes.len()
+            start,
+            end,
+            depth: 0,
+            limits: ReaderLimits::default(),
         }
     }
 
+    /// Gets the limits checked by this reader
+    pub fn limits(&self) -> &ReaderLimits {
+        &self.limits
+    }
+
     /// Reads next tag, `None` if all bytes have been read
     #[inline(always)]
     pub fn next_tag(&mut self, bytes: &[u8]) -> Result<u32> {
@@ -83,42 +160,48 @@ impl BytesReader {
     }
 
     #[inline(always)]
-    fn read_u8(&mut self, bytes: &[u8]) -> u8 {
-        let b = bytes[self.start];
+    fn read_u8(&mut self, bytes: &[u8]) -> Result<u8> {
+        if self.start >= self.end {
+            return error_at(ErrorKind::Eof, self.start);
+        }
+        let b = match bytes.get(self.start) {
+            Some(b) => *b,
+            None => return error_at(ErrorKind::Eof, self.start),
+        };
         self.start += 1;
-        b
+        Ok(b)
     }
 
     /// Reads the next varint encoded u64
     #[inline(always)]
     pub fn read_varint32(&mut self, bytes: &[u8]) -> Result<u32> {
-        let mut b = self.read_u8(bytes);
+        let mut b = self.read_u8(bytes)?;
         if b & 0x80 == 0 { return Ok(b as u32); }
         let mut r = (b & 0x7f) as u32;
 
-        b = self.read_u8(bytes);
+        b = self.read_u8(bytes)?;
         r |= ((b & 0x7f) as u32) << 7;
         if b & 0x80 == 0 { return Ok(r); }
 
-        b = self.read_u8(bytes);
+        b = self.read_u8(bytes)?;
         r |= ((b & 0x7f) as u32) << 14;
         if b & 0x80 == 0 { return Ok(r); }
 
-        b = self.read_u8(bytes);
+        b = self.read_u8(bytes)?;
         r |= ((b & 0x7f) as u32) << 21;
         if b & 0x80 == 0 { return Ok(r); }
 
-        b = self.read_u8(bytes);
+        b = self.read_u8(bytes)?;
         r |= ((b & 0xf) as u32) << 28;
         if b & 0x80 == 0 { return Ok(r); }
 
         // discards extra bytes
         for _ in 0..5 {
-            if self.read_u8(bytes) & 0x80 == 0 { return Ok(r); }
+            if self.read_u8(bytes)? & 0x80 == 0 { return Ok(r); }
         }
 
         // cannot read more than 10 bytes
-        Err(ErrorKind::Varint.into())
+        error_at(ErrorKind::Varint, self.start - 10)
     }
 
     /// Reads the next varint encoded u64
@@ -126,50 +209,50 @@ impl BytesReader {
     pub fn read_varint64(&mut self, bytes: &[u8]) -> Result<u64> {
 
         // part0
-        let mut b = self.read_u8(bytes);
+        let mut b = self.read_u8(bytes)?;
         if b & 0x80 == 0 { return Ok(b as u64); }
         let mut r0 = (b & 0x7f) as u32;
 
-        b = self.read_u8(bytes);
+        b = self.read_u8(bytes)?;
         r0 |= ((b & 0x7f) as u32) << 7;
         if b & 0x80 == 0 { return Ok(r0 as u64); }
 
-        b = self.read_u8(bytes);
+        b = self.read_u8(bytes)?;
         r0 |= ((b & 0x7f) as u32) << 14;
         if b & 0x80 == 0 { return Ok(r0 as u64); }
 
-        b = self.read_u8(bytes);
+        b = self.read_u8(bytes)?;
         r0 |= ((b & 0x7f) as u32) << 21;
         if b & 0x80 == 0 { return Ok(r0 as u64); }
 
         // part1
-        b = self.read_u8(bytes);
+        b = self.read_u8(bytes)?;
         let mut r1 = (b & 0x7f) as u32;
         if b & 0x80 == 0 { return Ok((r0 as u64 | (r1 as u64) << 28)); }
 
-        b = self.read_u8(bytes);
+        b = self.read_u8(bytes)?;
         r1 |= ((b & 0x7f) as u32) << 7;
         if b & 0x80 == 0 { return Ok((r0 as u64 | (r1 as u64) << 28)); }
 
-        b = self.read_u8(bytes);
+        b = self.read_u8(bytes)?;
         r1 |= ((b & 0x7f) as u32) << 14;
         if b & 0x80 == 0 { return Ok((r0 as u64 | (r1 as u64) << 28)); }
 
-        b = self.read_u8(bytes);
+        b = self.read_u8(bytes)?;
         r1 |= ((b & 0x7f) as u32) << 21;
         if b & 0x80 == 0 { return Ok((r0 as u64 | (r1 as u64) << 28)); }
 
         // part2
-        b = self.read_u8(bytes);
+        b = self.read_u8(bytes)?;
         let mut r2 = (b & 0x7f) as u32;
         if b & 0x80 == 0 { return Ok(((r0 as u64 | (r1 as u64) << 28) | (r2 as u64) << 56)); }
 
-        b = self.read_u8(bytes);
+        b = self.read_u8(bytes)?;
         r2 |= (b as u32) << 7;
         if b & 0x80 == 0 { return Ok(((r0 as u64 | (r1 as u64) << 28) | (r2 as u64) << 56)); }
 
         // cannot read more than 10 bytes
-        Err(ErrorKind::Varint.into())
+        error_at(ErrorKind::Varint, self.start - 10)
         
     }
 
@@ -213,14 +296,27 @@ impl BytesReader {
         Ok(((n >> 1) as i64) ^ (-((n & 1) as i64)))
     }
 
-    /// Reads fixed64 (little endian u64)
+    /// Reads a fixed size chunk of data and converts it with `read`
     #[inline]
     fn read_fixed<M, F: Fn(&[u8]) -> M>(&mut self, bytes: &[u8], len: usize, read: F) -> Result<M> {
-        let v = read(&bytes[self.start .. self.start + len]);
-        self.start += len;
+        let end = self.checked_end(len)?;
+        let v = match bytes.get(self.start..end) {
+            Some(b) => read(b),
+            None => return error_at(ErrorKind::Eof, self.start),
+        };
+        self.start = end;
         Ok(v)
     }
 
+    /// Computes the position `len` bytes ahead, making sure it doesn't go past `self.end`
+    #[inline(always)]
+    fn checked_end(&self, len: usize) -> Result<usize> {
+        match self.start.checked_add(len) {
+            Some(end) if end <= self.end => Ok(end),
+            _ => error_at(ErrorKind::Eof, self.start),
+        }
+    }
+
     /// Reads fixed64 (little endian u64)
     #[inline]
     pub fn read_fixed64(&mut self, bytes: &[u8]) -> Result<u64> {
@@ -273,15 +369,43 @@ impl BytesReader {
     fn read_len<'a, M, F>(&mut self, bytes: &'a [u8], mut read: F) -> Result<M>
         where F: FnMut(&mut BytesReader, &'a[u8]) -> Result<M>,
     {
-        let len = self.read_varint32(bytes)? as usize;
+        let len = self.read_field_len(bytes)?;
+        let end = self.checked_end(len)?;
+        if end > bytes.len() {
+            return error_at(ErrorKind::Eof, self.start);
+        }
         let cur_end = self.end;
-        self.end = self.start + len;
+        self.end = end;
         let v = read(self, bytes)?;
         self.start = self.end;
         self.end = cur_end;
         Ok(v)
     }
 
+    /// Reads the length of a length delimited field, checking it against `max_field_len`
+    #[inline(always)]
+    fn read_field_len(&mut self, bytes: &[u8]) -> Result<usize> {
+        let len = self.read_varint64(bytes)?;
+        if len > self.limits.max_field_len as u64 {
+            return error_at(ErrorKind::FieldTooLarge(len, self.limits.max_field_len), self.start);
+        }
+        Ok(len as usize)
+    }
+
+    /// Runs `read` one nesting level deeper, checking the depth against `max_depth`
+    #[inline(always)]
+    fn nested<M, F>(&mut self, read: F) -> Result<M>
+        where F: FnOnce(&mut BytesReader) -> Result<M>,
+    {
+        if self.depth >= self.limits.max_depth {
+            return error_at(ErrorKind::RecursionLimitExceeded(self.limits.max_depth), self.start);
+        }
+        self.depth += 1;
+        let v = read(self);
+        self.depth -= 1;
+        v
+    }
+
     /// Reads bytes (Vec<u8>)
     #[inline]
     pub fn read_bytes<'a>(&mut self, bytes: &'a[u8]) -> Result<&'a[u8]> {
@@ -291,7 +415,9 @@ impl BytesReader {
     /// Reads string (String)
     #[inline]
     pub fn read_string<'a>(&mut self, bytes: &'a[u8]) -> Result<&'a str> {
-        self.read_len(bytes, |r, b| ::std::str::from_utf8(&b[r.start..r.end]).map_err(|e| e.into()))
+        self.read_len(bytes, |r, b| {
+            str::from_utf8(&b[r.start..r.end]).map_err(|e| Error::from(e).at(r.start + e.valid_up_to()))
+        })
     }
 
     /// Reads packed repeated field (Vec<M>)
@@ -312,11 +438,113 @@ impl BytesReader {
     }
 
     /// Reads a nested message
+    ///
+    /// Fails with `ErrorKind::RecursionLimitExceeded` if messages are nested deeper than
+    /// `ReaderLimits::max_depth`.
     #[inline]
     pub fn read_message<'a, M, F>(&mut self, bytes: &'a[u8], read: F) -> Result<M>
         where F: FnMut(&mut BytesReader, &'a[u8]) -> Result<M> 
     {
-        self.read_len(bytes, read)
+        self.nested(|r| r.read_len(bytes, read))
+    }
+
+    /// Reads a map item: (key, value)
+    ///
+    /// A map entry is a length delimited message with the key as field 1 and the value as field 2.
+    /// Missing key or value are defaulted.
+    #[inline]
+    pub fn read_map<'a, K, V, F, G>(&mut self, bytes: &'a[u8], mut read_key: F, mut read_val: G) -> Result<(K, V)>
+        where F: FnMut(&mut BytesReader, &'a[u8]) -> Result<K>,
+              G: FnMut(&mut BytesReader, &'a[u8]) -> Result<V>,
+              K: Default,
+              V: Default,
+    {
+        self.read_len(bytes, |r, bytes| {
+            let mut k = K::default();
+            let mut v = V::default();
+            while !r.is_eof() {
+                let t = r.next_tag(bytes)?;
+                match t >> 3 {
+                    1 => k = read_key(r, bytes)?,
+                    2 => v = read_val(r, bytes)?,
+                    _ => r.read_unknown(bytes, t)?,
+                }
+            }
+            Ok((k, v))
+        })
+    }
+
+    /// Skips all fields up to the end group tag matching `tag` (the start group tag)
+    ///
+    /// Returns the position of the end group tag, the reader is left right after it
+    fn skip_group(&mut self, bytes: &[u8], tag: u32) -> Result<usize> {
+        loop {
+            let end = self.start;
+            let t = self.next_tag(bytes)?;
+            if t & 0x7 == WIRE_TYPE_END_GROUP as u32 {
+                if t >> 3 != tag >> 3 {
+                    return error_at(ErrorKind::UnexpectedEndGroup(t >> 3), end);
+                }
+                return Ok(end);
+            }
+            self.read_unknown(bytes, t)?;
+        }
+    }
+
+    /// Reads a group (deprecated proto2 feature)
+    ///
+    /// `tag` is the start group tag which has just been read. Contrary to messages, groups are not
+    /// length delimited but end with an end group tag: this tag is looked for first so that
+    /// `read` sees the group content as any regular message.
+    #[inline]
+    pub fn read_group<'a, M, F>(&mut self, bytes: &'a[u8], tag: u32, mut read: F) -> Result<M>
+        where F: FnMut(&mut BytesReader, &'a[u8]) -> Result<M>
+    {
+        self.nested(|r| {
+            let start = r.start;
+            let end = r.skip_group(bytes, tag)?;
+            let after = r.start;
+            let cur_end = r.end;
+            r.start = start;
+            r.end = end;
+            let v = read(r, bytes)?;
+            r.start = after;
+            r.end = cur_end;
+            Ok(v)
+        })
+    }
+
+    /// Iterates over a sequence of length delimited messages until all bytes have been read
+    ///
+    /// This is the framing used by `Writer::write_messages` and by Java `writeDelimitedTo`
+    /// / C++ `SerializeDelimitedToOstream`. The iterator stops after the first error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use quick_protobuf::{BytesReader, Result};
+    /// # struct Foo {}
+    /// # impl Foo {
+    /// #     fn from_reader(_: &mut BytesReader, _: &[u8]) -> Result<Foo> { Ok(Foo {}) }
+    /// # }
+    /// // 2 empty `Foo` messages, each prefixed with its length
+    /// let bytes = [0, 0];
+    /// let mut reader = BytesReader::from_bytes(&bytes);
+    /// let foos = reader.messages(&bytes, Foo::from_reader)
+    ///     .collect::<Result<Vec<_>>>()
+    ///     .expect("Cannot read Foos");
+    /// assert_eq!(2, foos.len());
+    /// ```
+    pub fn messages<'r, 'a, M, F>(&'r mut self, bytes: &'a[u8], read: F) -> Messages<'r, 'a, M, F>
+        where F: FnMut(&mut BytesReader, &'a[u8]) -> Result<M>
+    {
+        Messages {
+            reader: self,
+            bytes,
+            read,
+            done: false,
+            _message: PhantomData,
+        }
     }
 
     /// Reads unknown data, based on its tag value (which itself gives us the wire_type value)
@@ -324,19 +552,33 @@ impl BytesReader {
     pub fn read_unknown(&mut self, bytes: &[u8], tag_value: u32) -> Result<()> {
         match (tag_value & 0x7) as u8 {
             WIRE_TYPE_VARINT => { self.read_varint64(bytes)?; },
-            WIRE_TYPE_FIXED64 => self.start += 8,
-            WIRE_TYPE_FIXED32 => self.start += 4,
+            WIRE_TYPE_FIXED64 => self.start = self.checked_end(8)?,
+            WIRE_TYPE_FIXED32 => self.start = self.checked_end(4)?,
             WIRE_TYPE_LENGTH_DELIMITED => {
-                let len = self.read_varint64(bytes)? as usize;
-                self.start += len;
+                let len = self.read_field_len(bytes)?;
+                self.start = self.checked_end(len)?;
             },
-            WIRE_TYPE_START_GROUP | 
-                WIRE_TYPE_END_GROUP => { return Err(ErrorKind::Deprecated("group").into()); },
-            t => { return Err(ErrorKind::UnknownWireType(t).into()); },
+            WIRE_TYPE_START_GROUP => { self.nested(|r| r.skip_group(bytes, tag_value))?; },
+            WIRE_TYPE_END_GROUP => { return error_at(ErrorKind::UnexpectedEndGroup(tag_value >> 3), self.start); },
+            t => { return error_at(ErrorKind::UnknownWireType(t), self.start); },
         }
         Ok(())
     }
 
+    /// Reads unknown data, based on its tag value, and returns its raw bytes
+    ///
+    /// The returned slice holds everything following the tag on the wire (e.g. both the length
+    /// and the data of a length delimited field), so that the field can be written back verbatim
+    #[inline]
+    pub fn read_unknown_field<'a>(&mut self, bytes: &'a [u8], tag_value: u32) -> Result<&'a [u8]> {
+        let start = self.start;
+        self.read_unknown(bytes, tag_value)?;
+        match bytes.get(start..self.start) {
+            Some(b) => Ok(b),
+            None => error_at(ErrorKind::Eof, start),
+        }
+    }
+
     /// Gets the remaining length of bytes not read yet
     #[inline]
     pub fn len(&self) -> usize {
@@ -350,6 +592,38 @@ impl BytesReader {
     }
 }
 
+/// Fails with `kind`, which happened at `offset` in the decoded bytes
+#[cold]
+fn error_at<T>(kind: ErrorKind, offset: usize) -> Result<T> {
+    Err(Error::from(kind).at(offset))
+}
+
+/// An iterator over length delimited messages
+///
+/// Created by `BytesReader::messages` or `Reader::messages`
+pub struct Messages<'r, 'a, M, F> {
+    reader: &'r mut BytesReader,
+    bytes: &'a [u8],
+    read: F,
+    done: bool,
+    _message: PhantomData<M>,
+}
+
+impl<'r, 'a, M, F> Iterator for Messages<'r, 'a, M, F>
+    where F: FnMut(&mut BytesReader, &'a[u8]) -> Result<M>
+{
+    type Item = Result<M>;
+
+    fn next(&mut self) -> Option<Result<M>> {
+        if self.done || self.reader.is_eof() {
+            return None;
+        }
+        let m = self.reader.read_message(self.bytes, &mut self.read);
+        self.done = m.is_err();
+        Some(m)
+    }
+}
+
 /// A struct to read protobuf data
 ///
 /// Contrary to `BytesReader`, this struct will own a buffer
@@ -384,23 +658,20 @@ impl BytesReader {
 ///     println!("Found {} foos and {} bars", foobar.foos.len(), foobar.bars.len());
 /// }
 /// ```
+#[cfg(feature = "std")]
 pub struct Reader {
     buf: Vec<u8>,
     reader: BytesReader,
 }
 
+#[cfg(feature = "std")]
 impl Reader {
 
     /// Creates a new `Reader`
     pub fn from_reader<R: Read>(mut r: R, capacity: usize) -> Result<Reader> {
-        let mut buf = Vec::with_capacity(capacity);
-        unsafe { buf.set_len(capacity); }
-        buf.shrink_to_fit();
+        let mut buf = vec![0; capacity];
         r.read_exact(&mut buf)?;
-        let reader = BytesReader {
-            start: 0,
-            end: capacity,
-        };
+        let reader = BytesReader::new(0, capacity);
         Ok(Reader {
             buf: buf,
             reader: reader,
@@ -422,6 +693,240 @@ impl Reader {
         read(&mut self.reader, &self.buf)
     }
 
+    /// Reads the whole buffer as a single message
+    ///
+    /// This is a shortcut for `self.read(M::from_reader)`
+    #[inline]
+    pub fn read_message<'a, M: MessageRead<'a>>(&'a mut self) -> Result<M> {
+        self.read(M::from_reader)
+    }
+
+    /// Iterates over the length delimited messages of the buffer
+    ///
+    /// See `BytesReader::messages`
+    #[inline]
+    pub fn messages<'a, M, F>(&'a mut self, read: F) -> Messages<'a, 'a, M, F>
+        where F: FnMut(&mut BytesReader, &'a[u8]) -> Result<M>
+    {
+        self.reader.messages(&self.buf, read)
+    }
+
+}
+
+/// Default size of the internal buffer of a `StreamReader`
+#[cfg(feature = "std")]
+const DEFAULT_STREAM_CAPACITY: usize = 8 * 1024;
+
+/// Default maximum size of a message read by a `StreamReader`
+#[cfg(feature = "std")]
+pub const DEFAULT_STREAM_MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+/// A struct to read a stream of protobuf messages out of any `Read`
+///
+/// Contrary to `Reader`, the whole input is never loaded at once. The stream is expected to be
+/// a sequence of length delimited messages (as written by `Writer::write_message`) and the
+/// internal buffer is refilled on demand, so only one message needs to fit in memory.
+///
+/// Messages larger than `ReaderLimits::max_input_size` (`DEFAULT_STREAM_MAX_MESSAGE_SIZE` by
+/// default) are rejected as soon as their length is read, before buffering them.
+///
+/// # Examples
+///
+/// ```rust
+/// # mod foo_bar {
+/// #     use quick_protobuf::{BytesReader, Result};
+/// #     pub struct Foo {}
+/// #     pub struct Bar {}
+/// #     pub struct FooBar { pub foos: Vec<Foo>, pub bars: Vec<Bar>, }
+/// #     impl FooBar {
+/// #         pub fn from_reader(_: &mut BytesReader, _: &[u8]) -> Result<Self> {
+/// #              Ok(FooBar { foos: vec![], bars: vec![] })
+/// #         }
+/// #     }
+/// # }
+///
+/// // FooBar is a message generated from a proto file
+/// // in parcicular it contains a `from_reader` function
+/// use foo_bar::FooBar;
+/// use quick_protobuf::StreamReader;
+///
+/// fn main() {
+///     // any `Read` will do: a `File`, a `TcpStream` etc ...
+///     let input: &[u8];
+///     # input = &[0, 0];
+///     let mut reader = StreamReader::new(input);
+///
+///     // decode messages one by one until the end of the stream
+///     while let Some(foobar) = reader.read(FooBar::from_reader).expect("Cannot read FooBar") {
+///         println!("Found {} foos and {} bars", foobar.foos.len(), foobar.bars.len());
+///     }
+/// }
+/// ```
+#[cfg(feature = "std")]
+pub struct StreamReader<R> {
+    inner: R,
+    buf: Vec<u8>,
+    start: usize,
+    end: usize,
+    limits: ReaderLimits,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> StreamReader<R> {
+
+    /// Creates a new `StreamReader` with a default buffer capacity
+    pub fn new(inner: R) -> StreamReader<R> {
+        StreamReader::with_capacity(inner, DEFAULT_STREAM_CAPACITY)
+    }
+
+    /// Creates a new `StreamReader` with an initial buffer capacity
+    ///
+    /// The buffer grows if a message doesn't fit in it
+    pub fn with_capacity(inner: R, capacity: usize) -> StreamReader<R> {
+        StreamReader {
+            inner,
+            buf: vec![0; capacity],
+            start: 0,
+            end: 0,
+            limits: ReaderLimits {
+                max_input_size: DEFAULT_STREAM_MAX_MESSAGE_SIZE,
+                ..ReaderLimits::default()
+            },
+        }
+    }
+
+    /// Creates a new `StreamReader` with a default buffer capacity, checking `limits`
+    ///
+    /// `limits.max_input_size` is the maximum size of each message, the other limits are checked
+    /// by the `BytesReader` reading each message.
+    pub fn with_limits(inner: R, limits: ReaderLimits) -> StreamReader<R> {
+        StreamReader { limits, ..StreamReader::new(inner) }
+    }
+
+    /// Gets the limits checked by this reader
+    pub fn limits(&self) -> &ReaderLimits {
+        &self.limits
+    }
+
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps this `StreamReader`, returning the underlying reader
+    ///
+    /// Any data already buffered but not yet read is lost
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads from `inner` until at least `len` bytes are buffered
+    ///
+    /// Returns `false` if `inner` reached its end before
+    fn fill(&mut self, len: usize) -> Result<bool> {
+        while self.end - self.start < len {
+            if self.buf.len() - self.start < len {
+                // not enough room: move pending bytes at the front and grow if needed
+                self.buf.copy_within(self.start..self.end, 0);
+                self.end -= self.start;
+                self.start = 0;
+                if self.buf.len() < len {
+                    let new_len = ::core::cmp::max(len, 2 * self.buf.len());
+                    self.buf.resize(new_len, 0);
+                }
+            }
+            match self.inner.read(&mut self.buf[self.end..]) {
+                Ok(0) => return Ok(false),
+                Ok(n) => self.end += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(true)
+    }
+
+    /// Checks if the buffered bytes hold a complete varint
+    fn has_varint(&self) -> bool {
+        let pending = &self.buf[self.start..self.end];
+        pending.len() >= 10 || pending.iter().any(|b| b & 0x80 == 0)
+    }
+
+    /// Reads the next length delimited message and runs a `BytesReader` dependent function on it
+    ///
+    /// Returns `None` once the stream ends on a message boundary and an `ErrorKind::Eof` error
+    /// if it ends in the middle of a message. Fails with `ErrorKind::MessageTooLarge` if the
+    /// message is larger than `ReaderLimits::max_input_size`.
+    pub fn read<'a, M, F>(&'a mut self, mut read: F) -> Result<Option<M>>
+        where F: FnMut(&mut BytesReader, &'a[u8]) -> Result<M>
+    {
+        while !self.has_varint() {
+            let pending = self.end - self.start;
+            if !self.fill(pending + 1)? {
+                if pending == 0 {
+                    return Ok(None);
+                }
+                return Err(ErrorKind::Eof.into());
+            }
+        }
+
+        let (header_len, len) = {
+            let mut r = BytesReader::new(self.start, self.end);
+            let len = r.read_varint64(&self.buf)?;
+            (r.start - self.start, len)
+        };
+        let max = self.limits.max_input_size;
+        if len > max as u64 {
+            let len = ::core::cmp::min(len, usize::MAX as u64) as usize;
+            return Err(ErrorKind::MessageTooLarge(len, max).into());
+        }
+        let total = header_len.checked_add(len as usize).ok_or(ErrorKind::Eof)?;
+        if !self.fill(total)? {
+            return Err(ErrorKind::Eof.into());
+        }
+
+        let mut reader = BytesReader {
+            limits: self.limits,
+            ..BytesReader::new(self.start + header_len, self.start + total)
+        };
+        self.start += total;
+        read(&mut reader, &self.buf).map(Some)
+    }
+
+    /// Reads the next length delimited message
+    ///
+    /// This is a shortcut for `self.read(M::from_reader)`
+    #[inline]
+    pub fn read_message<'a, M: MessageRead<'a>>(&'a mut self) -> Result<Option<M>> {
+        self.read(M::from_reader)
+    }
+}
+
+/// Deserializes a message out of a slice holding exactly this message
+///
+/// # Examples
+///
+/// ```rust
+/// # mod foo_bar {
+/// #     use quick_protobuf::{MessageRead, BytesReader, Result};
+/// #     pub struct Foo {}
+/// #     impl<'a> MessageRead<'a> for Foo {
+/// #         fn from_reader(_: &mut BytesReader, _: &[u8]) -> Result<Self> {
+/// #              Ok(Foo {})
+/// #         }
+/// #     }
+/// # }
+/// use foo_bar::Foo;
+/// use quick_protobuf::deserialize_from_slice;
+///
+/// fn main() {
+///     let bytes: Vec<u8>;
+///     # bytes = vec![];
+///     let foo: Foo = deserialize_from_slice(&bytes).expect("Cannot read Foo");
+/// }
+/// ```
+pub fn deserialize_from_slice<'a, M: MessageRead<'a>>(bytes: &'a [u8]) -> Result<M> {
+    let mut reader = BytesReader::from_bytes(bytes);
+    M::from_reader(&mut reader, bytes)
 }
 
 #[test]
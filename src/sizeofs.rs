@@ -2,6 +2,9 @@
 //!
 //! This module is used primilarly when implementing the `MessageWrite::get_size`
 
+use alloc::vec::Vec;
+
+use message::MessageWrite;
 
 /// Computes the binary size of the varint encoded u64
 ///
@@ -68,3 +71,25 @@ pub fn sizeof_bool(_: bool) -> usize {
 pub fn sizeof_enum(v: i32) -> usize {
     sizeof_int32(v)
 }
+
+/// Computes the binary size of a length delimited map entry
+///
+/// `key_size` and `value_size` are the sizes of the key and value fields, tags included
+pub fn sizeof_map_entry(key_size: usize, value_size: usize) -> usize {
+    sizeof_var_length(key_size + value_size)
+}
+
+/// Computes the binary size of a length delimited nested message, recording its size
+///
+/// The size of `m`, and the index where the sizes of its own nested messages end, are pushed
+/// into `sizes` before the sizes of its nested messages, which is the order
+/// `Writer::write_message` reads them back.
+pub fn sizeof_message_cached<M: MessageWrite>(m: &M, sizes: &mut Vec<usize>) -> usize {
+    let i = sizes.len();
+    sizes.push(0);
+    sizes.push(0);
+    let len = m.get_size_cached(sizes);
+    sizes[i] = len;
+    sizes[i + 1] = sizes.len();
+    sizeof_var_length(len)
+}
@@ -0,0 +1,722 @@
+//! A module to convert messages from and to the protobuf text format
+//!
+//! pb-rs generates `TextWrite` and `TextRead` implementations for all messages and enums when
+//! run with `--text`. Field and enum value names are the ones of the .proto file, e.g.
+//!
+//! ```text
+//! id: 1
+//! name: "x"
+//! # a comment
+//! nested { kind: FOO values: [1, 2] }
+//! ```
+//!
+//! Parse errors point at the line and column of the faulty token.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::char;
+use core::fmt::{self, Write};
+use core::slice;
+use core::str;
+
+use errors::{Error, Result, ErrorKind};
+
+/// A trait to write a message or an enum in text format
+pub trait TextWrite {
+
+    /// Writes `self` as the value of the last `TextWriter::key`
+    ///
+    /// Top-level messages write their fields only
+    fn write_text(&self, w: &mut TextWriter);
+}
+
+/// A trait to read a message or an enum out of a parsed text format value
+pub trait TextRead: Sized {
+
+    /// Converts a parsed value into `Self`
+    fn from_text(v: &Value) -> Result<Self>;
+}
+
+/// Serializes a message in text format
+pub fn to_string<M: TextWrite>(m: &M) -> String {
+    let mut w = TextWriter::new();
+    m.write_text(&mut w);
+    w.into_string()
+}
+
+/// Deserializes a message out of a text format string
+pub fn from_str<M: TextRead>(s: &str) -> Result<M> {
+    M::from_text(&Value::parse(s)?)
+}
+
+/// The maximum nesting of messages and lists, deeper inputs are rejected
+pub const MAX_DEPTH: usize = 64;
+
+fn error<T>(msg: String, line: usize, column: usize) -> Result<T> {
+    Err(ErrorKind::Text(format!("{} at line {}, column {}", msg, line, column)).into())
+}
+
+/// A field of a parsed message
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    /// The name of the field
+    pub name: String,
+    /// The value of the field
+    pub value: Value,
+    /// The line of the field name, starting at 1
+    pub line: usize,
+    /// The column of the field name, starting at 1
+    pub column: usize,
+}
+
+impl Field {
+
+    /// Gets an error for a field the message doesn't have
+    pub fn unknown(&self) -> Error {
+        let msg = format!("unknown field '{}' at line {}, column {}", self.name, self.line, self.column);
+        ErrorKind::Text(msg).into()
+    }
+}
+
+/// The kind of a parsed value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Kind {
+    /// A number or an identifier (`true`, `inf`, an enum value name etc ...), as written
+    Literal(String),
+    /// A string, unescaped (adjacent strings are concatenated)
+    String(Vec<u8>),
+    /// A message, in `{ }` or `< >`
+    Message(Vec<Field>),
+    /// A list of values, in `[ ]`
+    List(Vec<Value>),
+}
+
+/// A parsed value, with its position
+#[derive(Debug, Clone, PartialEq)]
+pub struct Value {
+    /// The value
+    pub kind: Kind,
+    /// The line of the value, starting at 1
+    pub line: usize,
+    /// The column of the value, starting at 1
+    pub column: usize,
+}
+
+macro_rules! as_integer {
+    ($name:ident, $ty:ty) => (
+    /// Converts a decimal, hexadecimal (`0x`) or octal (`0`) literal into an integer
+    pub fn $name(&self) -> Result<$ty> {
+        let lit = self.literal("an integer")?;
+        match parse_integer(lit) {
+            Some(i) if i >= <$ty>::MIN as i128 && i <= <$ty>::MAX as i128 => Ok(i as $ty),
+            Some(_) => self.error(&format!("integer {} out of range", lit)),
+            None => self.error(&format!("expecting an integer, found '{}'", lit)),
+        }
+    }
+    );
+}
+
+macro_rules! as_float {
+    ($name:ident, $ty:ty) => (
+    /// Converts a number, `inf`, `-inf` or `nan` into a float
+    pub fn $name(&self) -> Result<$ty> {
+        let lit = self.literal("a float")?;
+        let (neg, abs) = match lit.strip_prefix('-') {
+            Some(abs) => (true, abs),
+            None => (false, lit),
+        };
+        let f = match &*abs.to_lowercase() {
+            "inf" | "infinity" => <$ty>::INFINITY,
+            "nan" => <$ty>::NAN,
+            abs if !abs.starts_with("0x") && abs.ends_with('f') => {
+                match abs[..abs.len() - 1].parse::<$ty>() {
+                    Ok(f) => f,
+                    Err(_) => return self.error("expecting a float"),
+                }
+            }
+            abs => match abs.parse::<$ty>() {
+                Ok(f) => f,
+                Err(_) => match parse_integer(abs) {
+                    Some(i) => i as $ty,
+                    None => return self.error("expecting a float"),
+                },
+            },
+        };
+        Ok(if neg { -f } else { f })
+    }
+    );
+}
+
+impl Value {
+
+    /// Parses a text format message
+    ///
+    /// The returned value is a `Kind::Message` holding the top-level fields. Fails if messages
+    /// and lists are nested deeper than `MAX_DEPTH`.
+    pub fn parse(s: &str) -> Result<Value> {
+        let mut p = Parser { bytes: s.as_bytes(), pos: 0, line: 1, column: 1, depth: 0 };
+        let fields = p.parse_fields(None)?;
+        Ok(Value { kind: Kind::Message(fields), line: 1, column: 1 })
+    }
+
+    fn error<T>(&self, msg: &str) -> Result<T> {
+        error(msg.to_string(), self.line, self.column)
+    }
+
+    /// Gets an error for an enum value name the enum `name` doesn't have
+    pub fn unknown_value(&self, name: &str) -> Error {
+        let msg = format!("unknown {} value '{}' at line {}, column {}",
+                          name, self.as_ident().unwrap_or(""), self.line, self.column);
+        ErrorKind::Text(msg).into()
+    }
+
+    fn literal(&self, expected: &str) -> Result<&str> {
+        match self.kind {
+            Kind::Literal(ref l) => Ok(l),
+            _ => self.error(&format!("expecting {}", expected)),
+        }
+    }
+
+    as_integer!(as_i32, i32);
+    as_integer!(as_i64, i64);
+    as_integer!(as_u32, u32);
+    as_integer!(as_u64, u64);
+    as_float!(as_f32, f32);
+    as_float!(as_f64, f64);
+
+    /// Converts `true`, `True`, `t`, `1`, `false`, `False`, `f` or `0` into a `bool`
+    pub fn as_bool(&self) -> Result<bool> {
+        match self.literal("a boolean")? {
+            "true" | "True" | "t" | "1" => Ok(true),
+            "false" | "False" | "f" | "0" => Ok(false),
+            _ => self.error("expecting a boolean"),
+        }
+    }
+
+    /// Gets an identifier, e.g. the name of an enum value
+    pub fn as_ident(&self) -> Result<&str> {
+        let lit = self.literal("an identifier")?;
+        if lit.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            Ok(lit)
+        } else {
+            self.error("expecting an identifier")
+        }
+    }
+
+    /// Gets a string
+    pub fn as_str(&self) -> Result<&str> {
+        match str::from_utf8(self.as_bytes()?) {
+            Ok(s) => Ok(s),
+            Err(_) => self.error("expecting a valid utf8 string"),
+        }
+    }
+
+    /// Gets the content of a string
+    pub fn as_bytes(&self) -> Result<&[u8]> {
+        match self.kind {
+            Kind::String(ref s) => Ok(s),
+            _ => self.error("expecting a string"),
+        }
+    }
+
+    /// Gets the fields of a message
+    pub fn as_message(&self) -> Result<&[Field]> {
+        match self.kind {
+            Kind::Message(ref m) => Ok(m),
+            _ => self.error("expecting a message"),
+        }
+    }
+
+    /// Gets the items of a list, or the value itself if it isn't a list
+    ///
+    /// Repeated fields can either be written several times or once with a list
+    pub fn values(&self) -> &[Value] {
+        match self.kind {
+            Kind::List(ref l) => l,
+            _ => slice::from_ref(self),
+        }
+    }
+}
+
+/// Parses a decimal, hexadecimal or octal integer
+fn parse_integer(s: &str) -> Option<i128> {
+    let (neg, abs) = match s.strip_prefix('-') {
+        Some(abs) => (true, abs),
+        None => (false, s),
+    };
+    let i = if abs.starts_with("0x") || abs.starts_with("0X") {
+        u64::from_str_radix(&abs[2..], 16).ok()?
+    } else if abs.len() > 1 && abs.starts_with('0') {
+        u64::from_str_radix(&abs[1..], 8).ok()?
+    } else {
+        abs.parse::<u64>().ok()?
+    } as i128;
+    Some(if neg { -i } else { i })
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    line: usize,
+    column: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+
+    fn error<T>(&self, expected: &str) -> Result<T> {
+        let found = match self.bytes.get(self.pos) {
+            Some(_) => {
+                let rest = &self.bytes[self.pos..];
+                let end = rest.iter()
+                    .position(|b| b.is_ascii_whitespace())
+                    .unwrap_or(rest.len())
+                    .min(16);
+                format!("'{}'", String::from_utf8_lossy(&rest[..end]))
+            }
+            None => "end of input".to_string(),
+        };
+        error(format!("expecting {}, found {}", expected, found), self.line, self.column)
+    }
+
+    fn bump(&mut self) {
+        if self.bytes[self.pos] == b'\n' {
+            self.line += 1;
+            self.column = 1;
+        } else if self.bytes[self.pos] & 0xC0 != 0x80 {
+            // do not count utf8 continuation bytes
+            self.column += 1;
+        }
+        self.pos += 1;
+    }
+
+    fn skip_whitespaces(&mut self) {
+        while self.pos < self.bytes.len() {
+            match self.bytes[self.pos] {
+                b' ' | b'\t' | b'\n' | b'\r' => self.bump(),
+                b'#' => while self.pos < self.bytes.len() && self.bytes[self.pos] != b'\n' {
+                    self.bump();
+                },
+                _ => break,
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespaces();
+        self.bytes.get(self.pos).cloned()
+    }
+
+    fn expect(&mut self, b: u8) -> Result<()> {
+        if self.peek() == Some(b) {
+            self.bump();
+            Ok(())
+        } else {
+            self.error(&format!("'{}'", b as char))
+        }
+    }
+
+    /// Parses fields until `end` (or the end of input for top-level fields)
+    fn parse_fields(&mut self, end: Option<u8>) -> Result<Vec<Field>> {
+        let mut fields = Vec::new();
+        loop {
+            let b = self.peek();
+            if b == end {
+                if b.is_some() {
+                    self.bump();
+                }
+                return Ok(fields);
+            }
+            let (line, column) = (self.line, self.column);
+            let name = match b {
+                Some(b) if b.is_ascii_alphabetic() || b == b'_' => self.parse_word(),
+                _ => return self.error(&match end {
+                    Some(end) => format!("a field name or '{}'", end as char),
+                    None => "a field name".to_string(),
+                }),
+            };
+            let colon = self.peek() == Some(b':');
+            if colon {
+                self.bump();
+            }
+            let value = match self.peek() {
+                Some(b'{') | Some(b'<') => self.parse_value()?,
+                _ if !colon => return self.error("':'"),
+                _ => self.parse_value()?,
+            };
+            fields.push(Field { name, value, line, column });
+            match self.peek() {
+                Some(b',') | Some(b';') => self.bump(),
+                _ => (),
+            }
+        }
+    }
+
+    fn parse_word(&mut self) -> String {
+        let start = self.pos;
+        while self.pos < self.bytes.len() {
+            match self.bytes[self.pos] {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_' | b'.' => self.bump(),
+                // exponent sign
+                b'+' | b'-' if self.pos > start + 1
+                    && (self.bytes[self.pos - 1] == b'e' || self.bytes[self.pos - 1] == b'E')
+                    && (self.bytes[start].is_ascii_digit() || self.bytes[start] == b'.') => self.bump(),
+                _ => break,
+            }
+        }
+        String::from_utf8_lossy(&self.bytes[start..self.pos]).into_owned()
+    }
+
+    /// Runs `parse` one nesting level deeper, checking the depth against `MAX_DEPTH`
+    fn nested<T, F>(&mut self, parse: F) -> Result<T>
+        where F: FnOnce(&mut Parser<'a>) -> Result<T>,
+    {
+        if self.depth >= MAX_DEPTH {
+            return error(format!("values nested deeper than {} levels", MAX_DEPTH), self.line, self.column);
+        }
+        self.depth += 1;
+        let v = parse(self);
+        self.depth -= 1;
+        v
+    }
+
+    fn parse_value(&mut self) -> Result<Value> {
+        let b = self.peek();
+        let (line, column) = (self.line, self.column);
+        let kind = match b {
+            Some(b'{') => Kind::Message(self.nested(|p| {
+                p.bump();
+                p.parse_fields(Some(b'}'))
+            })?),
+            Some(b'<') => Kind::Message(self.nested(|p| {
+                p.bump();
+                p.parse_fields(Some(b'>'))
+            })?),
+            Some(b'[') => Kind::List(self.nested(|p| {
+                p.bump();
+                let mut items = Vec::new();
+                if p.peek() != Some(b']') {
+                    loop {
+                        items.push(p.parse_value()?);
+                        if p.peek() != Some(b',') {
+                            break;
+                        }
+                        p.bump();
+                    }
+                }
+                p.expect(b']')?;
+                Ok(items)
+            })?),
+            Some(b'"') | Some(b'\'') => {
+                let mut s = Vec::new();
+                while let Some(q @ b'"') | Some(q @ b'\'') = self.peek() {
+                    self.parse_string(q, &mut s)?;
+                }
+                Kind::String(s)
+            }
+            Some(b'-') => {
+                self.bump();
+                match self.peek() {
+                    Some(b) if b.is_ascii_alphanumeric() || b == b'.' => (),
+                    _ => return self.error("a number"),
+                }
+                Kind::Literal(format!("-{}", self.parse_word()))
+            }
+            Some(b) if b.is_ascii_alphanumeric() || b == b'_' || b == b'.' => {
+                Kind::Literal(self.parse_word())
+            }
+            _ => return self.error("a value"),
+        };
+        Ok(Value { kind, line, column })
+    }
+
+    fn parse_digits(&mut self, radix: u32, max: usize) -> Option<u32> {
+        let start = self.pos;
+        let mut v = 0;
+        while self.pos - start < max {
+            match self.bytes.get(self.pos).and_then(|b| (*b as char).to_digit(radix)) {
+                Some(d) => v = v * radix + d,
+                None => break,
+            }
+            self.bump();
+        }
+        if self.pos == start { None } else { Some(v) }
+    }
+
+    fn parse_string(&mut self, quote: u8, s: &mut Vec<u8>) -> Result<()> {
+        self.bump();
+        loop {
+            let b = match self.bytes.get(self.pos) {
+                Some(b'\n') | None => return self.error(&format!("'{}'", quote as char)),
+                Some(b) => *b,
+            };
+            if b == quote {
+                self.bump();
+                return Ok(());
+            }
+            if b != b'\\' {
+                s.push(b);
+                self.bump();
+                continue;
+            }
+            self.bump();
+            let e = match self.bytes.get(self.pos) {
+                Some(e) => *e,
+                None => return self.error("an escape sequence"),
+            };
+            let c = match e {
+                b'0'..=b'7' => {
+                    let o = self.parse_digits(8, 3).unwrap_or(0);
+                    if o > 0xFF {
+                        return self.error("an octal escape below \\400");
+                    }
+                    s.push(o as u8);
+                    continue;
+                }
+                b'x' | b'X' => {
+                    self.bump();
+                    match self.parse_digits(16, 2) {
+                        Some(h) => s.push(h as u8),
+                        None => return self.error("hexadecimal digits"),
+                    }
+                    continue;
+                }
+                b'u' | b'U' => {
+                    self.bump();
+                    let len = if e == b'u' { 4 } else { 8 };
+                    match self.parse_digits(16, len).and_then(char::from_u32) {
+                        Some(c) => c,
+                        None => return self.error("a valid unicode escape"),
+                    }
+                }
+                _ => {
+                    let c = match e {
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'a' => '\x07',
+                        b'b' => '\x08',
+                        b'f' => '\x0c',
+                        b'v' => '\x0b',
+                        b'\\' | b'\'' | b'"' | b'?' => e as char,
+                        _ => return self.error("an escape sequence"),
+                    };
+                    self.bump();
+                    c
+                }
+            };
+            let mut buf = [0; 4];
+            s.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+        }
+    }
+}
+
+/// A struct to write messages in text format into a `String`
+///
+/// Fields are written one per line, nested messages being indented
+#[derive(Debug, Default)]
+pub struct TextWriter {
+    out: String,
+    /// for each open message, is it nested (i.e. enclosed in braces)
+    nested: Vec<bool>,
+    /// is a key written, waiting for its value
+    after_key: bool,
+}
+
+impl TextWriter {
+
+    /// Creates a new, empty, `TextWriter`
+    pub fn new() -> TextWriter {
+        TextWriter::default()
+    }
+
+    /// Gets the text written so far
+    pub fn into_string(self) -> String {
+        self.out
+    }
+
+    fn indent(&mut self) {
+        for _ in 0..self.nested.iter().filter(|n| **n).count() {
+            self.out.push_str("  ");
+        }
+    }
+
+    /// Writes the name of the field of the next value
+    pub fn key(&mut self, name: &str) {
+        self.indent();
+        self.out.push_str(name);
+        self.after_key = true;
+    }
+
+    fn write_scalar<T: fmt::Display>(&mut self, v: T) {
+        self.after_key = false;
+        let _ = writeln!(self.out, ": {}", v);
+    }
+
+    /// Starts a message, nested in braces if it is the value of a field
+    pub fn begin_message(&mut self) {
+        self.nested.push(self.after_key);
+        if self.after_key {
+            self.after_key = false;
+            self.out.push_str(" {\n");
+        }
+    }
+
+    /// Ends the current message
+    pub fn end_message(&mut self) {
+        if self.nested.pop() == Some(true) {
+            self.indent();
+            self.out.push_str("}\n");
+        }
+    }
+
+    /// Writes an `i32`
+    pub fn write_i32(&mut self, v: i32) {
+        self.write_scalar(v);
+    }
+
+    /// Writes a `u32`
+    pub fn write_u32(&mut self, v: u32) {
+        self.write_scalar(v);
+    }
+
+    /// Writes an `i64`
+    pub fn write_i64(&mut self, v: i64) {
+        self.write_scalar(v);
+    }
+
+    /// Writes a `u64`
+    pub fn write_u64(&mut self, v: u64) {
+        self.write_scalar(v);
+    }
+
+    /// Writes an `f32` (`inf`, `-inf` and `nan` for non finite values)
+    pub fn write_f32(&mut self, v: f32) {
+        if v.is_finite() {
+            self.write_scalar(v);
+        } else {
+            self.write_f64(v as f64);
+        }
+    }
+
+    /// Writes an `f64` (`inf`, `-inf` and `nan` for non finite values)
+    pub fn write_f64(&mut self, v: f64) {
+        if v.is_nan() {
+            self.write_scalar("nan");
+        } else if v.is_infinite() {
+            self.write_scalar(if v > 0. { "inf" } else { "-inf" });
+        } else {
+            self.write_scalar(v);
+        }
+    }
+
+    /// Writes a `bool`
+    pub fn write_bool(&mut self, v: bool) {
+        self.write_scalar(v);
+    }
+
+    /// Writes an identifier, e.g. the name of an enum value
+    pub fn write_ident(&mut self, v: &str) {
+        self.write_scalar(v);
+    }
+
+    /// Writes a quoted string
+    pub fn write_string(&mut self, v: &str) {
+        self.write_bytes(v.as_bytes());
+    }
+
+    /// Writes a quoted string, escaping non printable and non ascii bytes
+    pub fn write_bytes(&mut self, v: &[u8]) {
+        let mut s = String::with_capacity(v.len() + 2);
+        s.push('"');
+        for &b in v {
+            match b {
+                b'\n' => s.push_str("\\n"),
+                b'\r' => s.push_str("\\r"),
+                b'\t' => s.push_str("\\t"),
+                b'"' => s.push_str("\\\""),
+                b'\'' => s.push_str("\\'"),
+                b'\\' => s.push_str("\\\\"),
+                0x20..=0x7E => s.push(b as char),
+                _ => { let _ = write!(s, "\\{:03o}", b); }
+            }
+        }
+        s.push('"');
+        self.write_scalar(s);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let v = Value::parse("a: 1 b { c: -0x10, d: 'x\\ny' \"z\" } # comment\ne < f: [1.5f, inf] >;g: FOO")
+            .unwrap();
+        let fields = v.as_message().unwrap();
+        assert_eq!(4, fields.len());
+        assert_eq!(1, fields[0].value.as_i32().unwrap());
+        let b = fields[1].value.as_message().unwrap();
+        assert_eq!(-16, b[0].value.as_i64().unwrap());
+        assert_eq!("x\nyz", b[1].value.as_str().unwrap());
+        let f = fields[2].value.as_message().unwrap()[0].value.values();
+        assert_eq!(1.5, f[0].as_f32().unwrap());
+        assert_eq!(f64::INFINITY, f[1].as_f64().unwrap());
+        assert_eq!("FOO", fields[3].value.as_ident().unwrap());
+        let err = fields[3].value.as_i32().unwrap_err().to_string();
+        assert!(err.ends_with("expecting an integer, found 'FOO' at line 2, column 25"), "{}", err);
+        assert_eq!((2, 1), (fields[2].line, fields[2].column));
+        assert_eq!(&[0xFF, 0x01, b'a'], Value::parse("s: '\\377\\x01a'").unwrap()
+                   .as_message().unwrap()[0].value.as_bytes().unwrap());
+        assert!(fields[0].value.as_u32().is_ok());
+        assert!(Value::parse("a: -1").unwrap().as_message().unwrap()[0].value.as_u32().is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = Value::parse("a: 1\nb {\n  c 2\n}").unwrap_err().to_string();
+        assert!(err.ends_with("expecting ':', found '2' at line 3, column 5"), "{}", err);
+        let err = Value::parse("a: 1\nb { c: 2").unwrap_err().to_string();
+        assert!(err.contains("found end of input at line 2, column 9"), "{}", err);
+        let err = Value::parse("a: \"abc").unwrap_err().to_string();
+        assert!(err.contains("line 1, column 8"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_max_depth() {
+        let messages = |depth| format!("{}{}", "a {".repeat(depth), "}".repeat(depth));
+        assert!(Value::parse(&messages(MAX_DEPTH)).is_ok());
+        assert!(Value::parse(&messages(MAX_DEPTH + 1)).is_err());
+        let lists = |depth| format!("a: {}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(Value::parse(&lists(MAX_DEPTH)).is_ok());
+        assert!(Value::parse(&lists(MAX_DEPTH + 1)).is_err());
+
+        // must not overflow the stack
+        let err = Value::parse(&"a <".repeat(1_000_000)).unwrap_err().to_string();
+        assert!(err.contains("values nested deeper than 64 levels at line 1, column 195"), "{}", err);
+    }
+
+    #[test]
+    fn test_write() {
+        let mut w = TextWriter::new();
+        w.begin_message();
+        w.key("a");
+        w.write_i32(-1);
+        w.key("b");
+        w.begin_message();
+        w.key("c");
+        w.write_bytes(b"x\"\xFF");
+        w.key("d");
+        w.write_f64(f64::NEG_INFINITY);
+        w.end_message();
+        w.key("e");
+        w.write_ident("FOO");
+        w.end_message();
+        let s = w.into_string();
+        assert_eq!("a: -1\nb {\n  c: \"x\\\"\\377\"\n  d: -inf\n}\ne: FOO\n", s);
+        let v = Value::parse(&s).unwrap();
+        let b = v.as_message().unwrap()[1].value.as_message().unwrap();
+        assert_eq!(b"x\"\xFF", b[0].value.as_bytes().unwrap());
+    }
+}
@@ -1,12 +1,15 @@
 //! A module to manage protobuf serialization
 
+#[cfg(feature = "std")]
 use std::io::Write;
+use alloc::vec::Vec;
+use core::slice;
 
-use errors::Result;
-use message::MessageWrite;
+use errors::{Result, ErrorKind};
+use message::{MessageWrite, UnknownFields};
 
-use byteorder::WriteBytesExt;
-use byteorder::LittleEndian as LE;
+const WIRE_TYPE_START_GROUP: u32 = 3;
+const WIRE_TYPE_END_GROUP: u32 = 4;
 
 /// A struct to write protobuf messages
 ///
@@ -15,18 +18,17 @@ use byteorder::LittleEndian as LE;
 /// ```rust
 /// // an automatically generated module which is in a separate file in general
 /// mod foo_bar {
-///     # use quick_protobuf::{MessageWrite, Writer, Result};
+///     # use quick_protobuf::{MessageWrite, Writer, WriterBackend, Result};
 ///     # use std::borrow::Cow;
-///     # use std::io::Write;
 ///     pub struct Foo<'a> { pub name: Option<Cow<'a, str>>, }
 ///     pub struct Bar { pub id: Option<u32> }
 ///     pub struct FooBar<'a> { pub foos: Vec<Foo<'a>>, pub bars: Vec<Bar>, }
 ///     impl<'a> MessageWrite for FooBar<'a> {
 ///         // implements
 ///         // fn get_size(&self) -> usize { ... }
-///         // fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> { ... }
+///         // fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> { ... }
 ///         # fn get_size(&self) -> usize { 0 }
-///         # fn write_message<W: Write>(&self, _: &mut Writer<W>) -> Result<()> { Ok(()) }
+///         # fn write_message<W: WriterBackend>(&self, _: &mut Writer<W>) -> Result<()> { Ok(()) }
 ///     }
 /// }
 ///
@@ -54,22 +56,37 @@ use byteorder::LittleEndian as LE;
 /// ```
 pub struct Writer<W> {
     inner: W,
+    /// sizes of the nested messages of the messages being written, see `sizeof_message_cached`
+    sizes: Vec<usize>,
+    /// index of the size of the next nested message to write
+    next_size: usize,
+    /// end of the sizes recorded for the message being written
+    end_size: usize,
 }
 
-impl<W: Write> Writer<W> {
+impl<W: WriterBackend> Writer<W> {
 
     /// Creates a new `ProtobufWriter`
     pub fn new(w: W) -> Writer<W> {
-        Writer { inner: w }
+        Writer { inner: w, sizes: Vec::new(), next_size: 0, end_size: 0 }
+    }
+
+    /// Gets back the inner writer
+    pub fn into_inner(self) -> W {
+        self.inner
     }
 
     /// Writes a `varint` (compacted `u64`)
     pub fn write_varint(&mut self, mut v: u64) -> Result<()> {
+        let mut buf = [0; 10];
+        let mut len = 0;
         while v > 0x7F {
-            self.inner.write_u8(((v as u8) & 0x7F) | 0x80)?;
+            buf[len] = ((v as u8) & 0x7F) | 0x80;
             v >>= 7;
+            len += 1;
         }
-        self.inner.write_u8(v as u8).map_err(|e| e.into())
+        buf[len] = v as u8;
+        self.inner.pb_write_all(&buf[..len + 1])
     }
 
     /// Writes a tag, which represents both the field number and the wire type
@@ -109,32 +126,32 @@ impl<W: Write> Writer<W> {
 
     /// Writes a `fixed64` which is little endian coded `u64`
     pub fn write_fixed64(&mut self, v: u64) -> Result<()> {
-        self.inner.write_u64::<LE>(v).map_err(|e| e.into())
+        self.inner.pb_write_all(&v.to_le_bytes())
     }
 
     /// Writes a `fixed32` which is little endian coded `u32`
     pub fn write_fixed32(&mut self, v: u32) -> Result<()> {
-        self.inner.write_u32::<LE>(v).map_err(|e| e.into())
+        self.inner.pb_write_all(&v.to_le_bytes())
     }
 
     /// Writes a `sfixed64` which is little endian coded `i64`
     pub fn write_sfixed64(&mut self, v: i64) -> Result<()> {
-        self.inner.write_i64::<LE>(v).map_err(|e| e.into())
+        self.inner.pb_write_all(&v.to_le_bytes())
     }
 
     /// Writes a `sfixed32` which is little endian coded `i32`
     pub fn write_sfixed32(&mut self, v: i32) -> Result<()> {
-        self.inner.write_i32::<LE>(v).map_err(|e| e.into())
+        self.inner.pb_write_all(&v.to_le_bytes())
     }
 
     /// Writes a `float`
     pub fn write_float(&mut self, v: f32) -> Result<()> {
-        self.inner.write_f32::<LE>(v).map_err(|e| e.into())
+        self.inner.pb_write_all(&v.to_le_bytes())
     }
 
     /// Writes a `double`
     pub fn write_double(&mut self, v: f64) -> Result<()> {
-        self.inner.write_f64::<LE>(v).map_err(|e| e.into())
+        self.inner.pb_write_all(&v.to_le_bytes())
     }
 
     /// Writes a `bool` 1 = true, 0 = false
@@ -150,7 +167,7 @@ impl<W: Write> Writer<W> {
     /// Writes `bytes`: length first then the chunk of data
     pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
         self.write_varint(bytes.len() as u64)?;
-        self.inner.write_all(bytes).map_err(|e| e.into())
+        self.inner.pb_write_all(bytes)
     }
 
     /// Writes `string`: length first then the chunk of data
@@ -181,15 +198,67 @@ impl<W: Write> Writer<W> {
     /// all data at once
     pub fn write_packed_fixed_size<M>(&mut self, v: &[M], item_size: usize) -> Result<()> {
         let len = v.len() * item_size;
-        let bytes = unsafe { ::std::slice::from_raw_parts(v as *const [M] as *const M as *const u8, len) };
+        let bytes = unsafe { slice::from_raw_parts(v as *const [M] as *const M as *const u8, len) };
         self.write_bytes(bytes)
     }
 
     /// Writes a message which implements `MessageWrite`
+    ///
+    /// The message is prefixed with its varint encoded length. The sizes of all its nested
+    /// messages are computed at once (`MessageWrite::get_size_cached`), then reused when they
+    /// are written in turn.
+    ///
+    /// A message whose size was not recorded along with its parent's (the parent keeps the
+    /// default `get_size_cached`) is sized when it is written, like the outermost message.
     pub fn write_message<M: MessageWrite>(&mut self, m: &M) -> Result<()> {
-        let len = m.get_size();
-        self.write_varint(len as u64)?;
-        m.write_message(self)
+        let (next_size, end_size) = (self.next_size, self.end_size);
+        if next_size < end_size {
+            // nested message, already sized along with its parent
+            let (len, end) = (self.sizes[next_size], self.sizes[next_size + 1]);
+            self.next_size += 2;
+            self.end_size = end;
+            let res = self.write_varint(len as u64).and_then(|_| m.write_message(self));
+            self.next_size = end;
+            self.end_size = end_size;
+            return res;
+        }
+
+        let start = self.sizes.len();
+        let len = m.get_size_cached(&mut self.sizes);
+        self.next_size = start;
+        self.end_size = self.sizes.len();
+        let res = self.write_varint(len as u64).and_then(|_| m.write_message(self));
+        // do not leave sizes behind, even if writing failed half way
+        self.sizes.truncate(start);
+        self.next_size = next_size;
+        self.end_size = end_size;
+        res
+    }
+
+    /// Gets the size of the next nested message to write, `m`, without its length
+    ///
+    /// The size is the one computed along with the outermost message being written, if any, so
+    /// it is not computed again.
+    pub fn message_size<M: MessageWrite>(&self, m: &M) -> usize {
+        if self.next_size < self.end_size {
+            self.sizes[self.next_size]
+        } else {
+            m.get_size()
+        }
+    }
+
+    /// Appends a sequence of length delimited messages
+    ///
+    /// This is the framing used by Java `writeDelimitedTo` / C++ `SerializeDelimitedToOstream`,
+    /// messages can be read back with `BytesReader::messages` or `StreamReader::read`
+    pub fn write_messages<'a, M, I>(&mut self, messages: I) -> Result<()>
+        where M: MessageWrite + 'a,
+              I: IntoIterator<Item = &'a M>,
+    {
+        for m in messages {
+            self.write_message(m)?;
+        }
+        Ok(())
     }
 
     /// Writes tag then `int32`
@@ -231,37 +300,37 @@ impl<W: Write> Writer<W> {
     /// Writes tag then `fixed64`
     pub fn write_fixed64_with_tag(&mut self, tag: u32, v: u64) -> Result<()> {
         self.write_tag(tag)?;
-        self.inner.write_u64::<LE>(v).map_err(|e| e.into())
+        self.inner.pb_write_all(&v.to_le_bytes())
     }
 
     /// Writes tag then `fixed32`
     pub fn write_fixed32_with_tag(&mut self, tag: u32, v: u32) -> Result<()> {
         self.write_tag(tag)?;
-        self.inner.write_u32::<LE>(v).map_err(|e| e.into())
+        self.inner.pb_write_all(&v.to_le_bytes())
     }
 
     /// Writes tag then `sfixed64`
     pub fn write_sfixed64_with_tag(&mut self, tag: u32, v: i64) -> Result<()> {
         self.write_tag(tag)?;
-        self.inner.write_i64::<LE>(v).map_err(|e| e.into())
+        self.inner.pb_write_all(&v.to_le_bytes())
     }
 
     /// Writes tag then `sfixed32`
     pub fn write_sfixed32_with_tag(&mut self, tag: u32, v: i32) -> Result<()> {
         self.write_tag(tag)?;
-        self.inner.write_i32::<LE>(v).map_err(|e| e.into())
+        self.inner.pb_write_all(&v.to_le_bytes())
     }
 
     /// Writes tag then `float`
     pub fn write_float_with_tag(&mut self, tag: u32, v: f32) -> Result<()> {
         self.write_tag(tag)?;
-        self.inner.write_f32::<LE>(v).map_err(|e| e.into())
+        self.inner.pb_write_all(&v.to_le_bytes())
     }
 
     /// Writes tag then `double`
     pub fn write_double_with_tag(&mut self, tag: u32, v: f64) -> Result<()> {
         self.write_tag(tag)?;
-        self.inner.write_f64::<LE>(v).map_err(|e| e.into())
+        self.inner.pb_write_all(&v.to_le_bytes())
     }
 
     /// Writes tag then `bool`
@@ -274,7 +343,7 @@ impl<W: Write> Writer<W> {
     pub fn write_bytes_with_tag(&mut self, tag: u32, bytes: &[u8]) -> Result<()> {
         self.write_tag(tag)?;
         self.write_varint(bytes.len() as u64)?;
-        self.inner.write_all(bytes).map_err(|e| e.into())
+        self.inner.pb_write_all(bytes)
     }
 
     /// Writes tag then `string`
@@ -319,19 +388,160 @@ impl<W: Write> Writer<W> {
         }
         self.write_tag(tag)?;
         let len = v.len() * item_size;
-        let bytes = unsafe { ::std::slice::from_raw_parts(v as *const [M] as *const M as *const u8, len) };
+        let bytes = unsafe { slice::from_raw_parts(v as *const [M] as *const M as *const u8, len) };
         self.write_bytes(bytes)
     }
 
+    /// Writes a start group tag (deprecated proto2 feature)
+    pub fn write_start_group(&mut self, field_number: u32) -> Result<()> {
+        self.write_tag(field_number << 3 | WIRE_TYPE_START_GROUP)
+    }
+
+    /// Writes an end group tag (deprecated proto2 feature)
+    pub fn write_end_group(&mut self, field_number: u32) -> Result<()> {
+        self.write_tag(field_number << 3 | WIRE_TYPE_END_GROUP)
+    }
+
+    /// Writes a group: start group tag, then message fields, then end group tag
+    ///
+    /// `tag` is the start group tag. Contrary to messages, groups are not length delimited.
+    pub fn write_group_with_tag<M: MessageWrite>(&mut self, tag: u32, m: &M) -> Result<()> {
+        self.write_start_group(tag >> 3)?;
+        m.write_message(self)?;
+        self.write_end_group(tag >> 3)
+    }
+
     /// Writes tag then message
     pub fn write_message_with_tag<M: MessageWrite>(&mut self, tag: u32, m: &M) -> Result<()> {
         self.write_tag(tag)?;
         self.write_message(m)
     }
 
+    /// Writes a map entry: length first, then the key and the value fields
+    ///
+    /// `size` is the size of both key and value fields, tags included. The map field tag itself
+    /// must have been written already.
+    pub fn write_map<FK, FV>(&mut self,
+                             size: usize,
+                             tag_key: u32,
+                             mut write_key: FK,
+                             tag_val: u32,
+                             mut write_val: FV) -> Result<()>
+        where FK: FnMut(&mut Self) -> Result<()>,
+              FV: FnMut(&mut Self) -> Result<()>,
+    {
+        self.write_varint(size as u64)?;
+        self.write_tag(tag_key)?;
+        write_key(self)?;
+        self.write_tag(tag_val)?;
+        write_val(self)
+    }
+
+    /// Writes unknown fields back, verbatim
+    pub fn write_unknown_fields(&mut self, fields: &UnknownFields) -> Result<()> {
+        for &(tag, ref data) in fields.iter() {
+            self.write_tag(tag)?;
+            self.inner.pb_write_all(data)?;
+        }
+        Ok(())
+    }
+
     /// Writes tag then enum
     pub fn write_enum_with_tag(&mut self, tag: u32, v: i32) -> Result<()> {
         self.write_tag(tag)?;
         self.write_int32(v)
     }
 }
+
+/// Where a `Writer` writes the encoded bytes
+///
+/// It is implemented for all `io::Write` types and for `BytesWriter`, so generated code (which is
+/// generic over the backend) can write into either. Without the `std` feature, it is implemented
+/// for `BytesWriter` and `Vec<u8>` only.
+pub trait WriterBackend {
+
+    /// Writes all the bytes of `buf`
+    fn pb_write_all(&mut self, buf: &[u8]) -> Result<()>;
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriterBackend for W {
+    fn pb_write_all(&mut self, buf: &[u8]) -> Result<()> {
+        self.write_all(buf).map_err(|e| e.into())
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl WriterBackend for Vec<u8> {
+    fn pb_write_all(&mut self, buf: &[u8]) -> Result<()> {
+        self.extend_from_slice(buf);
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl WriterBackend for &mut Vec<u8> {
+    fn pb_write_all(&mut self, buf: &[u8]) -> Result<()> {
+        self.extend_from_slice(buf);
+        Ok(())
+    }
+}
+
+/// A writer backend serializing into a preallocated slice, without any allocation
+///
+/// Writing past the end of the slice fails with `ErrorKind::BufferOverflow`, the slice is
+/// typically sized with `MessageWrite::get_size`.
+///
+/// # Examples
+///
+/// ```rust
+/// use quick_protobuf::{BytesWriter, Writer};
+///
+/// let mut buf = [0; 3];
+/// {
+///     let mut writer = Writer::new(BytesWriter::new(&mut buf));
+///     writer.write_uint32_with_tag(8, 150).unwrap();
+///     assert_eq!(3, writer.into_inner().position());
+/// }
+/// assert_eq!([0x08, 0x96, 0x01], buf);
+/// ```
+#[derive(Debug)]
+pub struct BytesWriter<'a> {
+    buf: &'a mut [u8],
+    cursor: usize,
+}
+
+impl<'a> BytesWriter<'a> {
+
+    /// Creates a new `BytesWriter`, writing from the start of `buf`
+    pub fn new(buf: &'a mut [u8]) -> BytesWriter<'a> {
+        BytesWriter { buf, cursor: 0 }
+    }
+
+    /// Gets the number of bytes written so far
+    pub fn position(&self) -> usize {
+        self.cursor
+    }
+}
+
+impl<'a> WriterBackend for BytesWriter<'a> {
+    #[inline]
+    fn pb_write_all(&mut self, buf: &[u8]) -> Result<()> {
+        let end = self.cursor + buf.len();
+        if end > self.buf.len() {
+            return Err(ErrorKind::BufferOverflow(end, self.buf.len()).into());
+        }
+        self.buf[self.cursor..end].copy_from_slice(buf);
+        self.cursor = end;
+        Ok(())
+    }
+}
+
+/// Serializes a message into a slice, without its length, returning the number of bytes written
+///
+/// This is the counterpart of `deserialize_from_slice`.
+pub fn serialize_into_slice<M: MessageWrite>(message: &M, out: &mut [u8]) -> Result<usize> {
+    let mut writer = Writer::new(BytesWriter::new(out));
+    message.write_message(&mut writer)?;
+    Ok(writer.into_inner().position())
+}
@@ -0,0 +1,3 @@
+cd ../../codegen
+cargo run ../tests/fixtures/write_read.proto
+cd ../tests/fixtures
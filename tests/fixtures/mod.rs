@@ -0,0 +1,7 @@
+//! Modules generated by pb-rs from the .proto files of this directory, see `generate_module.sh`
+
+// generated modules are checked by building them, not by linting them
+#![allow(unused_imports)]
+#![allow(clippy::all)]
+
+pub mod write_read;
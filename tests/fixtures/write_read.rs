@@ -0,0 +1,212 @@
+//! Automatically generated rust module for 'write_read.proto' file
+
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use quick_protobuf::{MessageRead, MessageWrite, BytesReader, Writer, WriterBackend, Result};
+use quick_protobuf::sizeofs::*;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TestEnum {
+    FIRST = 1,
+    SECOND = 2,
+}
+
+impl Default for TestEnum {
+    fn default() -> Self {
+        TestEnum::FIRST
+    }
+}
+
+impl From<i32> for TestEnum {
+    fn from(i: i32) -> Self {
+        match i {
+            1 => TestEnum::FIRST,
+            2 => TestEnum::SECOND,
+            _ => Self::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct TestMessage {
+    pub id: Option<u32>,
+    pub val: Vec<i64>,
+}
+
+impl<'a> MessageRead<'a> for TestMessage {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.id = Some(r.read_uint32(bytes).map_err(|e| e.in_field("TestMessage", "id"))?),
+                Ok(16) => msg.val.push(r.read_sint64(bytes).map_err(|e| e.in_repeated_field("TestMessage", "val", msg.val.len()))?),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("TestMessage"))?; }
+                Err(e) => return Err(e.in_message("TestMessage")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for TestMessage {
+    fn get_size(&self) -> usize {
+        self.id.as_ref().map_or(0, |m| 1 + sizeof_uint32(*m))
+        + self.val.iter().map(|s| 1 + sizeof_sint64(*s)).sum::<usize>()
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.id { r.write_uint32_with_tag(8, *s)?; }
+        for s in &self.val { r.write_sint64_with_tag(16, *s)? }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct TestMessageBorrow<'a> {
+    pub id: Option<u32>,
+    pub val: Vec<Cow<'a, str>>,
+}
+
+impl<'a> MessageRead<'a> for TestMessageBorrow<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.id = Some(r.read_uint32(bytes).map_err(|e| e.in_field("TestMessageBorrow", "id"))?),
+                Ok(18) => msg.val.push(Cow::Borrowed(r.read_string(bytes).map_err(|e| e.in_repeated_field("TestMessageBorrow", "val", msg.val.len()))?)),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("TestMessageBorrow"))?; }
+                Err(e) => return Err(e.in_message("TestMessageBorrow")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for TestMessageBorrow<'a> {
+    fn get_size(&self) -> usize {
+        self.id.as_ref().map_or(0, |m| 1 + sizeof_uint32(*m))
+        + self.val.iter().map(|s| 1 + sizeof_var_length(s.len())).sum::<usize>()
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.id { r.write_uint32_with_tag(8, *s)?; }
+        for s in &self.val { r.write_string_with_tag(18, s)? }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct TestPacked {
+    pub val: Vec<u32>,
+}
+
+impl<'a> MessageRead<'a> for TestPacked {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => msg.val = r.read_packed(bytes, |r, bytes| r.read_uint32(bytes)).map_err(|e| e.in_field("TestPacked", "val"))?,
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("TestPacked"))?; }
+                Err(e) => return Err(e.in_message("TestPacked")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for TestPacked {
+    fn get_size(&self) -> usize {
+        if self.val.is_empty() { 0 } else { 1 + sizeof_var_length(self.val.iter().map(|s| sizeof_uint32(*s)).sum::<usize>()) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        r.write_packed_repeated_field_with_tag(10, &self.val, |r, m| r.write_uint32(*m), &|m| sizeof_uint32(*m))?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct TestAll<'a> {
+    pub message: Option<TestMessage>,
+    pub messages: Vec<TestMessageBorrow<'a>>,
+    pub f_fixed64: Option<u64>,
+    pub f_fixed32: Option<u32>,
+    pub f_double: Option<f64>,
+    pub f_float: Option<f32>,
+    pub f_bytes: Option<Cow<'a, [u8]>>,
+    pub f_enum: Option<TestEnum>,
+    pub packed: Option<TestPacked>,
+    pub entries: HashMap<Cow<'a, str>, TestMessage>,
+}
+
+impl<'a> MessageRead<'a> for TestAll<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => msg.message = Some(r.read_message(bytes, TestMessage::from_reader).map_err(|e| e.in_field("TestAll", "message"))?),
+                Ok(18) => msg.messages.push(r.read_message(bytes, TestMessageBorrow::from_reader).map_err(|e| e.in_repeated_field("TestAll", "messages", msg.messages.len()))?),
+                Ok(25) => msg.f_fixed64 = Some(r.read_fixed64(bytes).map_err(|e| e.in_field("TestAll", "f_fixed64"))?),
+                Ok(37) => msg.f_fixed32 = Some(r.read_fixed32(bytes).map_err(|e| e.in_field("TestAll", "f_fixed32"))?),
+                Ok(41) => msg.f_double = Some(r.read_double(bytes).map_err(|e| e.in_field("TestAll", "f_double"))?),
+                Ok(53) => msg.f_float = Some(r.read_float(bytes).map_err(|e| e.in_field("TestAll", "f_float"))?),
+                Ok(58) => msg.f_bytes = Some(Cow::Borrowed(r.read_bytes(bytes).map_err(|e| e.in_field("TestAll", "f_bytes"))?)),
+                Ok(64) => msg.f_enum = Some(r.read_enum(bytes).map_err(|e| e.in_field("TestAll", "f_enum"))?),
+                Ok(74) => msg.packed = Some(r.read_message(bytes, TestPacked::from_reader).map_err(|e| e.in_field("TestAll", "packed"))?),
+                Ok(82) => {
+                    let (key, value) = r.read_map(bytes, |r, bytes| r.read_string(bytes).map(Cow::Borrowed), |r, bytes| r.read_message(bytes, TestMessage::from_reader)).map_err(|e| e.in_field("TestAll", "entries"))?;
+                    msg.entries.insert(key, value);
+                }
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("TestAll"))?; }
+                Err(e) => return Err(e.in_message("TestAll")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for TestAll<'a> {
+    fn get_size(&self) -> usize {
+        self.message.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.get_size()))
+        + self.messages.iter().map(|s| 1 + sizeof_var_length(s.get_size())).sum::<usize>()
+        + self.f_fixed64.as_ref().map_or(0, |_| 1 + 8)
+        + self.f_fixed32.as_ref().map_or(0, |_| 1 + 4)
+        + self.f_double.as_ref().map_or(0, |_| 1 + 8)
+        + self.f_float.as_ref().map_or(0, |_| 1 + 4)
+        + self.f_bytes.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + self.f_enum.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+        + self.packed.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.get_size()))
+        + self.entries.iter().map(|(k, v)| 1 + sizeof_map_entry(1 + sizeof_var_length(k.len()), 1 + sizeof_var_length(v.get_size()))).sum::<usize>()
+    }
+
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        self.message.as_ref().map_or(0, |m| 1 + sizeof_message_cached(m, sizes))
+        + self.messages.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+        + self.f_fixed64.as_ref().map_or(0, |_| 1 + 8)
+        + self.f_fixed32.as_ref().map_or(0, |_| 1 + 4)
+        + self.f_double.as_ref().map_or(0, |_| 1 + 8)
+        + self.f_float.as_ref().map_or(0, |_| 1 + 4)
+        + self.f_bytes.as_ref().map_or(0, |m| 1 + sizeof_var_length(m.len()))
+        + self.f_enum.as_ref().map_or(0, |m| 1 + sizeof_enum(*m as i32))
+        + self.packed.as_ref().map_or(0, |m| 1 + sizeof_message_cached(m, sizes))
+        + self.entries.iter().map(|(k, v)| 1 + sizeof_map_entry(1 + sizeof_var_length(k.len()), 1 + sizeof_message_cached(v, sizes))).sum::<usize>()
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.message { r.write_message_with_tag(10, s)?; }
+        for s in &self.messages { r.write_message_with_tag(18, s)? }
+        if let Some(ref s) = self.f_fixed64 { r.write_fixed64_with_tag(25, *s)?; }
+        if let Some(ref s) = self.f_fixed32 { r.write_fixed32_with_tag(37, *s)?; }
+        if let Some(ref s) = self.f_double { r.write_double_with_tag(41, *s)?; }
+        if let Some(ref s) = self.f_float { r.write_float_with_tag(53, *s)?; }
+        if let Some(ref s) = self.f_bytes { r.write_bytes_with_tag(58, s)?; }
+        if let Some(ref s) = self.f_enum { r.write_enum_with_tag(64, *s as i32)?; }
+        if let Some(ref s) = self.packed { r.write_message_with_tag(74, s)?; }
+        for (k, v) in self.entries.iter() { r.write_tag(82)?; r.write_map(1 + sizeof_var_length(k.len()) + 1 + sizeof_var_length(r.message_size(v)), 10, |r| r.write_string(k), 18, |r| r.write_message(v))?; }
+        Ok(())
+    }
+}
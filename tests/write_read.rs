@@ -1,7 +1,14 @@
 extern crate quick_protobuf;
 
-use std::io::{Write};
-use quick_protobuf::{BytesReader, Writer, MessageWrite, Result};
+mod fixtures;
+
+use std::borrow::Cow;
+use std::cell::Cell;
+use quick_protobuf::{BytesReader, Reader, StreamReader, Writer, WriterBackend, BytesWriter, MessageRead, MessageWrite, Result};
+use quick_protobuf::{ReaderLimits, DEFAULT_MAX_DEPTH, DEFAULT_STREAM_MAX_MESSAGE_SIZE};
+use quick_protobuf::errors::ErrorKind;
+use quick_protobuf::{serialize_into_slice, deserialize_from_slice};
+use quick_protobuf::UnknownFields;
 use quick_protobuf::sizeofs::*;
 
 macro_rules! write_read_primitive {
@@ -15,7 +22,7 @@ fn $name(){
         let mut w = Writer::new(&mut buf);
         w.$write(v).unwrap();
     }
-    let mut r = BytesReader::from_bytes(&*buf);
+    let mut r = BytesReader::from_bytes(&buf);
     assert_eq!(v, r.$read(&buf).unwrap());
 }
     );
@@ -43,7 +50,7 @@ fn wr_bytes(){
         let mut w = Writer::new(&mut buf);
         w.write_bytes(v).unwrap();
     }
-    let mut r = BytesReader::from_bytes(&*buf);
+    let mut r = BytesReader::from_bytes(&buf);
     assert_eq!(v, r.read_bytes(&buf).unwrap());
 }
 
@@ -95,9 +102,8 @@ struct TestMessage {
     val: Vec<i64>,
 }
 
-// impl MessageRead for TestMessage {
-impl TestMessage {
-    fn from_reader(r: &mut BytesReader, bytes: &[u8]) -> Result<TestMessage> {
+impl<'a> MessageRead<'a> for TestMessage {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<TestMessage> {
         let mut msg = TestMessage::default();
         while !r.is_eof() {
             match r.next_tag(bytes) {
@@ -117,7 +123,7 @@ impl MessageWrite for TestMessage {
         + self.val.iter().map(|m| 1 + sizeof_sint64(*m)).sum::<usize>()
     }
 
-    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
         if let Some(ref s) = self.id { r.write_uint32_with_tag(10, *s)?; }
         for s in &self.val { r.write_sint64_with_tag(18, *s)?; }
         Ok(())
@@ -143,21 +149,56 @@ fn wr_message(){
     assert_eq!(buf.len(), sizeof_varint(8) + v.get_size());
 }
 
+#[test]
+fn wr_bytes_writer(){
+    let v = TestMessage {
+        id: Some(63),
+        val: vec![53, 5, 76, 743, 23, 753],
+    };
+    let mut buf = Vec::new();
+    {
+        let mut w = Writer::new(&mut buf);
+        w.write_message(&v).unwrap();
+    }
+
+    let mut out = vec![0; sizeof_var_length(v.get_size())];
+    {
+        let mut w = Writer::new(BytesWriter::new(&mut out));
+        w.write_message(&v).unwrap();
+        assert_eq!(buf.len(), w.into_inner().position());
+    }
+    assert_eq!(buf, out);
+
+    let mut out = vec![0; v.get_size()];
+    assert_eq!(out.len(), serialize_into_slice(&v, &mut out).unwrap());
+    assert_eq!(v, deserialize_from_slice(&out).unwrap());
+
+    // too small
+    let mut out = vec![0; v.get_size() - 1];
+    match *serialize_into_slice(&v, &mut out).unwrap_err().kind() {
+        ErrorKind::BufferOverflow(needed, len) => {
+            assert_eq!(out.len(), len);
+            assert!(needed > len);
+        }
+        ref e => panic!("Expecting a buffer overflow, got {:?}", e),
+    }
+}
+
 #[derive(PartialEq, Eq, Debug, Clone, Default)]
 struct TestMessageBorrow<'a> {
     id: Option<u32>,
     val: Vec<&'a str>,
 }
 
-impl<'a> TestMessageBorrow<'a> {
+impl<'a> MessageRead<'a> for TestMessageBorrow<'a> {
     fn from_reader(r: &mut BytesReader, bytes: &'a[u8]) -> Result<TestMessageBorrow<'a>> {
         let mut msg = TestMessageBorrow::default();
         while !r.is_eof() {
             match r.next_tag(bytes) {
-                Ok(10) => msg.id = Some(r.read_uint32(bytes)?),
-                Ok(18) => msg.val.push(r.read_string(bytes)?),
-                Ok(t) => { r.read_unknown(bytes, t)?; }
-                Err(e) => return Err(e),
+                Ok(10) => msg.id = Some(r.read_uint32(bytes).map_err(|e| e.in_field("TestMessageBorrow", "id"))?),
+                Ok(18) => msg.val.push(r.read_string(bytes).map_err(|e| e.in_repeated_field("TestMessageBorrow", "val", msg.val.len()))?),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("TestMessageBorrow"))?; }
+                Err(e) => return Err(e.in_message("TestMessageBorrow")),
             }
             println!("{:?}", msg);
         }
@@ -171,9 +212,9 @@ impl<'a> MessageWrite for TestMessageBorrow<'a> {
         + self.val.iter().map(|m| 1 + sizeof_var_length(m.len())).sum::<usize>()
     }
 
-    fn write_message<W: Write>(&self, r: &mut Writer<W>) -> Result<()> {
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
         if let Some(ref s) = self.id { r.write_uint32_with_tag(10, *s)?; }
-        for s in &self.val { r.write_string_with_tag(18, *s)?; }
+        for s in &self.val { r.write_string_with_tag(18, s)?; }
         Ok(())
     }
 }
@@ -210,3 +251,938 @@ fn wr_packed_uint32(){
     let mut r = BytesReader::from_bytes(&buf);
     assert_eq!(v, r.read_packed(&buf, |r, b| r.read_uint32(b)).unwrap());
 }
+
+fn assert_truncations_fail<F>(buf: &[u8], mut read: F)
+    where F: FnMut(&mut BytesReader, &[u8]) -> Result<()>,
+{
+    for i in 0..buf.len() {
+        let truncated = &buf[..i];
+        let mut r = BytesReader::from_bytes(truncated);
+        assert!(read(&mut r, truncated).is_err(), "truncation at {} should fail", i);
+    }
+}
+
+/// Reads every prefix of the body of message `m` with `from_reader`: only the prefixes ending
+/// between two fields are valid messages
+fn assert_body_truncations_fail<M, F>(m: &M, mut from_reader: F)
+    where M: MessageWrite,
+          F: FnMut(&mut BytesReader, &[u8]) -> Result<()>,
+{
+    let mut body = Vec::new();
+    m.write_message(&mut Writer::new(&mut body)).unwrap();
+    let mut ends = vec![0];
+    let mut r = BytesReader::from_bytes(&body);
+    while !r.is_eof() {
+        let tag = r.next_tag(&body).unwrap();
+        r.read_unknown(&body, tag).unwrap();
+        ends.push(body.len() - r.len());
+    }
+    for i in 0..body.len() + 1 {
+        let truncated = &body[..i];
+        let res = from_reader(&mut BytesReader::from_bytes(truncated), truncated);
+        if ends.contains(&i) {
+            assert!(res.is_ok(), "truncation at {} should be a valid message: {:?}", i, res);
+        } else {
+            assert!(res.is_err(), "truncation at {} should fail", i);
+        }
+    }
+}
+
+#[test]
+fn truncated_primitives(){
+    let mut buf = Vec::new();
+    {
+        let mut w = Writer::new(&mut buf);
+        w.write_uint64(u64::MAX).unwrap();
+        w.write_fixed64(54).unwrap();
+        w.write_fixed32(54).unwrap();
+        w.write_double(5.8).unwrap();
+        w.write_float(5.8).unwrap();
+        w.write_string("test_write_read").unwrap();
+        w.write_bytes(b"test_write_read").unwrap();
+    }
+    assert_truncations_fail(&buf, |r, b| {
+        r.read_uint64(b)?;
+        r.read_fixed64(b)?;
+        r.read_fixed32(b)?;
+        r.read_double(b)?;
+        r.read_float(b)?;
+        r.read_string(b)?;
+        r.read_bytes(b).map(|_| ())
+    });
+
+    let mut r = BytesReader::from_bytes(&buf);
+    assert!(r.read_uint64(&buf).is_ok());
+    assert!(r.read_fixed64(&buf).is_ok());
+}
+
+#[test]
+fn truncated_message(){
+    let v = fixtures::write_read::TestMessage {
+        id: Some(63),
+        val: vec![53, 5, 76, 743, 23, 753],
+    };
+    assert_body_truncations_fail(&v, |r, b| fixtures::write_read::TestMessage::from_reader(r, b).map(|_| ()));
+}
+
+#[test]
+fn truncated_message_borrow(){
+    let v = fixtures::write_read::TestMessageBorrow {
+        id: Some(63),
+        val: vec![Cow::Borrowed("ea"), Cow::Borrowed("jhaw"), Cow::Borrowed("bdk")],
+    };
+    assert_body_truncations_fail(&v, |r, b| fixtures::write_read::TestMessageBorrow::from_reader(r, b).map(|_| ()));
+}
+
+#[test]
+fn truncated_generated_message(){
+    use fixtures::write_read::{TestAll, TestEnum, TestMessage, TestMessageBorrow, TestPacked};
+
+    let mut v = TestAll {
+        message: Some(TestMessage { id: Some(1), val: vec![-1, 2] }),
+        messages: vec![
+            TestMessageBorrow { id: Some(2), val: vec![Cow::Borrowed("a"), Cow::Borrowed("bc")] },
+            TestMessageBorrow { id: None, val: vec![Cow::Borrowed("")] },
+        ],
+        f_fixed64: Some(54),
+        f_fixed32: Some(54),
+        f_double: Some(5.8),
+        f_float: Some(5.8),
+        f_bytes: Some(Cow::Borrowed(b"test_write_read")),
+        f_enum: Some(TestEnum::SECOND),
+        packed: Some(TestPacked { val: vec![43, 54, 6123] }),
+        ..TestAll::default()
+    };
+    v.entries.insert(Cow::Borrowed("key"), TestMessage { id: Some(3), val: vec![4] });
+    let mut buf = Vec::new();
+    {
+        let mut w = Writer::new(&mut buf);
+        w.write_message(&v).unwrap();
+    }
+    let mut r = BytesReader::from_bytes(&buf);
+    assert_eq!(v, r.read_message(&buf, TestAll::from_reader).unwrap());
+    assert_body_truncations_fail(&v, |r, b| TestAll::from_reader(r, b).map(|_| ()));
+}
+
+#[test]
+fn truncated_packed(){
+    let v = vec![43, 54, 64, 234, 6123, 643];
+    let mut buf = Vec::new();
+    {
+        let mut w = Writer::new(&mut buf);
+        w.write_packed_repeated_field(&v, |r, m| r.write_uint32(*m), &|m| sizeof_uint32(*m)).unwrap();
+    }
+    assert_truncations_fail(&buf, |r, b| r.read_packed(b, |r, b| r.read_uint32(b)).map(|_| ()));
+}
+
+#[test]
+fn truncated_unknown(){
+    // a varint, a fixed64, a length delimited and a fixed32 unknown field
+    let buf = [0x08, 0x96, 0x01,
+               0x11, 1, 2, 3, 4, 5, 6, 7, 8,
+               0x1a, 0x03, 1, 2, 3,
+               0x25, 1, 2, 3, 4];
+    assert_truncations_fail(&buf, |r, b| {
+        for _ in 0..4 {
+            let tag = r.next_tag(b)?;
+            r.read_unknown(b, tag)?;
+        }
+        Ok(())
+    });
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+struct TestOuter<'a> {
+    items: Vec<TestMessageBorrow<'a>>,
+}
+
+impl<'a> MessageRead<'a> for TestOuter<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a[u8]) -> Result<TestOuter<'a>> {
+        let mut msg = TestOuter::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => msg.items.push(r.read_message(bytes, TestMessageBorrow::from_reader).map_err(|e| e.in_repeated_field("TestOuter", "items", msg.items.len()))?),
+                Ok(t) => { r.read_unknown(bytes, t).map_err(|e| e.in_message("TestOuter"))?; }
+                Err(e) => return Err(e.in_message("TestOuter")),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+#[test]
+fn read_error_path(){
+    // items[1].val[1] is not valid utf8
+    let buf = [0x0a, 0x02, 0x08, 0x01,
+               0x0a, 0x08, 0x12, 0x01, b'a', 0x12, 0x03, b'b', 0xff, b'c'];
+    let err = deserialize_from_slice::<TestOuter>(&buf).unwrap_err();
+    match *err.kind() {
+        ErrorKind::StrUtf8(_) => (),
+        ref e => panic!("Expecting an utf8 error, got {:?}", e),
+    }
+    assert_eq!(Some(12), err.offset());
+    assert_eq!(Some("TestOuter"), err.message());
+    assert_eq!(Some("TestOuter.items[1].val[1]".to_string()), err.path());
+    assert!(err.to_string().starts_with("offset 12, field TestOuter.items[1].val[1]: "));
+
+    // truncated varint in items[0].id
+    let buf = [0x0a, 0x02, 0x0a, 0x81];
+    let err = deserialize_from_slice::<TestOuter>(&buf).unwrap_err();
+    assert_eq!("offset 4, field TestOuter.items[0].id: unexpected end of file", err.to_string());
+
+    // invalid wire type at the top level
+    let buf = [0x0f];
+    let err = deserialize_from_slice::<TestOuter>(&buf).unwrap_err();
+    assert_eq!("offset 1, message TestOuter: wire type must be less than 6, found 7", err.to_string());
+
+    // errors not coming from a reader have neither offset nor path
+    let err = quick_protobuf::errors::Error::from(ErrorKind::Varint);
+    assert_eq!(None, err.offset());
+    assert_eq!(None, err.path());
+    assert_eq!("cannot decode varint", err.to_string());
+}
+
+#[test]
+fn nested_length_past_parent_end(){
+    // the outer message is 3 bytes long but its string field claims 5 bytes
+    let buf = [0x03, 0x12, 0x05, b'a', b'b', b'c', b'd', b'e'];
+    let mut r = BytesReader::from_bytes(&buf);
+    assert!(r.read_message(&buf, TestMessageBorrow::from_reader).is_err());
+
+    // same for an unknown length delimited field
+    let buf = [0x03, 0x1a, 0x05, b'a', b'b', b'c', b'd', b'e'];
+    let mut r = BytesReader::from_bytes(&buf);
+    assert!(r.read_message(&buf, TestMessage::from_reader).is_err());
+}
+
+/// A `Read` yielding one byte at a time, to force `StreamReader` refills
+struct OneByteReader<'a>(&'a [u8]);
+
+impl<'a> ::std::io::Read for OneByteReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> ::std::io::Result<usize> {
+        if self.0.is_empty() || buf.is_empty() {
+            return Ok(0);
+        }
+        buf[0] = self.0[0];
+        self.0 = &self.0[1..];
+        Ok(1)
+    }
+}
+
+#[test]
+fn stream_reader(){
+    let messages = (0..50).map(|i| TestMessage {
+        id: Some(i),
+        val: (0..i as i64).collect(),
+    }).collect::<Vec<_>>();
+    let mut buf = Vec::new();
+    {
+        let mut w = Writer::new(&mut buf);
+        for m in &messages {
+            w.write_message(m).unwrap();
+        }
+    }
+
+    let mut r = StreamReader::with_capacity(OneByteReader(&buf), 4);
+    for m in &messages {
+        assert_eq!(Some(m.clone()), r.read(TestMessage::from_reader).unwrap());
+    }
+    assert_eq!(None, r.read(TestMessage::from_reader).unwrap());
+}
+
+#[test]
+fn stream_reader_borrow(){
+    let v = TestMessageBorrow {
+        id: Some(63),
+        val: vec!["ea", "jhaw", "bdk"],
+    };
+    let mut buf = Vec::new();
+    {
+        let mut w = Writer::new(&mut buf);
+        w.write_message(&v).unwrap();
+        w.write_message(&v).unwrap();
+    }
+
+    let mut r = StreamReader::new(&*buf);
+    assert_eq!(Some(v.clone()), r.read(TestMessageBorrow::from_reader).unwrap());
+    assert_eq!(Some(v.clone()), r.read(TestMessageBorrow::from_reader).unwrap());
+    assert_eq!(None, r.read(TestMessageBorrow::from_reader).unwrap());
+}
+
+#[test]
+fn stream_reader_truncated(){
+    let v = TestMessage {
+        id: Some(63),
+        val: vec![53, 5, 76, 743, 23, 753],
+    };
+    let mut buf = Vec::new();
+    {
+        let mut w = Writer::new(&mut buf);
+        w.write_message(&v).unwrap();
+    }
+    for i in 1..buf.len() {
+        let mut r = StreamReader::new(OneByteReader(&buf[..i]));
+        assert!(r.read(TestMessage::from_reader).is_err(), "truncation at {} should fail", i);
+    }
+}
+
+#[test]
+fn stream_reader_too_large(){
+    // a 5 bytes input announcing a 4GB message is rejected without buffering it
+    let buf = [0xff, 0xff, 0xff, 0xff, 0x0f];
+    let mut r = StreamReader::new(&buf[..]);
+    match r.read(TestMessage::from_reader).unwrap_err().into_kind() {
+        ErrorKind::MessageTooLarge(0xffff_ffff, DEFAULT_STREAM_MAX_MESSAGE_SIZE) => (),
+        e => panic!("Expecting MessageTooLarge, got {:?}", e),
+    }
+
+    let v = TestMessage {
+        id: Some(63),
+        val: vec![53, 5, 76, 743, 23, 753],
+    };
+    let mut buf = Vec::new();
+    {
+        let mut w = Writer::new(&mut buf);
+        w.write_message(&v).unwrap();
+    }
+    let limits = ReaderLimits { max_input_size: v.get_size(), ..ReaderLimits::default() };
+    let mut r = StreamReader::with_limits(&buf[..], limits);
+    assert_eq!(Some(v.clone()), r.read(TestMessage::from_reader).unwrap());
+    let limits = ReaderLimits { max_input_size: v.get_size() - 1, ..ReaderLimits::default() };
+    let mut r = StreamReader::with_limits(&buf[..], limits);
+    assert!(r.read(TestMessage::from_reader).is_err());
+}
+
+#[test]
+fn wr_delimited_messages(){
+    let messages = (0..10).map(|i| TestMessage {
+        id: Some(i),
+        val: (0..i as i64).collect(),
+    }).collect::<Vec<_>>();
+    let mut buf = Vec::new();
+    {
+        let mut w = Writer::new(&mut buf);
+        w.write_messages(&messages).unwrap();
+    }
+
+    let mut r = BytesReader::from_bytes(&buf);
+    let read = r.messages(&buf, TestMessage::from_reader).collect::<Result<Vec<_>>>().unwrap();
+    assert_eq!(messages, read);
+
+    let mut r = Reader::from_reader(&*buf, buf.len()).unwrap();
+    let read = r.messages(TestMessage::from_reader).collect::<Result<Vec<_>>>().unwrap();
+    assert_eq!(messages, read);
+}
+
+#[test]
+fn delimited_messages_truncated(){
+    let v = TestMessageBorrow {
+        id: Some(63),
+        val: vec!["ea", "jhaw", "bdk"],
+    };
+    let mut buf = Vec::new();
+    {
+        let mut w = Writer::new(&mut buf);
+        w.write_messages(&[v.clone(), v.clone()]).unwrap();
+    }
+    let truncated = &buf[..buf.len() - 1];
+    let mut r = BytesReader::from_bytes(truncated);
+    let mut messages = r.messages(truncated, TestMessageBorrow::from_reader);
+    assert_eq!(v, messages.next().unwrap().unwrap());
+    assert!(messages.next().unwrap().is_err());
+    assert!(messages.next().is_none());
+}
+
+#[test]
+fn read_message_helpers(){
+    let v = TestMessageBorrow {
+        id: Some(63),
+        val: vec!["ea", "jhaw", "bdk"],
+    };
+    let mut buf = Vec::new();
+    {
+        let mut w = Writer::new(&mut buf);
+        v.write_message(&mut w).unwrap();
+    }
+    assert_eq!(v, deserialize_from_slice(&buf).unwrap());
+
+    let mut r = Reader::from_reader(&*buf, buf.len()).unwrap();
+    assert_eq!(v, r.read_message::<TestMessageBorrow>().unwrap());
+
+    let mut buf = Vec::new();
+    {
+        let mut w = Writer::new(&mut buf);
+        w.write_message(&v).unwrap();
+    }
+    let mut r = StreamReader::new(&*buf);
+    assert_eq!(Some(v.clone()), r.read_message::<TestMessageBorrow>().unwrap());
+    assert_eq!(None, r.read_message::<TestMessageBorrow>().unwrap());
+}
+
+/// An older version of `TestMessageBorrow`, which doesn't know about `val`
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+struct TestMessageUnknown<'a> {
+    id: Option<u32>,
+    unknown_fields: UnknownFields<'a>,
+}
+
+impl<'a> MessageRead<'a> for TestMessageUnknown<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a[u8]) -> Result<TestMessageUnknown<'a>> {
+        let mut msg = TestMessageUnknown::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => msg.id = Some(r.read_uint32(bytes)?),
+                Ok(t) => msg.unknown_fields.push(t, Cow::Borrowed(r.read_unknown_field(bytes, t)?)),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for TestMessageUnknown<'a> {
+    fn get_size(&self) -> usize {
+        self.id.as_ref().map_or(0, |m| 1 + sizeof_uint32(*m))
+        + self.unknown_fields.get_size()
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.id { r.write_uint32_with_tag(10, *s)?; }
+        r.write_unknown_fields(&self.unknown_fields)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn wr_unknown_fields(){
+    let v = TestMessageBorrow {
+        id: Some(63),
+        val: vec!["ea", "jhaw", "bdk"],
+    };
+    let mut buf = Vec::new();
+    {
+        let mut w = Writer::new(&mut buf);
+        w.write_message(&v).unwrap();
+    }
+
+    let mut r = BytesReader::from_bytes(&buf);
+    let mut unknown = r.read_message(&buf, TestMessageUnknown::from_reader).unwrap();
+    assert_eq!(Some(63), unknown.id);
+    assert_eq!(3, unknown.unknown_fields.len());
+
+    // modify a known field then write back: unknown fields must be preserved
+    unknown.id = Some(64);
+    let mut out = Vec::new();
+    {
+        let mut w = Writer::new(&mut out);
+        w.write_message(&unknown).unwrap();
+    }
+    assert_eq!(buf.len(), out.len());
+    assert_eq!(out.len(), sizeof_varint(unknown.get_size() as u64) + unknown.get_size());
+
+    let mut r = BytesReader::from_bytes(&out);
+    let read = r.read_message(&out, TestMessageBorrow::from_reader).unwrap();
+    assert_eq!(TestMessageBorrow { id: Some(64), ..v }, read);
+}
+
+#[test]
+fn wr_group(){
+    // only `val` has a tag matching its wire type, which is needed to find the end of the group
+    let v = TestMessageBorrow {
+        id: None,
+        val: vec!["ea", "jhaw", "bdk"],
+    };
+    let mut buf = Vec::new();
+    {
+        let mut w = Writer::new(&mut buf);
+        w.write_group_with_tag(3 << 3 | 3, &v).unwrap();
+        w.write_uint32_with_tag(8, 42).unwrap();
+    }
+    assert_eq!(buf.len(), 2 + v.get_size() + 2);
+
+    let mut r = BytesReader::from_bytes(&buf);
+    let tag = r.next_tag(&buf).unwrap();
+    assert_eq!(v, r.read_group(&buf, tag, TestMessageBorrow::from_reader).unwrap());
+    assert_eq!(8, r.next_tag(&buf).unwrap());
+    assert_eq!(42, r.read_uint32(&buf).unwrap());
+    assert!(r.is_eof());
+}
+
+#[derive(Debug, Default)]
+struct TestTree {
+    id: u32,
+    children: Vec<TestTree>,
+    // number of times the size of the node has been computed
+    sized: Cell<usize>,
+}
+
+impl<'a> MessageRead<'a> for TestTree {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<TestTree> {
+        let mut msg = TestTree::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.id = r.read_uint32(bytes)?,
+                Ok(18) => msg.children.push(r.read_message(bytes, TestTree::from_reader)?),
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for TestTree {
+    fn get_size(&self) -> usize {
+        self.get_size_cached(&mut Vec::new())
+    }
+
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        self.sized.set(self.sized.get() + 1);
+        1 + sizeof_uint32(self.id)
+        + self.children.iter().map(|s| 1 + sizeof_message_cached(s, sizes)).sum::<usize>()
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        r.write_uint32_with_tag(8, self.id)?;
+        for s in &self.children { r.write_message_with_tag(18, s)?; }
+        Ok(())
+    }
+}
+
+impl TestTree {
+    fn new(id: u32, depth: usize) -> TestTree {
+        TestTree {
+            id,
+            children: if depth == 0 { vec![] } else { (0..2).map(|i| TestTree::new(id * 2 + i, depth - 1)).collect() },
+            sized: Cell::new(0),
+        }
+    }
+
+    fn ids(&self) -> Vec<(u32, usize)> {
+        let mut ids = vec![(self.id, self.sized.get())];
+        for c in &self.children {
+            ids.extend(c.ids());
+        }
+        ids
+    }
+}
+
+#[test]
+fn wr_nested_messages_sized_once(){
+    let v = TestTree::new(1, 6);
+    let mut buf = Vec::new();
+    {
+        let mut w = Writer::new(&mut buf);
+        w.write_message(&v).unwrap();
+        w.write_message(&v).unwrap();
+    }
+    // every node is sized once per `write_message`, whatever its depth
+    assert!(v.ids().iter().all(|&(_, sized)| sized == 2));
+    assert_eq!(buf.len(), 2 * sizeof_var_length(v.get_size()));
+
+    let mut r = BytesReader::from_bytes(&buf);
+    for _ in 0..2 {
+        let read = r.read_message(&buf, TestTree::from_reader).unwrap();
+        assert_eq!(v.ids().iter().map(|i| i.0).collect::<Vec<_>>(),
+                   read.ids().iter().map(|i| i.0).collect::<Vec<_>>());
+    }
+    assert!(r.is_eof());
+}
+
+// a message keeping the default `get_size_cached`, between messages overriding it
+#[derive(Debug, Default)]
+struct TestBranch {
+    trees: Vec<TestTree>,
+}
+
+impl<'a> MessageRead<'a> for TestBranch {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<TestBranch> {
+        let mut msg = TestBranch::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => msg.trees.push(r.read_message(bytes, TestTree::from_reader)?),
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for TestBranch {
+    fn get_size(&self) -> usize {
+        self.trees.iter().map(|t| 1 + sizeof_var_length(t.get_size())).sum()
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        for t in &self.trees { r.write_message_with_tag(10, t)?; }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct TestForest {
+    branches: Vec<TestBranch>,
+    tree: TestTree,
+}
+
+impl MessageWrite for TestForest {
+    fn get_size(&self) -> usize {
+        self.get_size_cached(&mut Vec::new())
+    }
+
+    fn get_size_cached(&self, sizes: &mut Vec<usize>) -> usize {
+        self.branches.iter().map(|b| 1 + sizeof_message_cached(b, sizes)).sum::<usize>()
+        + 1 + sizeof_message_cached(&self.tree, sizes)
+    }
+
+    fn write_message<W: WriterBackend>(&self, r: &mut Writer<W>) -> Result<()> {
+        for b in &self.branches { r.write_message_with_tag(10, b)?; }
+        r.write_message_with_tag(18, &self.tree)
+    }
+}
+
+#[test]
+fn wr_nested_messages_not_cached(){
+    let v = TestForest {
+        branches: vec![
+            TestBranch { trees: vec![TestTree::new(1, 2), TestTree::new(2, 1)] },
+            TestBranch::default(),
+            TestBranch { trees: vec![TestTree::new(3, 3)] },
+        ],
+        tree: TestTree::new(4, 2),
+    };
+    let mut buf = Vec::new();
+    Writer::new(&mut buf).write_message(&v).unwrap();
+    assert_eq!(buf.len(), sizeof_var_length(v.get_size()));
+
+    let ids = |t: &TestTree| t.ids().iter().map(|i| i.0).collect::<Vec<_>>();
+    let mut r = BytesReader::from_bytes(&buf);
+    let len = r.read_varint32(&buf).unwrap() as usize;
+    assert_eq!(v.get_size(), len);
+    let mut branches = Vec::new();
+    while !r.is_eof() {
+        match r.next_tag(&buf).unwrap() {
+            10 => branches.push(r.read_message(&buf, TestBranch::from_reader).unwrap()),
+            18 => assert_eq!(ids(&v.tree), ids(&r.read_message(&buf, TestTree::from_reader).unwrap())),
+            t => panic!("unexpected tag {}", t),
+        }
+    }
+    assert_eq!(v.branches.len(), branches.len());
+    for (b, read) in v.branches.iter().zip(&branches) {
+        assert_eq!(b.trees.iter().map(&ids).collect::<Vec<_>>(), read.trees.iter().map(&ids).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn skip_nested_groups(){
+    let mut buf = Vec::new();
+    {
+        let mut w = Writer::new(&mut buf);
+        w.write_start_group(5).unwrap();
+        w.write_uint32_with_tag(8, 1).unwrap();
+        w.write_start_group(6).unwrap();
+        w.write_string_with_tag(18, "nested").unwrap();
+        w.write_start_group(5).unwrap();
+        w.write_end_group(5).unwrap();
+        w.write_end_group(6).unwrap();
+        w.write_end_group(5).unwrap();
+        w.write_uint32_with_tag(8, 42).unwrap();
+    }
+
+    let mut r = BytesReader::from_bytes(&buf);
+    let tag = r.next_tag(&buf).unwrap();
+    r.read_unknown(&buf, tag).unwrap();
+    assert_eq!(8, r.next_tag(&buf).unwrap());
+    assert_eq!(42, r.read_uint32(&buf).unwrap());
+    assert!(r.is_eof());
+
+    // a group without its end tag, or with a mismatched one, cannot be skipped
+    assert_truncations_fail(&buf[..buf.len() - 2], |r, b| {
+        let tag = r.next_tag(b)?;
+        r.read_unknown(b, tag)
+    });
+    let buf = [5 << 3 | 3, 6 << 3 | 4];
+    let mut r = BytesReader::from_bytes(&buf);
+    let tag = r.next_tag(&buf).unwrap();
+    assert!(r.read_unknown(&buf, tag).is_err());
+}
+
+fn nested_tree(depth: usize) -> Vec<u8> {
+    let mut v = TestTree::default();
+    for id in 0..depth as u32 {
+        v = TestTree { id, children: vec![v], ..TestTree::default() };
+    }
+    let mut buf = Vec::new();
+    Writer::new(&mut buf).write_message(&v).unwrap();
+    buf
+}
+
+#[test]
+fn read_max_depth(){
+    // the outer message is nested once, then every child is nested once more
+    let buf = nested_tree(DEFAULT_MAX_DEPTH - 1);
+    let mut r = BytesReader::from_bytes(&buf);
+    assert!(r.read_message(&buf, TestTree::from_reader).is_ok());
+
+    let buf = nested_tree(DEFAULT_MAX_DEPTH);
+    let mut r = BytesReader::from_bytes(&buf);
+    match r.read_message(&buf, TestTree::from_reader).unwrap_err().into_kind() {
+        ErrorKind::RecursionLimitExceeded(DEFAULT_MAX_DEPTH) => (),
+        e => panic!("Expecting RecursionLimitExceeded, got {:?}", e),
+    }
+
+    let limits = ReaderLimits { max_depth: 3, ..ReaderLimits::default() };
+    let buf = nested_tree(2);
+    let mut r = BytesReader::with_limits(&buf, limits).unwrap();
+    assert!(r.read_message(&buf, TestTree::from_reader).is_ok());
+    let buf = nested_tree(3);
+    let mut r = BytesReader::with_limits(&buf, limits).unwrap();
+    assert!(r.read_message(&buf, TestTree::from_reader).is_err());
+}
+
+#[test]
+fn read_max_depth_raised(){
+    // legitimately deep input is read once the limit is raised
+    let buf = nested_tree(2 * DEFAULT_MAX_DEPTH);
+    let mut r = BytesReader::from_bytes(&buf);
+    assert!(r.read_message(&buf, TestTree::from_reader).is_err());
+
+    let limits = ReaderLimits { max_depth: 2 * DEFAULT_MAX_DEPTH + 1, ..ReaderLimits::default() };
+    let mut r = BytesReader::with_limits(&buf, limits).unwrap();
+    let v = r.read_message(&buf, TestTree::from_reader).unwrap();
+    assert_eq!(2 * DEFAULT_MAX_DEPTH, v.ids().len() - 1);
+    assert!(r.is_eof());
+}
+
+#[test]
+fn read_deeply_nested_input(){
+    // 100 000 nested messages (or groups) must fail cleanly instead of overflowing the stack
+    // built backwards, from the innermost message
+    let mut buf = vec![1, 8];
+    for _ in 0..100_000 {
+        let mut len = Vec::new();
+        Writer::new(&mut len).write_varint(buf.len() as u64).unwrap();
+        buf.extend(len.iter().rev());
+        buf.push(18);
+    }
+    buf.reverse();
+    let mut r = BytesReader::from_bytes(&buf);
+    assert!(TestTree::from_reader(&mut r, &buf).is_err());
+
+    let mut buf = Vec::new();
+    {
+        let mut w = Writer::new(&mut buf);
+        for _ in 0..100_000 { w.write_start_group(5).unwrap(); }
+        for _ in 0..100_000 { w.write_end_group(5).unwrap(); }
+    }
+    let mut r = BytesReader::from_bytes(&buf);
+    let tag = r.next_tag(&buf).unwrap();
+    match r.read_unknown(&buf, tag).unwrap_err().into_kind() {
+        ErrorKind::RecursionLimitExceeded(_) => (),
+        e => panic!("Expecting RecursionLimitExceeded, got {:?}", e),
+    }
+}
+
+#[test]
+fn read_size_limits(){
+    let mut buf = Vec::new();
+    {
+        let mut w = Writer::new(&mut buf);
+        w.write_string_with_tag(10, "abcd").unwrap();
+        w.write_bytes_with_tag(26, b"abcd").unwrap();
+    }
+
+    let limits = ReaderLimits { max_input_size: buf.len() - 1, ..ReaderLimits::default() };
+    match BytesReader::with_limits(&buf, limits).unwrap_err().into_kind() {
+        ErrorKind::InputTooLarge(12, 11) => (),
+        e => panic!("Expecting InputTooLarge, got {:?}", e),
+    }
+    let limits = ReaderLimits { max_input_size: buf.len(), ..ReaderLimits::default() };
+    assert!(BytesReader::with_limits(&buf, limits).is_ok());
+
+    let limits = ReaderLimits { max_field_len: 3, ..ReaderLimits::default() };
+    let mut r = BytesReader::with_limits(&buf, limits).unwrap();
+    assert_eq!(10, r.next_tag(&buf).unwrap());
+    let err = r.read_string(&buf).unwrap_err();
+    assert_eq!(Some(2), err.offset());
+    match err.into_kind() {
+        ErrorKind::FieldTooLarge(4, 3) => (),
+        e => panic!("Expecting FieldTooLarge, got {:?}", e),
+    }
+
+    // unknown fields are checked too
+    let mut r = BytesReader::with_limits(&buf, limits).unwrap();
+    assert_eq!(10, r.next_tag(&buf).unwrap());
+    assert!(r.read_unknown(&buf, 10).is_err());
+    let limits = ReaderLimits { max_field_len: 4, ..ReaderLimits::default() };
+    let mut r = BytesReader::with_limits(&buf, limits).unwrap();
+    while !r.is_eof() {
+        let tag = r.next_tag(&buf).unwrap();
+        r.read_unknown(&buf, tag).unwrap();
+    }
+}
+
+#[test]
+fn wr_map(){
+    let entries = vec![("one", 1i32), ("", 0), ("three hundred", 300)];
+    let mut buf = Vec::new();
+    {
+        let mut w = Writer::new(&mut buf);
+        for &(k, v) in &entries {
+            w.write_tag(3 << 3 | 2).unwrap();
+            w.write_map(1 + sizeof_var_length(k.len()) + 1 + sizeof_int32(v),
+                        10, |w| w.write_string(k),
+                        16, |w| w.write_int32(v)).unwrap();
+        }
+    }
+    let size: usize = entries.iter()
+        .map(|&(k, v)| 1 + sizeof_map_entry(1 + sizeof_var_length(k.len()), 1 + sizeof_int32(v)))
+        .sum();
+    assert_eq!(buf.len(), size);
+
+    let mut r = BytesReader::from_bytes(&buf);
+    for &(k, v) in &entries {
+        assert_eq!(3 << 3 | 2, r.next_tag(&buf).unwrap());
+        assert_eq!((k, v), r.read_map(&buf, |r, b| r.read_string(b), |r, b| r.read_int32(b)).unwrap());
+    }
+    assert!(r.is_eof());
+}
+
+#[test]
+fn read_map_defaults(){
+    // value only, key only (then an unknown field) and empty entries
+    let buf = [0x02, 0x10, 0x2a,
+               0x04, 0x08, 0x07, 0x18, 0x01,
+               0x00];
+    let mut r = BytesReader::from_bytes(&buf);
+    let read = |r: &mut BytesReader| r.read_map(&buf, |r, b| r.read_int32(b), |r, b| r.read_int32(b)).unwrap();
+    assert_eq!((0, 42), read(&mut r));
+    assert_eq!((7, 0), read(&mut r));
+    assert_eq!((0, 0), read(&mut r));
+    assert!(r.is_eof());
+}
+
+#[test]
+fn wr_grpc_frames(){
+    use quick_protobuf::grpc::{encode_frame, write_frame, decode_frame, HEADER_LEN};
+
+    let v = TestMessage {
+        id: Some(63),
+        val: vec![53, 5, 76, 743, 23, 753],
+    };
+    let buf = encode_frame(&v).unwrap();
+    assert_eq!(&buf[..HEADER_LEN], &[0, 0, 0, 0, v.get_size() as u8]);
+
+    let frame = decode_frame(&buf, 1024).unwrap().unwrap();
+    assert!(!frame.compressed);
+    assert_eq!(buf.len(), frame.len());
+    assert_eq!(v, frame.read_message().unwrap());
+
+    // 2 frames in a row
+    let mut buf = Vec::new();
+    write_frame(&mut buf, &v).unwrap();
+    write_frame(&mut buf, &TestMessage::default()).unwrap();
+    let first = decode_frame(&buf, 1024).unwrap().unwrap();
+    let second = decode_frame(&buf[first.len()..], 1024).unwrap().unwrap();
+    assert_eq!(v, first.read_message().unwrap());
+    assert_eq!(TestMessage::default(), second.read_message::<TestMessage>().unwrap());
+    assert_eq!(buf.len(), first.len() + second.len());
+}
+
+#[test]
+fn grpc_partial_frames(){
+    use quick_protobuf::grpc::{encode_frame, FrameDecoder};
+
+    let v = TestMessage {
+        id: Some(63),
+        val: vec![53, 5, 76, 743, 23, 753],
+    };
+    let mut buf = encode_frame(&v).unwrap();
+    buf.extend(encode_frame(&TestMessage::default()).unwrap());
+
+    // receive the frames one byte at a time
+    let mut decoder = FrameDecoder::new();
+    let mut messages = Vec::new();
+    for b in &buf {
+        decoder.push(&[*b]);
+        while let Some(frame) = decoder.next_frame().unwrap() {
+            messages.push(frame.read_message::<TestMessage>().unwrap());
+        }
+    }
+    assert_eq!(vec![v, TestMessage::default()], messages);
+    assert!(decoder.is_empty());
+
+    // a truncated frame is pending
+    decoder.push(&buf[..buf.len() - 1]);
+    assert!(decoder.next_frame().unwrap().is_some());
+    assert!(decoder.next_frame().unwrap().is_none());
+    assert!(!decoder.is_empty());
+}
+
+#[test]
+fn grpc_invalid_frames(){
+    use quick_protobuf::grpc::{decode_frame, FrameDecoder};
+
+    // too large, rejected as soon as the header is received
+    let mut decoder = FrameDecoder::with_max_message_size(4);
+    decoder.push(&[0, 0, 0, 0, 5]);
+    match *decoder.next_frame().unwrap_err().kind() {
+        ErrorKind::MessageTooLarge(5, 4) => (),
+        ref e => panic!("Expecting MessageTooLarge, got {:?}", e),
+    }
+
+    match *decode_frame(&[2, 0, 0, 0, 0], 4).unwrap_err().kind() {
+        ErrorKind::InvalidCompressedFlag(2) => (),
+        ref e => panic!("Expecting InvalidCompressedFlag, got {:?}", e),
+    }
+
+    let frame = decode_frame(&[1, 0, 0, 0, 1, 0], 4).unwrap().unwrap();
+    assert!(frame.compressed);
+    assert_eq!(&[0], frame.data);
+    match *frame.read_message::<TestMessage>().unwrap_err().kind() {
+        ErrorKind::CompressedMessage => (),
+        ref e => panic!("Expecting CompressedMessage, got {:?}", e),
+    }
+}
+
+#[test]
+fn raw_decode(){
+    use quick_protobuf::raw::{RawMessage, RawValue};
+
+    let mut buf = Vec::new();
+    {
+        let mut w = Writer::new(&mut buf);
+        w.write_int32_with_tag(8, -1).unwrap();
+        w.write_fixed64_with_tag(17, 2).unwrap();
+        w.write_fixed32_with_tag(29, 3).unwrap();
+        w.write_string_with_tag(34, "hi\n").unwrap();
+        w.write_bytes_with_tag(42, &[8, 150, 1]).unwrap();
+        w.write_start_group(6).unwrap();
+        w.write_bool_with_tag(8, true).unwrap();
+        w.write_end_group(6).unwrap();
+        w.write_bytes_with_tag(58, &[]).unwrap();
+    }
+
+    let msg = RawMessage::decode(&buf).unwrap();
+    let values = msg.fields.iter().map(|f| (f.number, f.wire_type)).collect::<Vec<_>>();
+    assert_eq!(vec![(1, 0), (2, 1), (3, 5), (4, 2), (5, 2), (6, 3), (7, 2)], values);
+    assert_eq!(RawValue::Varint(u64::MAX), msg.fields[0].value);
+    assert_eq!(RawValue::Bytes(b"hi\n"), msg.fields[3].value);
+    match msg.fields[4].value {
+        RawValue::Message(ref m) => assert_eq!(RawValue::Varint(150), m.fields[0].value),
+        ref v => panic!("Expecting a message, got {:?}", v),
+    }
+    assert_eq!(quick_protobuf::text::to_string(&msg),
+               "1: 18446744073709551615\n\
+                2: 0x0000000000000002\n\
+                3: 0x00000003\n\
+                4: \"hi\\n\"\n\
+                5 {\n  1: 150\n}\n\
+                6 {\n  1: 1\n}\n\
+                7: \"\"\n");
+
+    // unterminated group and invalid wire type
+    assert!(RawMessage::decode(&buf[..buf.len() - 4]).is_err());
+    assert!(RawMessage::decode(&[0x0F]).is_err());
+}